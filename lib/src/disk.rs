@@ -0,0 +1,73 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context, Result, anyhow};
+use futures::StreamExt;
+use log::trace;
+use nix::sys::statvfs::statvfs;
+use tokio::{fs, task};
+
+use crate::{StaticStream, StreamContext, util::polling::polling_interval};
+
+/// usage information about a mounted filesystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskUsage {
+    /// total size of the filesystem in bytes
+    pub total: u64,
+    /// bytes which are used on the filesystem
+    pub used: u64,
+    /// bytes which are still available to unprivileged users
+    pub available: u64,
+}
+
+impl DiskUsage {
+    /// returns the used space as a percentage (0-1)
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 { 0f64 } else { self.used as f64 / self.total as f64 }
+    }
+}
+
+/// reads the usage of the filesystem the given path is mounted on
+pub async fn read_disk_usage(path: &Path) -> Result<DiskUsage> {
+    if !fs::try_exists(path).await.unwrap_or_default() {
+        return Err(anyhow!("mount path `{}` does not exist", path.to_string_lossy()));
+    }
+
+    let owned = path.to_owned();
+    let stat = task::spawn_blocking(move || statvfs(&owned))
+        .await
+        .context("failed to join statvfs task")?
+        .with_context(|| format!("failed to statvfs `{}`", path.to_string_lossy()))?;
+
+    let size = stat.fragment_size() as u64;
+
+    Ok(DiskUsage {
+        total: stat.blocks() as u64 * size,
+        used: (stat.blocks() as u64).saturating_sub(stat.blocks_free() as u64) * size,
+        available: stat.blocks_available() as u64 * size,
+    })
+}
+
+/// creates a stream which polls the usage of the filesystem at the given path
+/// and fires if it changed
+pub fn listen_disk_usage(path: PathBuf, polling: Duration) -> StaticStream<DiskUsage> {
+    let interval = polling_interval(polling);
+
+    futures::stream::unfold((path, interval, None), async |(path, mut interval, last)| {
+        let mut next = last;
+
+        while next == last {
+            interval.tick().await;
+
+            trace!("polling disk usage for `{}`", path.to_string_lossy());
+            if let Some(usage) = read_disk_usage(&path).await.stream_log("disk usage stream") {
+                next = Some(usage);
+            };
+        }
+
+        Some((next?, (path, interval, next)))
+    })
+    .boxed()
+}
@@ -1,13 +1,16 @@
-use std::path::PathBuf;
+use std::{collections::HashSet, future, path::PathBuf, time::Duration};
 
-use anyhow::{Context, Result};
-use futures::StreamExt;
+use anyhow::{Context, Result, anyhow};
+use futures::{StreamExt, stream};
+use log::{debug, warn};
 use serde::Deserialize;
 use tokio::{
     io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
     net::UnixStream,
+    sync::mpsc,
+    time::sleep,
 };
-use tokio_stream::wrappers::LinesStream;
+use tokio_stream::wrappers::{LinesStream, ReceiverStream};
 
 use crate::{StaticStream, StreamContext, util::StreamCustomExt};
 
@@ -20,6 +23,43 @@ pub struct WorkspaceState {
     pub window_amount: u64,
     #[serde(rename = "hasfullscreen")]
     pub fullscreen: bool,
+    /// whether a window on this workspace requested attention since it was
+    /// last focused, this is tracked from events
+    #[serde(skip)]
+    pub urgent: bool,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ClientState {
+    pub address: String,
+    pub workspace: ClientWorkspace,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ClientWorkspace {
+    pub id: i64,
+}
+
+impl WorkspaceState {
+    /// creates the state of a workspace which does not exist (yet)
+    pub fn empty(id: i64) -> Self {
+        Self { id, monitor_id: None, window_amount: 0, fullscreen: false, urgent: false }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct MonitorState {
+    pub id: u64,
+    pub name: String,
+    pub description: String,
+    pub focused: bool,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)] // hyprland returns an empty object if no window is focused
+pub struct WindowState {
+    pub class: String,
+    pub title: String,
 }
 
 #[derive(Clone)]
@@ -67,6 +107,26 @@ impl HyprlandInstance {
         .context("failed to deserialize output of `workspaces` hyprctl command")
     }
 
+    /// gets the workspaces shown on a given monitor from socket 1, this ignores
+    /// special workspaces
+    pub async fn get_monitor_workspaces(&self, monitor_id: u64) -> Result<Vec<WorkspaceState>> {
+        let mut workspaces = self.get_all_workspaces().await?;
+        workspaces.retain(|state| state.monitor_id == Some(monitor_id) && state.id >= 0);
+
+        Ok(workspaces)
+    }
+
+    /// gets the state of all monitors from socket 1
+    pub async fn get_all_monitors(&self) -> Result<Vec<MonitorState>> {
+        serde_json::from_str(
+            &self
+                .dispatch_command("monitors")
+                .await
+                .context("failed to run `monitors` hyprctl command")?,
+        )
+        .context("failed to deserialize output of `monitors` hyprctl command")
+    }
+
     /// gets the state of the active workspace from socket 1
     pub async fn get_active_workspace(&self) -> Result<WorkspaceState> {
         serde_json::from_str(
@@ -78,19 +138,66 @@ impl HyprlandInstance {
         .context("failed to deserialize output of `activeworkspace` hyprctl command")
     }
 
+    /// gets the state of all windows from socket 1
+    pub async fn get_all_clients(&self) -> Result<Vec<ClientState>> {
+        serde_json::from_str(
+            &self
+                .dispatch_command("clients")
+                .await
+                .context("failed to run `clients` hyprctl command")?,
+        )
+        .context("failed to deserialize output of `clients` hyprctl command")
+    }
+
+    /// gets the id of the workspace the window with the given address (as
+    /// reported by events, without `0x`) is on
+    async fn get_client_workspace(&self, address: &str) -> Result<Option<i64>> {
+        Ok(self
+            .get_all_clients()
+            .await?
+            .into_iter()
+            .find(|client| client.address.trim_start_matches("0x") == address)
+            .map(|client| client.workspace.id))
+    }
+
+    /// gets the state of the active window from socket 1
+    pub async fn get_active_window(&self) -> Result<WindowState> {
+        serde_json::from_str(
+            &self
+                .dispatch_command("activewindow")
+                .await
+                .context("failed to run `activewindow` hyprctl command")?,
+        )
+        .context("failed to deserialize output of `activewindow` hyprctl command")
+    }
+
+    /// runs an arbitrary dispatcher with its arguments (like `togglefloating`
+    /// or `workspace 2`) and returns the reply of hyprland, which is `ok` if it
+    /// succeeded
+    pub async fn run_dispatch(&self, dispatcher: &str) -> Result<String> {
+        let dispatcher = dispatcher.trim();
+
+        // control characters could smuggle in further commands or corrupt the socket
+        if dispatcher.is_empty() || dispatcher.contains(char::is_control) {
+            return Err(anyhow!("dispatcher is empty or contains control characters"));
+        }
+
+        self.dispatch_command(&format!("dispatch {dispatcher}"))
+            .await
+            .map(|reply| reply.trim().to_string())
+            .with_context(|| format!("failed to run dispatcher `{dispatcher}`"))
+    }
+
     /// runs a dispatcher to select the workspace with the given id
     pub async fn run_select_workspace(&self, id: i64) -> Result<()> {
-        self.dispatch_command(&format!("dispatch workspace {id}")).await.map(|_| ())
+        self.run_dispatch(&format!("workspace {id}")).await.map(|_| ())
     }
 
     /// runs a dispatcher to select a workspace relatively given an offset
     pub async fn run_select_workspace_relative(&self, offset: i64) -> Result<()> {
-        self.dispatch_command(&format!(
-            "dispatch workspace m{}{offset}",
-            if offset > 0 { "+" } else { "" }
-        ))
-        .await
-        .map(|_| ())
+        self.run_dispatch(&format!("workspace m{}{offset}", if offset > 0 { "+" } else { "" }))
+            .await
+            .map(|_| ())
     }
 
     /// listens to socket 2 for all hyprland events and returns them as a stream
@@ -112,9 +219,65 @@ impl HyprlandInstance {
             .boxed())
     }
 
+    /// repeatedly creates a stream on socket 2 with `listen` and forwards its
+    /// items. if the socket is closed, we try to reconnect with a backoff
+    fn listen_reconnecting<T, F, Fut>(self, name: &'static str, listen: F) -> StaticStream<T>
+    where
+        T: Send + 'static,
+        F: Fn(Self) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<StaticStream<T>>> + Send,
+    {
+        const BACKOFF_MIN: Duration = Duration::from_millis(500);
+        const BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+        let (tx, rx) = mpsc::channel(1);
+
+        tokio::spawn(async move {
+            let mut backoff = BACKOFF_MIN;
+
+            loop {
+                match listen(self.clone()).await {
+                    Ok(mut stream) => {
+                        backoff = BACKOFF_MIN;
+
+                        while let Some(item) = stream.next().await {
+                            if tx.send(item).await.is_err() {
+                                debug!("{name} stream was dropped");
+                                return;
+                            }
+                        }
+
+                        warn!("hl's socket 2 was closed for {name}, trying to reconnect");
+                    }
+                    Err(e) => warn!("failed to listen to {name}: {e:#}"),
+                }
+
+                if tx.is_closed() {
+                    debug!("{name} stream was dropped");
+                    return;
+                }
+
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(BACKOFF_MAX);
+            }
+        });
+
+        ReceiverStream::new(rx).boxed()
+    }
+
     /// listens to socket 2 and creates a stream that fires each time with the
-    /// current workspace data
-    pub async fn listen_workspaces(
+    /// current workspace data. if the socket is closed, we try to reconnect
+    /// with a backoff and report the full state again once reconnected
+    pub fn listen_workspaces(self, monitor_id: u64) -> StaticStream<(i64, Vec<WorkspaceState>)> {
+        self.listen_reconnecting("hl workspaces", move |this| {
+            this.listen_workspaces_once(monitor_id)
+        })
+    }
+
+    /// connects to socket 2 once and creates a stream which first fires with
+    /// the current workspace data and then each time it changes. workspaces
+    /// stay urgent until they are focused
+    async fn listen_workspaces_once(
         self,
         monitor_id: u64,
     ) -> Result<StaticStream<(i64, Vec<WorkspaceState>)>> {
@@ -131,45 +294,158 @@ impl HyprlandInstance {
             "monitoradded",
         ];
 
-        let mut workspaces = self.get_all_workspaces().await?;
-        workspaces.retain(|state| state.monitor_id == Some(monitor_id) && state.id >= 0);
+        let events = self.clone().listen_events().await?;
 
+        let workspaces = self.get_monitor_workspaces(monitor_id).await?;
         let active = self.get_active_workspace().await?;
 
+        let initial = (active.id, workspaces.clone());
         let params = (self.clone(), monitor_id);
 
-        Ok(self
-            .listen_events()
-            .await?
-            .scan_owning(
-                (active.id, workspaces, params),
-                async |(mut selected, mut state, params), (event, args)| {
-                    match event.as_str() {
-                        "workspacev2" => {
-                            let next = args.first().and_then(|id| id.parse::<i64>().ok())?;
-
-                            if state.iter().any(|ws| next == ws.id) {
-                                selected = next;
+        Ok(stream::once(future::ready(initial))
+            .chain(
+                events
+                    .scan_owning(
+                        (active.id, workspaces, HashSet::new(), params),
+                        async |(mut selected, mut state, mut urgent, params), (event, args)| {
+                            match event.as_str() {
+                                "workspacev2" => {
+                                    let next =
+                                        args.first().and_then(|id| id.parse::<i64>().ok())?;
+
+                                    if state.iter().any(|ws| next == ws.id) {
+                                        selected = next;
+                                    }
+
+                                    urgent.remove(&next);
+                                }
+                                "urgent" => {
+                                    let address = args.first()?;
+                                    let workspace = params
+                                        .0
+                                        .get_client_workspace(address)
+                                        .await
+                                        .stream_log("hl workspaces")?;
+
+                                    // the focused workspace does not need any attention
+                                    if let Some(id) = workspace
+                                        && id != selected
+                                    {
+                                        urgent.insert(id);
+                                    }
+                                }
+                                event if REFETCH_EVENTS.contains(&event) => {
+                                    state = params
+                                        .0
+                                        .get_monitor_workspaces(params.1)
+                                        .await
+                                        .stream_log("hl workspaces")?;
+                                }
+
+                                // this event does not tell us anything, we don't do anything
+                                _ => return Some(((selected, state, urgent, params), None)),
+                            };
+
+                            for ws in &mut state {
+                                ws.urgent = urgent.contains(&ws.id);
                             }
-                        }
-                        event if REFETCH_EVENTS.contains(&event) => {
-                            state =
-                                params.0.get_all_workspaces().await.stream_log("hl workspaces")?;
-
-                            // remove workspaces on other monitors and ignore special ones
-                            state.retain(|state| {
-                                state.monitor_id == Some(params.1) && state.id >= 0
-                            });
-                        }
 
-                        // this event does not tell us anything, we don't do anything
-                        _ => return Some(((selected, state, params), None)),
-                    };
+                            Some((
+                                (selected, state.clone(), urgent, params),
+                                Some((selected, state)),
+                            ))
+                        },
+                    )
+                    .filter_map(async |s| s),
+            )
+            .boxed())
+    }
+
+    /// listens to socket 2 and creates a stream that fires with the title of
+    /// the active window each time it changes, `None` if no window is focused
+    pub fn listen_active_window(self) -> StaticStream<Option<String>> {
+        self.listen_reconnecting("hl active window", Self::listen_active_window_once)
+    }
+
+    /// connects to socket 2 once and creates a stream which first fires with
+    /// the current active window title and then each time it changes
+    async fn listen_active_window_once(self) -> Result<StaticStream<Option<String>>> {
+        fn non_empty(title: String) -> Option<String> {
+            if title.is_empty() { None } else { Some(title) }
+        }
+
+        let events = self.clone().listen_events().await?;
+        let active = self.get_active_window().await?;
+
+        Ok(stream::once(future::ready(non_empty(active.title)))
+            .chain(events.filter_map(async |(event, args)| {
+                if event != "activewindow" {
+                    return None;
+                }
+
+                // the title itself may contain commas
+                Some(non_empty(args.get(1..).map(|title| title.join(",")).unwrap_or_default()))
+            }))
+            .boxed())
+    }
+
+    /// listens to socket 2 and creates a stream that fires with all monitors
+    /// each time one is added or removed
+    pub fn listen_monitors(self) -> StaticStream<Vec<MonitorState>> {
+        self.listen_reconnecting("hl monitors", Self::listen_monitors_once)
+    }
+
+    /// connects to socket 2 once and creates a stream which first fires with the
+    /// current monitors and then each time they change
+    async fn listen_monitors_once(self) -> Result<StaticStream<Vec<MonitorState>>> {
+        let events = self.clone().listen_events().await?;
+        let monitors = self.get_all_monitors().await?;
 
-                    Some(((selected, state.clone(), params), Some((selected, state))))
-                },
+        Ok(stream::once(future::ready(monitors))
+            .chain(
+                events
+                    .filter(|(event, _)| {
+                        future::ready(event == "monitoradded" || event == "monitorremoved")
+                    })
+                    .filter_map(move |_| {
+                        let this = self.clone();
+                        async move { this.get_all_monitors().await.stream_log("hl monitors") }
+                    }),
             )
-            .filter_map(async |s| s)
+            .boxed())
+    }
+
+    /// listens to socket 2 and creates a stream that fires with the name of the
+    /// current submap each time it changes, empty for the default one
+    pub fn listen_submap(self) -> StaticStream<String> {
+        self.listen_reconnecting("hl submap", Self::listen_submap_once)
+    }
+
+    /// connects to socket 2 once and creates a stream which fires each time the
+    /// submap changes
+    async fn listen_submap_once(self) -> Result<StaticStream<String>> {
+        // we cannot query the submap, but we are usually in the default one when connecting
+        Ok(stream::once(future::ready(String::new()))
+            .chain(self.listen_events().await?.filter_map(async |(event, args)| {
+                if event == "submap" { Some(args.join(",")) } else { None }
+            }))
+            .boxed())
+    }
+
+    /// listens to socket 2 and creates a stream that fires each time a
+    /// screencast (e.g. through the desktop portal) starts or stops
+    pub fn listen_screencast(self) -> StaticStream<bool> {
+        self.listen_reconnecting("hl screencast", Self::listen_screencast_once)
+    }
+
+    /// connects to socket 2 once and creates a stream which fires each time a
+    /// screencast starts or stops
+    async fn listen_screencast_once(self) -> Result<StaticStream<bool>> {
+        // we cannot query screencasts, so we assume there is none when connecting
+        Ok(stream::once(future::ready(false))
+            .chain(self.listen_events().await?.filter_map(async |(event, args)| {
+                if event == "screencast" { Some(args.first()? == "1") } else { None }
+            }))
             .boxed())
     }
 }
@@ -0,0 +1,116 @@
+use std::{env, path::PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use nix::unistd::getuid;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::UnixStream,
+};
+
+/// largest frame which is accepted over the socket
+pub const MAX_FRAME_SIZE: usize = 4 * 1024 * 1024;
+
+/// path where the unix socket of a running bar is located
+pub fn socket_path() -> PathBuf {
+    if let Ok(path) = env::var("LIISCHTE_SOCKET") {
+        PathBuf::from(path)
+    } else if let Ok(runtime) = env::var("XDG_RUNTIME_DIR") {
+        PathBuf::from(runtime).join("liischte.sock")
+    } else {
+        // /tmp is shared between users, so every user gets their own socket
+        PathBuf::from(format!("/tmp/liischte-{}.sock", getuid()))
+    }
+}
+
+/// layer of the wlr-layer-shell a surface of the bar is shown on
+#[derive(Clone, Copy, Deserialize, Serialize, Debug)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowLayer {
+    Overlay,
+    Top,
+    Bottom,
+    Background,
+}
+
+/// message passed over ipc
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum IpcMessage {
+    /// passes a message to a module, its response is written back to the client
+    ModuleUpdate(String, String),
+    /// changes the layer of the bar, none for the configured one
+    LayerChange(Option<WindowLayer>),
+    /// queries the state of a module, which is written back to the client
+    Query(String),
+    /// queries the state of a module in waybar's json format, which is
+    /// written back to the client
+    QueryWaybar(String),
+    /// re-reads the config and rebuilds all modules
+    Reload,
+    /// shows the osd of a module right away
+    ShowOsd(String),
+    /// runs a hyprland dispatcher, whose reply is written back to the client
+    Dispatch(String),
+    /// changes which modules are shown and in what order
+    SetModules(Vec<String>),
+}
+
+impl IpcMessage {
+    /// returns whether the bar writes a response back for this message
+    pub fn expects_response(&self) -> bool {
+        matches!(
+            self,
+            Self::Query(_) | Self::QueryWaybar(_) | Self::ModuleUpdate(..) | Self::Dispatch(_)
+        )
+    }
+}
+
+/// writes a frame to the socket, which is the length of the data as a big
+/// endian u32 followed by the data itself
+pub async fn write_frame(stream: &mut (impl AsyncWrite + Unpin), data: &[u8]) -> Result<()> {
+    if data.len() > MAX_FRAME_SIZE {
+        return Err(anyhow!("frame of {} bytes exceeds the maximum size", data.len()));
+    }
+
+    stream.write_u32(data.len() as u32).await.context("failed to write frame length")?;
+    stream.write_all(data).await.context("failed to write frame")
+}
+
+/// reads a whole frame written by `write_frame` from the socket
+pub async fn read_frame(stream: &mut (impl AsyncRead + Unpin)) -> Result<Vec<u8>> {
+    let len = stream.read_u32().await.context("failed to read frame length")? as usize;
+    if len > MAX_FRAME_SIZE {
+        return Err(anyhow!("frame of {len} bytes exceeds the maximum size"));
+    }
+
+    let mut buf = vec![0u8; len];
+    stream.read_exact(&mut buf).await.context("failed to read frame")?;
+
+    Ok(buf)
+}
+
+/// sends a message to the running bar
+pub async fn send(msg: IpcMessage) -> Result<()> {
+    let mut stream =
+        UnixStream::connect(socket_path()).await.context("failed to connect to ipc socket")?;
+
+    write_frame(&mut stream, &serde_json::to_vec(&msg).context("failed to serialize message")?)
+        .await
+        .context("failed to write to ipc socket")
+}
+
+/// sends a message to the running bar and waits for its response
+pub async fn query(msg: IpcMessage) -> Result<Value> {
+    let mut stream =
+        UnixStream::connect(socket_path()).await.context("failed to connect to ipc socket")?;
+
+    write_frame(&mut stream, &serde_json::to_vec(&msg).context("failed to serialize message")?)
+        .await
+        .context("failed to write to ipc socket")?;
+
+    let buf = read_frame(&mut stream).await.context("failed to read from ipc socket")?;
+
+    serde_json::from_slice(&buf).context("failed to deserialize response")
+}
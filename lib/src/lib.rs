@@ -21,7 +21,7 @@ pub mod networkmanager;
 pub mod pipewire;
 
 /// implementations using the sysfs
-#[cfg(any(feature = "power", feature = "backlight"))]
+#[cfg(any(feature = "power", feature = "backlight", feature = "thermal", feature = "cpufreq"))]
 pub mod sysfs;
 
 /// implementation of running processes information using the procfs
@@ -32,8 +32,29 @@ pub mod process;
 #[cfg(feature = "mako")]
 pub mod mako;
 
+/// implementation of media player information and controls using the mpris
+/// dbus interface
+#[cfg(feature = "mpris")]
+pub mod mpris;
+
+/// implementation of disk usage information using statvfs
+#[cfg(feature = "disk")]
+pub mod disk;
+
+/// implementation of current weather information using http apis like
+/// open-meteo or wttr.in
+#[cfg(feature = "weather")]
+pub mod weather;
+
+/// implementation of a client for the ipc of a running bar, so other programs
+/// can control it
+#[cfg(feature = "ipc")]
+pub mod ipc;
+
 mod util;
 
+pub use util::polling::set_polling_jitter;
+
 /// a boxed stream with a static lifetime
 pub type StaticStream<T> = BoxStream<'static, T>;
 
@@ -1,18 +1,118 @@
-use std::future;
+use std::{collections::HashMap, future};
 
-use futures::{FutureExt, StreamExt, stream::BoxStream};
+use futures::{
+    FutureExt, StreamExt,
+    stream::{self, BoxStream},
+};
 use log::debug;
 use modemmanager::dbus::modem::ModemProxy;
 use rusty_network_manager::DeviceProxy;
 use tokio::{select, sync::mpsc};
 use tokio_stream::wrappers::ReceiverStream;
-use zbus::{Connection, proxy::Builder, zvariant::OwnedObjectPath};
+use zbus::{
+    Connection, proxy,
+    proxy::Builder,
+    zvariant::{OwnedObjectPath, OwnedValue},
+};
 
 use crate::{
     StaticStream, StreamContext,
     networkmanager::{NetworkManager, describe_path},
 };
 
+/// short names of access technologies, with the bits of the technologies they
+/// stand for, ordered from fastest to slowest
+/// see https://www.freedesktop.org/software/ModemManager/api/latest/ModemManager-Flags-and-Enumerations.html#MMModemAccessTechnology
+const ACCESS_TECHNOLOGIES: &[(u32, &str)] = &[
+    (1 << 15, "5G"),
+    (1 << 14 | 1 << 16 | 1 << 17, "LTE"),
+    (1 << 9, "H+"),
+    (1 << 6 | 1 << 7 | 1 << 8, "H"),
+    (1 << 5 | 1 << 11 | 1 << 12 | 1 << 13, "3G"),
+    (1 << 4, "E"),
+    (1 << 3 | 1 << 10, "G"),
+    (1 << 1 | 1 << 2, "2G"),
+];
+
+#[proxy(
+    interface = "org.freedesktop.ModemManager1.Modem.Modem3gpp",
+    default_service = "org.freedesktop.ModemManager1"
+)]
+trait Modem3gpp {
+    /// name of the operator the modem is registered with
+    #[zbus(property)]
+    fn operator_name(&self) -> zbus::Result<String>;
+}
+
+/// rate in seconds at which ModemManager polls the signal of a modem
+const SIGNAL_RATE: u32 = 5;
+
+#[proxy(
+    interface = "org.freedesktop.ModemManager1.Modem.Signal",
+    default_service = "org.freedesktop.ModemManager1"
+)]
+trait ModemSignal {
+    /// enables polling of the signal values at the given rate in seconds
+    fn setup(&self, rate: u32) -> zbus::Result<()>;
+
+    /// signal values of the different access technologies, which are empty if
+    /// not in use
+    #[zbus(property)]
+    fn nr5g(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+    #[zbus(property)]
+    fn lte(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+    #[zbus(property)]
+    fn umts(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+    #[zbus(property)]
+    fn gsm(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+}
+
+impl ModemSignalProxy<'_> {
+    /// reads the received power in dBm of the technology in use, preferring
+    /// the reference signal power over the total one
+    async fn read_dbm(&self) -> Option<f64> {
+        let technologies: [(_, &[&str]); 4] = [
+            (self.nr5g().await, &["rsrp"]),
+            (self.lte().await, &["rsrp", "rssi"]),
+            (self.umts().await, &["rscp", "rssi"]),
+            (self.gsm().await, &["rssi"]),
+        ];
+
+        technologies.into_iter().find_map(|(values, keys)| {
+            let values = values.ok()?;
+
+            keys.iter().find_map(|key| f64::try_from(values.get(*key)?.try_clone().ok()?).ok())
+        })
+    }
+}
+
+/// information about the network a modem is registered in
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CellularInfo {
+    /// name of the operator, empty if unknown
+    pub operator: String,
+    /// short name of the fastest access technology in use (like `5G` or `LTE`)
+    pub technology: Option<&'static str>,
+}
+
+/// converts an access technology bitmask to the short name of the fastest one
+fn technology_name(technologies: u32) -> Option<&'static str> {
+    ACCESS_TECHNOLOGIES.iter().find(|(bits, _)| technologies & bits != 0).map(|(_, name)| *name)
+}
+
+/// creates a builder for the proxy of the modem at the given path
+fn build_modem_proxy<'a>(
+    modem: String,
+    connection: &'a Connection,
+) -> Result<Builder<'a, ModemProxy<'a>>, zbus::Error> {
+    // for some reason, the ModemProxy doesn't have `new_from_path`, which means we
+    // have to bind to the interface in a more manual fashion
+    ModemProxy::builder(connection)
+        .path(modem)?
+        .interface("org.freedesktop.ModemManager1.Modem")?
+        .destination("org.freedesktop.ModemManager1")
+}
+
 impl NetworkManager {
     /// listen to the cellular signal strength on a given device. note that the
     /// device passed here must be a cellular device, otherwise the stream won't
@@ -46,19 +146,7 @@ impl NetworkManager {
 
                 debug!("tracking modem {} for signal strength", describe_path(&modem));
 
-                fn build_proxy<'a>(
-                    modem: String,
-                    connection: &'a Connection,
-                ) -> Result<Builder<'a, ModemProxy<'a>>, zbus::Error> {
-                    // for some reason, the ModemProxy doesn't have `new_from_path`, which means we
-                    // have to bind to the interface in a more manual fashion
-                    ModemProxy::builder(connection)
-                        .path(modem)?
-                        .interface("org.freedesktop.ModemManager1.Modem")?
-                        .destination("org.freedesktop.ModemManager1")
-                }
-
-                let proxy = build_proxy(modem, connection)
+                let proxy = build_modem_proxy(modem, connection)
                     .stream_context(STREAM, "failed to create modem proxy")?
                     .build()
                     .await
@@ -141,4 +229,233 @@ impl NetworkManager {
 
         ReceiverStream::new(rx).boxed()
     }
+
+    /// listen to the operator and access technology of the modem of a given
+    /// device. just like `listen_cellular_strength`, the device must be a
+    /// cellular device and ModemManager must be running for this to produce
+    /// anything
+    pub fn listen_cellular_info(self, device: OwnedObjectPath) -> StaticStream<CellularInfo> {
+        const STREAM: &str = "mm cellular info";
+
+        let (tx, rx) = mpsc::channel(1);
+
+        tokio::spawn(async move {
+            let Some(proxy) = DeviceProxy::new_from_path(device, &self.connection)
+                .await
+                .stream_context(STREAM, "failed to bind to device")
+            else {
+                return;
+            };
+
+            async fn track_modem<'a>(
+                modem: String,
+                connection: &'a Connection,
+            ) -> Option<(ModemProxy<'a>, Modem3gppProxy<'a>, BoxStream<'a, ()>)> {
+                // we don't want to try bind empty objects
+                if modem.is_empty() || modem == "/" {
+                    return None;
+                }
+
+                debug!("tracking modem {} for network info", describe_path(&modem));
+
+                let modem_proxy = build_modem_proxy(modem.clone(), connection)
+                    .stream_context(STREAM, "failed to create modem proxy")?
+                    .build()
+                    .await
+                    .stream_context(STREAM, "failed to bind to modem")?;
+
+                let gpp_proxy = Modem3gppProxy::builder(connection)
+                    .path(modem)
+                    .stream_context(STREAM, "failed to create 3gpp proxy")?
+                    .build()
+                    .await
+                    .stream_context(STREAM, "failed to bind to 3gpp interface of modem")?;
+
+                // both values are read again on any change
+                let stream = stream::select(
+                    modem_proxy.receive_access_technologies_changed().await.map(|_| ()),
+                    gpp_proxy.receive_operator_name_changed().await.map(|_| ()),
+                )
+                .boxed();
+
+                Some((modem_proxy, gpp_proxy, stream))
+            }
+
+            async fn read_info(modem: &ModemProxy<'_>, gpp: &Modem3gppProxy<'_>) -> CellularInfo {
+                CellularInfo {
+                    operator: gpp
+                        .operator_name()
+                        .await
+                        .stream_context(STREAM, "failed to read operator name")
+                        .unwrap_or_default(),
+                    technology: modem
+                        .access_technologies()
+                        .await
+                        .stream_context(STREAM, "failed to read access technologies")
+                        .and_then(technology_name),
+                }
+            }
+
+            let mut modem = if let Some(string) =
+                proxy.udi().await.stream_context(STREAM, "failed to read active modem")
+            {
+                track_modem(string, &self.connection).await
+            } else {
+                None
+            };
+
+            let mut changed_stream = proxy
+                .receive_udi_changed()
+                .await
+                .filter_map(async |change| {
+                    change.get().await.stream_context(STREAM, "failed to read changed active modem")
+                })
+                .boxed();
+
+            let mut read = true;
+
+            loop {
+                if read {
+                    read = false;
+
+                    if let Some((modem, gpp, _)) = modem.as_ref()
+                        && tx.send(read_info(modem, gpp).await).await.is_err()
+                    {
+                        debug!("cellular info stream was dropped");
+                        return;
+                    }
+                }
+
+                let change = modem
+                    .as_mut()
+                    .map(|(_, _, stream)| stream.next().boxed())
+                    .unwrap_or_else(|| future::pending().boxed());
+
+                select! {
+                    biased;
+                    next_modem = changed_stream.next() => {
+                        let Some(next_modem) = next_modem else { continue };
+
+                        modem = track_modem(next_modem, &self.connection).await;
+                        read = true; // update the stream with the new value
+                    }
+                    changed = change => {
+                        if changed.is_some() {
+                            read = true;
+                        }
+                    }
+                }
+            }
+        });
+
+        ReceiverStream::new(rx).boxed()
+    }
+
+    /// listen to the received signal power in dBm on a given device. just like
+    /// `listen_cellular_strength`, the device must be a cellular device and
+    /// ModemManager must be running for this to produce anything
+    pub fn listen_cellular_dbm(self, device: OwnedObjectPath) -> StaticStream<f64> {
+        const STREAM: &str = "mm cellular dbm";
+
+        let (tx, rx) = mpsc::channel(1);
+
+        tokio::spawn(async move {
+            let Some(proxy) = DeviceProxy::new_from_path(device, &self.connection)
+                .await
+                .stream_context(STREAM, "failed to bind to device")
+            else {
+                return;
+            };
+
+            async fn track_modem<'a>(
+                modem: String,
+                connection: &'a Connection,
+            ) -> Option<(ModemSignalProxy<'a>, BoxStream<'a, ()>)> {
+                // we don't want to try bind empty objects
+                if modem.is_empty() || modem == "/" {
+                    return None;
+                }
+
+                debug!("tracking modem {} for signal power", describe_path(&modem));
+
+                let proxy = ModemSignalProxy::builder(connection)
+                    .path(modem)
+                    .stream_context(STREAM, "failed to create signal proxy")?
+                    .build()
+                    .await
+                    .stream_context(STREAM, "failed to bind to signal interface of modem")?;
+
+                // the values are only refreshed after polling was set up
+                proxy
+                    .setup(SIGNAL_RATE)
+                    .await
+                    .stream_context(STREAM, "failed to set up signal polling")?;
+
+                // all values are read again on any change
+                let stream = stream::select_all([
+                    proxy.receive_nr5g_changed().await.map(|_| ()).boxed(),
+                    proxy.receive_lte_changed().await.map(|_| ()).boxed(),
+                    proxy.receive_umts_changed().await.map(|_| ()).boxed(),
+                    proxy.receive_gsm_changed().await.map(|_| ()).boxed(),
+                ])
+                .boxed();
+
+                Some((proxy, stream))
+            }
+
+            let mut modem = if let Some(string) =
+                proxy.udi().await.stream_context(STREAM, "failed to read active modem")
+            {
+                track_modem(string, &self.connection).await
+            } else {
+                None
+            };
+
+            let mut changed_stream = proxy
+                .receive_udi_changed()
+                .await
+                .filter_map(async |change| {
+                    change.get().await.stream_context(STREAM, "failed to read changed active modem")
+                })
+                .boxed();
+
+            let mut read = true;
+
+            loop {
+                if read {
+                    read = false;
+
+                    if let Some((proxy, _)) = modem.as_ref()
+                        && let Some(dbm) = proxy.read_dbm().await
+                        && tx.send(dbm).await.is_err()
+                    {
+                        debug!("cellular dbm stream was dropped");
+                        return;
+                    }
+                }
+
+                let change = modem
+                    .as_mut()
+                    .map(|(_, stream)| stream.next().boxed())
+                    .unwrap_or_else(|| future::pending().boxed());
+
+                select! {
+                    biased;
+                    next_modem = changed_stream.next() => {
+                        let Some(next_modem) = next_modem else { continue };
+
+                        modem = track_modem(next_modem, &self.connection).await;
+                        read = true; // update the stream with the new value
+                    }
+                    changed = change => {
+                        if changed.is_some() {
+                            read = true;
+                        }
+                    }
+                }
+            }
+        });
+
+        ReceiverStream::new(rx).boxed()
+    }
 }
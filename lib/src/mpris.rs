@@ -0,0 +1,290 @@
+use std::{collections::HashMap, future};
+
+use anyhow::{Context, Result};
+use futures::{
+    FutureExt, StreamExt,
+    stream::{self, BoxStream},
+};
+use log::{debug, trace};
+use tokio::{select, sync::mpsc, time::Instant};
+use tokio_stream::wrappers::ReceiverStream;
+use zbus::{Connection, fdo::DBusProxy, proxy, zvariant::OwnedValue};
+
+use crate::{StaticStream, StreamContext, util::StreamCustomExt};
+
+/// prefix of the bus names every mpris player has
+const PLAYER_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+#[proxy(interface = "org.mpris.MediaPlayer2.Player", default_path = "/org/mpris/MediaPlayer2")]
+pub trait PlayerInterface {
+    /// toggles between playing and paused
+    fn play_pause(&self) -> zbus::Result<()>;
+
+    /// skips to the next track
+    fn next(&self) -> zbus::Result<()>;
+
+    /// skips to the previous track
+    fn previous(&self) -> zbus::Result<()>;
+
+    /// property holding the current playback status
+    #[zbus(property)]
+    fn playback_status(&self) -> zbus::Result<String>;
+
+    /// property holding the metadata of the current track
+    #[zbus(property)]
+    fn metadata(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+}
+
+/// status of a player
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PlaybackStatus {
+    /// a track is currently playing
+    Playing,
+    /// a track is paused
+    Paused,
+    /// there is no track playing
+    Stopped,
+}
+
+impl PlaybackStatus {
+    fn parse(string: &str) -> Self {
+        match string {
+            "Playing" => Self::Playing,
+            "Paused" => Self::Paused,
+            _ => Self::Stopped,
+        }
+    }
+}
+
+/// playback state of a single player
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PlaybackState {
+    /// bus name of the player, used to control it
+    pub player: String,
+    /// title of the current track
+    pub title: String,
+    /// artists of the current track, comma separated
+    pub artist: String,
+    /// status of the player
+    pub status: PlaybackStatus,
+}
+
+impl PlaybackState {
+    fn update_metadata(&mut self, metadata: HashMap<String, OwnedValue>) {
+        self.title = metadata
+            .get("xesam:title")
+            .and_then(|value| String::try_from(value.try_clone().ok()?).ok())
+            .unwrap_or_default();
+
+        self.artist = metadata
+            .get("xesam:artist")
+            .and_then(|value| Vec::<String>::try_from(value.try_clone().ok()?).ok())
+            .map(|artists| artists.join(", "))
+            .unwrap_or_default();
+    }
+}
+
+#[derive(Clone)] // everything in here's reference counted anyways
+pub struct Mpris {
+    connection: Connection,
+}
+
+impl Mpris {
+    /// connects to the session bus to talk to mpris players
+    pub async fn connect() -> Result<Self> {
+        debug!("trying to connect to dbus session bus for mpris");
+
+        let connection =
+            Connection::session().await.context("failed to connect to dbus session bus")?;
+
+        Ok(Self { connection })
+    }
+
+    /// listen to the playback state of the most recently active player, `None`
+    /// if there is no player
+    pub fn listen_playback(self) -> StaticStream<Option<PlaybackState>> {
+        const STREAM: &str = "mpris playback";
+
+        let (tx, rx) = mpsc::channel(1);
+
+        tokio::spawn(async move {
+            let Some(dbus) = DBusProxy::new(&self.connection)
+                .await
+                .stream_context(STREAM, "failed to bind to dbus interface")
+            else {
+                return;
+            };
+
+            let Some(mut name_stream) = dbus
+                .receive_name_owner_changed()
+                .await
+                .stream_context(STREAM, "failed to listen to name changes")
+            else {
+                return;
+            };
+
+            let mut trackers = HashMap::new();
+            let mut states = HashMap::new();
+
+            let names = dbus
+                .list_names()
+                .await
+                .stream_context(STREAM, "failed to list bus names")
+                .unwrap_or_default();
+
+            for name in names.iter().filter(|name| name.starts_with(PLAYER_PREFIX)) {
+                if let Some((tracker, state)) = TrackedPlayer::track(name, &self.connection)
+                    .await
+                    .stream_context(STREAM, "failed to track initial player")
+                {
+                    trackers.insert(tracker.name.clone(), tracker);
+                    states.insert(state.player.clone(), (Instant::now(), state));
+                }
+            }
+
+            let mut sent = None;
+
+            loop {
+                // playing players are preferred, then the one that changed last
+                let current = states
+                    .values()
+                    .max_by_key(|(changed, state)| {
+                        (state.status == PlaybackStatus::Playing, *changed)
+                    })
+                    .map(|(_, state)| state.clone());
+
+                // other players or unrelated names may have changed
+                if sent.as_ref() != Some(&current) {
+                    if tx.send(current.clone()).await.is_err() {
+                        debug!("mpris playback stream was dropped");
+                        return;
+                    }
+
+                    sent = Some(current);
+                }
+
+                let idle = trackers.is_empty();
+                let mut streams =
+                    stream::select_all(trackers.values_mut().map(|a| &mut a.stream)).boxed();
+
+                // selecting from no players ends immediately, so we only wait for new ones
+                let update = if idle { future::pending().boxed() } else { streams.next().boxed() };
+
+                select! {
+                    biased;
+                    change = name_stream.next() => {
+                        let Some(change) = change else { continue };
+                        let Some(args) = change.args().stream_context(STREAM, "failed to read name change") else { continue };
+
+                        let name = args.name().to_string();
+                        if !name.starts_with(PLAYER_PREFIX) { continue; }
+
+                        drop(streams); // we want to modify trackers
+
+                        trackers.remove(&name);
+                        states.remove(&name);
+
+                        // the player was removed if it does not have a new owner
+                        if args.new_owner().is_none() { continue; }
+
+                        if let Some((tracker, state)) = TrackedPlayer::track(&name, &self.connection)
+                            .await
+                            .stream_context(STREAM, "failed to track new player")
+                        {
+                            trackers.insert(tracker.name.clone(), tracker);
+                            states.insert(state.player.clone(), (Instant::now(), state));
+                        }
+                    }
+                    Some(state) = update => {
+                        states.insert(state.player.clone(), (Instant::now(), state));
+                    }
+                }
+            }
+        });
+
+        ReceiverStream::new(rx).boxed()
+    }
+
+    /// toggles playback of a given player
+    pub async fn play_pause(&self, player: &str) -> Result<()> {
+        self.player(player).await?.play_pause().await.context("failed to toggle playback")
+    }
+
+    /// skips to the next track on a given player
+    pub async fn next(&self, player: &str) -> Result<()> {
+        self.player(player).await?.next().await.context("failed to skip to next track")
+    }
+
+    /// skips to the previous track on a given player
+    pub async fn previous(&self, player: &str) -> Result<()> {
+        self.player(player).await?.previous().await.context("failed to skip to previous track")
+    }
+
+    /// binds to the player interface of a given player
+    async fn player(&self, player: &str) -> Result<PlayerInterfaceProxy<'_>> {
+        PlayerInterfaceProxy::builder(&self.connection)
+            .destination(player.to_owned())?
+            .build()
+            .await
+            .with_context(|| format!("failed to bind to player `{player}`"))
+    }
+}
+
+struct TrackedPlayer<'a> {
+    name: String,
+    _proxy: PlayerInterfaceProxy<'a>,
+    stream: BoxStream<'a, PlaybackState>,
+}
+
+impl<'a> TrackedPlayer<'a> {
+    async fn track(name: &str, connection: &'a Connection) -> Result<(Self, PlaybackState)> {
+        let proxy = PlayerInterfaceProxy::builder(connection)
+            .destination(name.to_owned())?
+            .build()
+            .await
+            .context("failed to bind to player")?;
+
+        let mut initial = PlaybackState {
+            player: name.to_owned(),
+            title: String::new(),
+            artist: String::new(),
+            status: PlaybackStatus::parse(&proxy.playback_status().await?),
+        };
+        initial.update_metadata(proxy.metadata().await?);
+
+        debug!("tracking mpris player `{name}`");
+
+        enum Event {
+            Status(PlaybackStatus),
+            Metadata(HashMap<String, OwnedValue>),
+        }
+
+        let stream = stream::select(
+            proxy
+                .receive_playback_status_changed()
+                .await
+                .filter_map(async |val| {
+                    val.get().await.ok().map(|v| Event::Status(PlaybackStatus::parse(&v)))
+                })
+                .boxed(),
+            proxy
+                .receive_metadata_changed()
+                .await
+                .filter_map(async |val| val.get().await.ok().map(Event::Metadata))
+                .boxed(),
+        )
+        .scan_owning(initial.clone(), async |mut state, event| {
+            trace!("updating state of mpris player `{}`", state.player);
+
+            match event {
+                Event::Status(status) => state.status = status,
+                Event::Metadata(metadata) => state.update_metadata(metadata),
+            }
+
+            Some((state.clone(), state))
+        })
+        .boxed();
+
+        Ok((Self { name: name.to_owned(), _proxy: proxy, stream }, initial))
+    }
+}
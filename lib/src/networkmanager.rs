@@ -1,20 +1,55 @@
-use std::{collections::HashMap, future};
+use std::{collections::HashMap, future, time::Duration};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow};
 use futures::{
     FutureExt, StreamExt,
     stream::{self, BoxStream},
 };
-use log::{debug, trace};
-use rusty_network_manager::{AccessPointProxy, ActiveProxy, NetworkManagerProxy, WirelessProxy};
-use tokio::{select, sync::mpsc};
+use log::{debug, trace, warn};
+use rusty_network_manager::{
+    AccessPointProxy, ActiveProxy, DeviceProxy, NetworkManagerProxy, WirelessProxy,
+};
+use tokio::{
+    select,
+    sync::mpsc,
+    time::{sleep, timeout},
+};
 use tokio_stream::wrappers::ReceiverStream;
-use zbus::Connection;
+use zbus::{
+    Connection, proxy,
+    zvariant::{ObjectPath, OwnedValue},
+};
 
 use crate::{StaticStream, StreamContext, util::StreamCustomExt};
 
 pub use zbus::zvariant::OwnedObjectPath;
 
+/// time to wait for a requested scan to finish
+const SCAN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// device type of wifi devices
+/// see https://people.freedesktop.org/~lkundrak/nm-docs/nm-dbus-types.html#NMDeviceType
+const DEVICE_TYPE_WIFI: u32 = 2;
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Settings",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager/Settings"
+)]
+trait Settings {
+    /// lists all known connection profiles
+    fn list_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.NetworkManager.Settings.Connection",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait SettingsConnection {
+    /// returns the settings of the connection profile, without secrets
+    fn get_settings(&self) -> zbus::Result<HashMap<String, HashMap<String, OwnedValue>>>;
+}
+
 #[derive(Clone)] // everything in here's reference counted anyways
 pub struct NetworkManager {
     pub(crate) connection: Connection,
@@ -33,8 +68,92 @@ impl NetworkManager {
         Ok(Self { connection, proxy })
     }
 
-    /// listen to changes of the primarily used connection
-    pub async fn listen_primary_connection(&self) -> StaticStream<Option<OwnedObjectPath>> {
+    /// repeatedly creates a stream with `listen` and forwards its items. if the
+    /// stream ends or network manager is restarted, we reconnect with a backoff
+    /// and create the stream again, which then reports the full state again
+    fn listen_reconnecting<T, F, Fut>(self, name: &'static str, listen: F) -> StaticStream<T>
+    where
+        T: Send + 'static,
+        F: Fn(Self) -> Fut + Send + 'static,
+        Fut: Future<Output = StaticStream<T>> + Send,
+    {
+        const BACKOFF_MIN: Duration = Duration::from_millis(500);
+        const BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+        let (tx, rx) = mpsc::channel(1);
+
+        tokio::spawn(async move {
+            let mut backoff = BACKOFF_MIN;
+            let mut existing = Some(self); // the first attempt uses the existing connection
+
+            loop {
+                let nm = match existing.take() {
+                    Some(nm) => Ok(nm),
+                    None => Self::connnect().await,
+                };
+
+                match nm {
+                    Ok(nm) => {
+                        // the owner changes if network manager is stopped or restarted
+                        let mut owner = match nm.proxy.inner().receive_owner_changed().await {
+                            Ok(owner) => owner.boxed(),
+                            Err(e) => {
+                                warn!("failed to watch network manager for {name}: {e:#}");
+                                stream::pending().boxed()
+                            }
+                        };
+
+                        let mut stream = listen(nm).await;
+
+                        loop {
+                            select! {
+                                item = stream.next() => {
+                                    let Some(item) = item else {
+                                        warn!("{name} stream has ended, trying to reconnect");
+                                        break;
+                                    };
+
+                                    backoff = BACKOFF_MIN;
+
+                                    if tx.send(item).await.is_err() {
+                                        debug!("{name} stream was dropped");
+                                        return;
+                                    }
+                                }
+                                _ = owner.next() => {
+                                    warn!("network manager has restarted, reconnecting {name}");
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => warn!("failed to reconnect to network manager for {name}: {e:#}"),
+                }
+
+                if tx.is_closed() {
+                    debug!("{name} stream was dropped");
+                    return;
+                }
+
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(BACKOFF_MAX);
+            }
+        });
+
+        ReceiverStream::new(rx).boxed()
+    }
+
+    /// listen to changes of the primarily used connection, reconnecting if
+    /// network manager is restarted
+    pub fn listen_primary_connection(self) -> StaticStream<Option<OwnedObjectPath>> {
+        self.listen_reconnecting("nm primary connection", |nm| async move {
+            nm.listen_primary_connection_once().await
+        })
+    }
+
+    /// listen to changes of the primarily used connection on the current
+    /// connection to network manager
+    async fn listen_primary_connection_once(&self) -> StaticStream<Option<OwnedObjectPath>> {
         const STREAM: &str = "nm primary connection";
 
         self.proxy
@@ -54,8 +173,205 @@ impl NetworkManager {
             .boxed()
     }
 
-    /// listen to all active connections
+    /// returns whether airplane mode is active, meaning all radios managed by
+    /// network manager (wifi and wwan) are disabled
+    pub async fn airplane_mode(&self) -> Result<bool> {
+        let wireless =
+            self.proxy.wireless_enabled().await.context("failed to read wireless radio state")?;
+        let wwan = self.proxy.wwan_enabled().await.context("failed to read wwan radio state")?;
+
+        Ok(!wireless && !wwan)
+    }
+
+    /// enables or disables airplane mode, by toggling all radios managed by
+    /// network manager. if only some of the radios could be toggled (e.g.
+    /// because there is no wwan device), this still succeeds
+    pub async fn set_airplane_mode(&self, enabled: bool) -> Result<()> {
+        let wireless = self
+            .proxy
+            .set_wireless_enabled(!enabled)
+            .await
+            .context("failed to toggle wireless radio");
+        let wwan =
+            self.proxy.set_wwan_enabled(!enabled).await.context("failed to toggle wwan radio");
+
+        match (wireless, wwan) {
+            (Err(e), Err(_)) => Err(e),
+            (Err(e), Ok(())) | (Ok(()), Err(e)) => {
+                debug!("could only partially toggle airplane mode: {e:#}");
+                Ok(())
+            }
+            (Ok(()), Ok(())) => Ok(()),
+        }
+    }
+
+    /// listen to changes of the airplane mode, see `airplane_mode`
+    pub async fn listen_airplane_mode(&self) -> StaticStream<bool> {
+        const STREAM: &str = "nm airplane mode";
+
+        let wireless = self.proxy.receive_wireless_enabled_changed().await.map(|_| ()).boxed();
+        let wwan = self.proxy.receive_wwan_enabled_changed().await.map(|_| ()).boxed();
+
+        let nm = self.clone();
+
+        // we re-read both radios on every change, as we need the combined state
+        stream::select(wireless, wwan)
+            .filter_map(move |_| {
+                let nm = nm.clone();
+
+                async move {
+                    nm.airplane_mode().await.stream_context(STREAM, "failed to read radio state")
+                }
+            })
+            .boxed()
+    }
+
+    /// returns all wifi devices managed by network manager
+    pub async fn wireless_devices(&self) -> Result<Vec<OwnedObjectPath>> {
+        let mut devices = vec![];
+
+        for path in self.proxy.get_devices().await.context("failed to list devices")? {
+            let device = DeviceProxy::new_from_path(path.clone(), &self.connection)
+                .await
+                .context("failed to bind to device")?;
+
+            if device.device_type().await.context("failed to read device type")? == DEVICE_TYPE_WIFI
+            {
+                devices.push(path);
+            }
+        }
+
+        Ok(devices)
+    }
+
+    /// returns the access points a wireless device has found during its last
+    /// scan, without requesting a new one
+    pub async fn access_points(&self, device: &OwnedObjectPath) -> Result<Vec<ApInfo>> {
+        let proxy = WirelessProxy::new_from_path(device.clone(), &self.connection)
+            .await
+            .context("failed to bind to wireless device")?;
+
+        self.read_access_points(&proxy).await
+    }
+
+    /// requests a scan on a wireless device and returns the access points
+    /// found once it is done. if the device refuses to scan (e.g. because it
+    /// has just scanned), the results of the previous scan are returned
+    pub async fn scan_access_points(&self, device: &OwnedObjectPath) -> Result<Vec<ApInfo>> {
+        let proxy = WirelessProxy::new_from_path(device.clone(), &self.connection)
+            .await
+            .context("failed to bind to wireless device")?;
+
+        let previous = proxy.last_scan().await.context("failed to read last scan time")?;
+        let mut changes = proxy.receive_last_scan_changed().await;
+
+        if let Err(e) = proxy.request_scan(HashMap::new()).await {
+            debug!("wireless device {} refused to scan: {e}", describe_path(device));
+        } else {
+            // the scan finishes asynchronously, which changes the last scan time
+            let finished = async {
+                while let Some(change) = changes.next().await {
+                    if change.get().await.is_ok_and(|last| last != previous) {
+                        break;
+                    }
+                }
+            };
+
+            if timeout(SCAN_TIMEOUT, finished).await.is_err() {
+                debug!(
+                    "scan on {} did not finish in time, using old results",
+                    describe_path(device)
+                );
+            }
+        }
+
+        self.read_access_points(&proxy).await
+    }
+
+    /// reads all access points currently known to a wireless device
+    async fn read_access_points(&self, proxy: &WirelessProxy<'_>) -> Result<Vec<ApInfo>> {
+        const STREAM: &str = "nm access points";
+
+        async fn read(path: OwnedObjectPath, connection: &Connection) -> Result<ApInfo> {
+            let proxy = AccessPointProxy::new_from_path(path.clone(), connection)
+                .await
+                .context("failed to bind to access point")?;
+
+            // privacy flag signals wep, otherwise wpa or rsn flags are set
+            let secured = proxy.flags().await? & 0x1 != 0
+                || proxy.wpa_flags().await? != 0
+                || proxy.rsn_flags().await? != 0;
+
+            Ok(ApInfo {
+                ssid: String::from_utf8_lossy(&proxy.ssid().await?).into_owned(),
+                strength: proxy.strength().await? as f64 / 100f64,
+                secured,
+                path,
+            })
+        }
+
+        let mut aps = vec![];
+
+        for path in proxy.get_all_access_points().await.context("failed to list access points")? {
+            // access points may vanish while we read them
+            if let Some(ap) = read(path, &self.connection)
+                .await
+                .stream_context(STREAM, "failed to read access point")
+            {
+                aps.push(ap);
+            }
+        }
+
+        Ok(aps)
+    }
+
+    /// activates a known connection profile for the given ssid on a wireless
+    /// device
+    pub async fn activate_connection(&self, device: &OwnedObjectPath, ssid: &str) -> Result<()> {
+        let settings = SettingsProxy::new(&self.connection)
+            .await
+            .context("failed to bind to network manager settings")?;
+
+        for path in settings.list_connections().await.context("failed to list connections")? {
+            let profile = SettingsConnectionProxy::new(&self.connection, path.clone())
+                .await
+                .context("failed to bind to connection profile")?
+                .get_settings()
+                .await
+                .context("failed to read connection profile")?;
+
+            let Some(value) =
+                profile.get("802-11-wireless").and_then(|wireless| wireless.get("ssid"))
+            else {
+                continue;
+            };
+
+            if Vec::<u8>::try_from(value.try_clone()?).is_ok_and(|other| other == ssid.as_bytes()) {
+                debug!("activating connection {} for `{ssid}`", describe_path(&path));
+
+                self.proxy
+                    .activate_connection(&path, device, &ObjectPath::from_static_str_unchecked("/"))
+                    .await
+                    .context("failed to activate connection")?;
+
+                return Ok(());
+            }
+        }
+
+        Err(anyhow!("no known connection profile for `{ssid}`"))
+    }
+
+    /// listen to all active connections, reconnecting if network manager is
+    /// restarted
     pub fn listen_active_connections(self) -> StaticStream<Vec<ActiveConnection>> {
+        self.listen_reconnecting("nm active connections", |nm| async move {
+            nm.listen_active_connections_once()
+        })
+    }
+
+    /// listen to all active connections on the current connection to network
+    /// manager
+    fn listen_active_connections_once(self) -> StaticStream<Vec<ActiveConnection>> {
         const STREAM: &str = "nm active connections";
 
         let (tx, rx) = mpsc::channel(1);
@@ -132,6 +448,11 @@ impl NetworkManager {
                         // update state
                         states.insert(state.path.clone(), state);
                     }
+                    _ = tx.closed() => {
+                        // this happens when we reconnect
+                        debug!("network manager active connections stream was dropped");
+                        return;
+                    }
                 }
             }
         });
@@ -349,6 +670,19 @@ impl<'a> TrackedActiveConnection<'a> {
     }
 }
 
+/// an access point found by a wireless device
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApInfo {
+    /// dbus path of the access point
+    pub path: OwnedObjectPath,
+    /// name of the network, might be empty for hidden networks
+    pub ssid: String,
+    /// signal strength from 0 to 1
+    pub strength: f64,
+    /// whether the network requires authentication
+    pub secured: bool,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ActiveConnection {
     /// dbus path of the connection (see primary connection)
@@ -418,17 +752,36 @@ impl ActiveConnectionKind {
     }
 }
 
+/// shortens an object path to its last two segments for logging, paths with
+/// fewer segments are returned as is
 pub fn describe_path(path: &str) -> &str {
-    let mut count = 0;
+    // the separator is ascii, so slicing after it is always on a char boundary
+    path.rmatch_indices('/').nth(1).map_or(path, |(i, _)| &path[(i + 1)..])
+}
 
-    for (i, c) in path.chars().rev().enumerate() {
-        if c == '/' {
-            count += 1;
-        }
-        if count == 2 {
-            return &path[(path.len() - i)..];
-        }
+#[cfg(test)]
+mod tests {
+    use super::describe_path;
+
+    #[test]
+    fn describes_typical_paths() {
+        assert_eq!(describe_path("/org/freedesktop/NetworkManager/Devices/3"), "Devices/3");
+        assert_eq!(
+            describe_path("/org/freedesktop/NetworkManager/AccessPoint/42"),
+            "AccessPoint/42"
+        );
     }
 
-    return path;
+    #[test]
+    fn keeps_short_paths() {
+        assert_eq!(describe_path(""), "");
+        assert_eq!(describe_path("/"), "/");
+        assert_eq!(describe_path("/Devices"), "/Devices");
+    }
+
+    #[test]
+    fn handles_non_ascii_paths() {
+        assert_eq!(describe_path("/ä/Gerät/ö"), "Gerät/ö");
+        assert_eq!(describe_path("/äöü"), "/äöü");
+    }
 }
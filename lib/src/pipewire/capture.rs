@@ -0,0 +1,119 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use log::{debug, trace, warn};
+use pipewire::{
+    node::{Node, NodeListener, NodeState as PwNodeState},
+    spa::utils::dict::DictRef,
+};
+use tokio::sync::broadcast::Sender;
+
+struct CaptureTrackerObject {
+    _proxy: Node,
+    _listener: NodeListener,
+
+    /// whether the stream is currently running
+    running: bool,
+}
+
+/// tracks all audio capture streams of applications to know whether any of
+/// them is recording
+pub(crate) struct CaptureTracker {
+    updates: Sender<bool>,
+
+    streams: RefCell<HashMap<u32, CaptureTrackerObject>>,
+}
+
+impl CaptureTracker {
+    pub fn new(updates: Sender<bool>) -> Self {
+        Self { updates, streams: RefCell::new(HashMap::new()) }
+    }
+
+    /// tries to add a node, if it is a capture stream
+    pub fn add_node<F>(self: &Rc<Self>, id: u32, props: &DictRef, bind: F)
+    where
+        F: FnOnce() -> Option<Node>,
+    {
+        if props.get("media.class") != Some("Stream/Input/Audio") {
+            return;
+        }
+
+        // these only peek at the levels, like pavucontrol does
+        if props.get("stream.monitor") == Some("true") {
+            trace!("skipping bind to monitoring stream {id}");
+            return;
+        }
+
+        let Some(node) = bind() else {
+            return;
+        };
+
+        let listener = node
+            .add_listener_local()
+            .info({
+                let this = self.clone();
+                move |info| {
+                    this.update_state(id, matches!(info.state(), PwNodeState::Running));
+                }
+            })
+            .register();
+
+        debug!(
+            "adding capture stream {id} to tracker ('{}')",
+            props.get("application.name").unwrap_or("<unknown>")
+        );
+
+        self.streams
+            .borrow_mut()
+            .insert(id, CaptureTrackerObject { _proxy: node, _listener: listener, running: false });
+    }
+
+    /// removes a stream if it is tracked
+    pub fn remove(&self, id: u32) {
+        let result = self.streams.borrow_mut().remove(&id); // for borrow lifetime
+        if let Some(removed) = result {
+            debug!("removing capture stream {id} from tracker");
+
+            if removed.running {
+                self.update();
+            }
+        }
+    }
+
+    /// updates whether a tracked stream is running
+    fn update_state(&self, id: u32, running: bool) {
+        let mut changed = false;
+
+        if let Some(stream) = self.streams.borrow_mut().get_mut(&id) {
+            trace!("updating state for capture stream {id}");
+
+            changed = stream.running != running;
+            stream.running = running;
+        } else {
+            warn!("tried to update state for capture stream {id} which is not tracked");
+        }
+
+        if changed {
+            self.update();
+        }
+    }
+
+    /// broadcasts whether any stream is capturing
+    fn update(&self) {
+        let active = self.streams.borrow().values().any(|stream| stream.running);
+        trace!("sending capture update ({active})");
+
+        if self.updates.send(active).is_err() {
+            warn!("failed to send capture update to channel");
+        }
+    }
+
+    /// triggers a manual update in the channel
+    pub fn trigger_update(&self) {
+        self.update();
+    }
+
+    /// stops tracking all streams, which drops their proxies and listeners
+    pub fn clear(&self) {
+        self.streams.borrow_mut().clear();
+    }
+}
@@ -113,6 +113,21 @@ impl Default for DefaultState {
 }
 
 impl DefaultState {
+    /// returns whether the default sink is known
+    pub fn sink_known(&self) -> bool {
+        self.sink != DEFAULT_STATE_UNKNOWN
+    }
+
+    /// returns whether the default source is known
+    pub fn source_known(&self) -> bool {
+        self.source != DEFAULT_STATE_UNKNOWN
+    }
+
+    /// returns whether both the default sink and source are known
+    pub fn is_known(&self) -> bool {
+        self.sink_known() && self.source_known()
+    }
+
     fn update(&mut self, key: Option<&str>, value: Option<&str>) -> bool {
         let Some(key) = key else {
             // the docs mention that a null key means the removal of all values,
@@ -151,3 +166,62 @@ impl DefaultState {
 struct DefaultNodeValue {
     name: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_parses_name() {
+        let mut state = DefaultState::default();
+
+        assert!(state.update(Some("default.audio.sink"), Some(r#"{ "name": "speakers" }"#)));
+        assert_eq!(state.sink, "speakers");
+        assert!(state.sink_known());
+        assert!(!state.is_known());
+
+        assert!(state.update(Some("default.audio.source"), Some(r#"{ "name": "mic" }"#)));
+        assert_eq!(state.source, "mic");
+        assert!(state.is_known());
+    }
+
+    #[test]
+    fn update_ignores_null_key() {
+        let mut state = DefaultState::default();
+        state.update(Some("default.audio.sink"), Some(r#"{ "name": "speakers" }"#));
+
+        assert!(!state.update(None, None));
+        assert_eq!(state.sink, "speakers");
+    }
+
+    #[test]
+    fn update_ignores_unrecognized_key() {
+        let mut state = DefaultState::default();
+
+        assert!(!state.update(Some("default.video.source"), Some(r#"{ "name": "camera" }"#)));
+        assert!(!state.sink_known());
+        assert!(!state.source_known());
+    }
+
+    #[test]
+    fn update_resets_malformed_json() {
+        let mut state = DefaultState::default();
+        state.update(Some("default.configured.audio.sink"), Some(r#"{ "name": "speakers" }"#));
+
+        assert!(state.update(Some("default.configured.audio.sink"), Some("{ name: speakers")));
+        assert_eq!(state.configured_sink, DEFAULT_STATE_UNKNOWN);
+
+        state.update(Some("default.configured.audio.sink"), Some(r#"{ "name": "speakers" }"#));
+        assert!(state.update(Some("default.configured.audio.sink"), Some(r#"{ "id": 42 }"#)));
+        assert_eq!(state.configured_sink, DEFAULT_STATE_UNKNOWN);
+    }
+
+    #[test]
+    fn update_resets_removed_value() {
+        let mut state = DefaultState::default();
+        state.update(Some("default.audio.source"), Some(r#"{ "name": "mic" }"#));
+
+        assert!(state.update(Some("default.audio.source"), None));
+        assert!(!state.source_known());
+    }
+}
@@ -1,60 +1,146 @@
-use std::{rc::Rc, thread};
+use std::{
+    cell::Cell,
+    collections::HashMap,
+    future,
+    rc::Rc,
+    sync::{Arc, RwLock},
+    thread,
+    time::Duration,
+};
 
 use anyhow::{Context as _, Result, anyhow};
 use futures::StreamExt;
-use log::{trace, warn};
+use log::{debug, trace, warn};
 use pipewire::{
     channel::{self as pwchannel, Receiver as PwReceiver, Sender as PwSender},
     context::ContextRc,
+    core::PW_ID_CORE,
     main_loop::MainLoopRc,
     registry::{GlobalObject, RegistryRc},
     spa::utils::dict::DictRef,
 };
-use tokio::sync::broadcast::{self, Receiver as BcReceiver, Sender as BcSender};
-use tokio_stream::wrappers::BroadcastStream;
+use tokio::{
+    sync::{
+        broadcast::{self, Receiver as BcReceiver, Sender as BcSender},
+        watch,
+    },
+    time::timeout,
+};
+use tokio_stream::wrappers::{BroadcastStream, WatchStream};
 
 use crate::{
     StaticStream, StreamContext,
     pipewire::{
+        capture::CaptureTracker,
         default::{DefaultState, DefaultTracker},
-        node::{NodeState, NodeTracker},
+        node::{DeviceProfiles, NodeEvent, NodeState, NodeTracker, from_visual},
+        peak::PeakTracker,
     },
 };
 
+mod capture;
 pub mod default;
 pub mod node;
+mod peak;
+
+/// time a capture has to be stopped for before it is reported, so that brief
+/// gaps between captures are ignored
+const CAPTURE_GRACE: Duration = Duration::from_secs(2);
+/// time after which connecting to pipewire is retried if it failed
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// state of the connection to pipewire
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PipewireStatus {
+    /// the first connection is being established
+    Connecting,
+    Connected,
+    /// the connection failed or was lost, it is retried periodically
+    Failed,
+}
 
 pub struct PipewireInstance {
+    status: watch::Receiver<PipewireStatus>,
     sinks: BcReceiver<Vec<NodeState>>,
     sources: BcReceiver<Vec<NodeState>>,
     defaults: BcReceiver<DefaultState>,
+    events: BcReceiver<NodeEvent>,
+    profiles: BcReceiver<DeviceProfiles>,
+    capture: BcReceiver<bool>,
+    peaks: BcReceiver<(String, f32)>,
+    ids: Arc<RwLock<HashMap<String, u32>>>,
     actions: PwSender<PipewireAction>,
 }
 
 impl PipewireInstance {
     /// start the pipewire instance
     /// this will create a new thread which will communicate directly with
-    /// pipewire
+    /// pipewire, and reconnects to it if the connection is lost
     pub fn start() -> Self {
+        let (status_tx, status_rx) = watch::channel(PipewireStatus::Connecting);
         let (sinks_tx, sinks_rx) = broadcast::channel(1);
         let (sources_tx, sources_rx) = broadcast::channel(1);
         let (defaults_tx, defaults_rx) = broadcast::channel(1);
+        let (events_tx, events_rx) = broadcast::channel(32); // events should not get lost
+        let (profiles_tx, profiles_rx) = broadcast::channel(8); // devices update separately
+        let (capture_tx, capture_rx) = broadcast::channel(1);
+        let (peaks_tx, peaks_rx) = broadcast::channel(32); // peaks arrive with every buffer
         let (actions_tx, actions_rx) = pwchannel::channel();
+        let ids = Arc::new(RwLock::new(HashMap::new()));
+
+        let channels = PipewireChannels {
+            status: status_tx,
+            sinks: sinks_tx,
+            sources: sources_tx,
+            defaults: defaults_tx,
+            events: events_tx,
+            profiles: profiles_tx,
+            capture: capture_tx,
+            ids: ids.clone(),
+        };
+
+        thread::spawn(move || {
+            let mut actions = Some(actions_rx);
+            let peaks = Rc::new(PeakTracker::new(peaks_tx));
+
+            loop {
+                match PipewireThread::run(&channels, &mut actions, &peaks) {
+                    Ok(true) => break,
+                    Ok(false) => warn!("lost connection to pipewire, reconnecting"),
+                    Err(e) => warn!("failed to run pipewire thread, retrying: {e:#}"),
+                }
 
-        thread::spawn(|| {
-            if let Err(e) = PipewireThread::run(sinks_tx, sources_tx, defaults_tx, actions_rx) {
-                warn!("failed to run pipewire thread: {e:#}");
-            };
+                channels.status.send_replace(PipewireStatus::Failed);
+                thread::sleep(RECONNECT_INTERVAL);
+
+                // nobody is interested in pipewire anymore
+                if channels.status.is_closed() {
+                    debug!("pipewire instance was dropped, stopping thread");
+                    break;
+                }
+            }
         });
 
         PipewireInstance {
+            status: status_rx,
             sinks: sinks_rx,
             sources: sources_rx,
             defaults: defaults_rx,
+            events: events_rx,
+            profiles: profiles_rx,
+            capture: capture_rx,
+            peaks: peaks_rx,
+            ids,
             actions: actions_tx,
         }
     }
 
+    /// listen to the state of the connection to pipewire, which fires with the
+    /// current state first
+    pub fn listen_status(&self) -> StaticStream<PipewireStatus> {
+        WatchStream::new(self.status.clone()).boxed()
+    }
+
     /// listen to changes to the system's used default devices (sink and source)
     pub fn listen_defaults(&self) -> StaticStream<DefaultState> {
         BroadcastStream::new(self.defaults.resubscribe())
@@ -80,6 +166,84 @@ impl PipewireInstance {
             .boxed()
     }
 
+    /// listen to changes of single sinks and sources, which includes them being
+    /// added and removed
+    pub fn listen_node_events(&self) -> StaticStream<NodeEvent> {
+        BroadcastStream::new(self.events.resubscribe())
+            .filter_map(async |r| r.stream_context("pw events", "failed to receive from broadcast"))
+            .boxed()
+    }
+
+    /// listen to the profiles of the device with the given name and which one of
+    /// them is active, like a headset switching between a2dp and hsp
+    pub fn listen_profiles(&self, device: &str) -> StaticStream<DeviceProfiles> {
+        let device = device.to_owned();
+
+        BroadcastStream::new(self.profiles.resubscribe())
+            .filter_map(async |r| {
+                r.stream_context("pw profiles", "failed to receive from broadcast")
+            })
+            .filter(move |profiles| future::ready(profiles.name == device))
+            .boxed()
+    }
+
+    /// listen to whether any application is capturing audio (e.g. from a
+    /// microphone). the end of a capture is only reported after a grace period,
+    /// so that brief gaps between captures don't show up
+    pub fn listen_capture_active(&self) -> StaticStream<bool> {
+        let updates = BroadcastStream::new(self.capture.resubscribe())
+            .filter_map(async |r| {
+                r.stream_context("pw capture", "failed to receive from broadcast")
+            })
+            .boxed();
+
+        futures::stream::unfold((updates, None), async |(mut updates, last)| {
+            loop {
+                let mut active = updates.next().await?;
+
+                // we wait whether the capture resumes before reporting its end
+                while !active && last == Some(true) {
+                    match timeout(CAPTURE_GRACE, updates.next()).await {
+                        Ok(next) => active = next?,
+                        Err(_) => break,
+                    }
+                }
+
+                if Some(active) != last {
+                    return Some((active, (updates, Some(active))));
+                }
+            }
+        })
+        .boxed()
+    }
+
+    /// listen to the peak level (0-1) of the sink or source with the given name,
+    /// which fires for every buffer of audio. for sinks, this is the level of
+    /// what they play. the node is only measured while this stream is alive
+    pub fn listen_peak(&self, name: &str) -> StaticStream<f32> {
+        let node = name.to_owned();
+        let guard = PeakGuard(self.actions.clone(), name.to_owned());
+
+        if let Err(e) = self.send_command(PipewireAction::AttachPeak(name.to_owned())) {
+            warn!("failed to start measuring peak of `{name}`: {e:#}");
+        }
+
+        BroadcastStream::new(self.peaks.resubscribe())
+            .filter_map(async |r| r.stream_context("pw peaks", "failed to receive from broadcast"))
+            .filter(move |(name, _)| future::ready(*name == node))
+            .map(move |(_, peak)| {
+                let _guard = &guard; // detaches once the stream is dropped
+                peak
+            })
+            .boxed()
+    }
+
+    /// looks up the id of the sink or source with the given name, which can be
+    /// used to correlate the names of the defaults with a `NodeState`
+    pub fn node_id(&self, name: &str) -> Option<u32> {
+        self.ids.read().ok()?.get(name).copied()
+    }
+
     /// set the default sink the system uses
     pub fn set_default_sink(&self, name: &str) -> Result<()> {
         self.send_command(PipewireAction::DefaultSink(name.to_string()))
@@ -90,10 +254,30 @@ impl PipewireInstance {
         self.send_command(PipewireAction::DefaultSource(name.to_string()))
     }
 
-    /// sets the given node's volume for each channel
+    /// sets the given node's volume for each channel in "visual" form (like
+    /// `NodeState::volume`)
     /// make sure your channel slice has the right amount of entries
     pub fn set_volume(&self, name: &str, volume: &[f32]) -> Result<()> {
-        self.send_command(PipewireAction::NodeVolume(name.to_string(), volume.to_owned()))
+        self.send_command(PipewireAction::NodeVolume(
+            name.to_string(),
+            volume.iter().copied().map(from_visual).collect(),
+        ))
+    }
+
+    /// sets the given node's volume for each channel in linear form (like
+    /// `NodeState::linear_volume`)
+    /// make sure your channel slice has the right amount of entries
+    pub fn set_volume_linear(&self, name: &str, volume: &[f32]) -> Result<()> {
+        self.send_command(PipewireAction::NodeVolume(
+            name.to_string(),
+            volume.iter().map(|volume| volume.max(0f32)).collect(),
+        ))
+    }
+
+    /// switches the device with the given name to the profile with the given
+    /// index (see `DeviceProfile::index`)
+    pub fn set_profile(&self, device: &str, profile: u32) -> Result<()> {
+        self.send_command(PipewireAction::DeviceProfile(device.to_string(), profile))
     }
 
     /// sets the given node's mute state
@@ -114,30 +298,72 @@ impl PipewireInstance {
     }
 }
 
+/// stops measuring the peak of a node when dropped
+struct PeakGuard(PwSender<PipewireAction>, String);
+
+impl Drop for PeakGuard {
+    fn drop(&mut self) {
+        if self.0.send(PipewireAction::DetachPeak(self.1.clone())).is_err() {
+            debug!("pipewire thread has already exited");
+        }
+    }
+}
+
+impl Drop for PipewireInstance {
+    fn drop(&mut self) {
+        // the thread cleans up and exits on its own, so we don't have to join it
+        if self.send_command(PipewireAction::Quit).is_err() {
+            debug!("pipewire thread has already exited");
+        }
+    }
+}
+
 /// this can be sent to the pipewire thread to do something
 /// usually takes the device name as first argument
 enum PipewireAction {
     DefaultSink(String),
     DefaultSource(String),
-    NodeVolume(String, Vec<f32>),
+    NodeVolume(String, Vec<f32>), // linear volume
     NodeMute(String, bool),
+    DeviceProfile(String, u32),
+    AttachPeak(String),
+    DetachPeak(String),
     Update, // sends an update through every channel
+    Quit,   // stops the mainloop and thereby the thread
+}
+
+/// channels through which the pipewire thread reports, these outlive
+/// reconnections
+struct PipewireChannels {
+    status: watch::Sender<PipewireStatus>,
+    sinks: BcSender<Vec<NodeState>>,
+    sources: BcSender<Vec<NodeState>>,
+    defaults: BcSender<DefaultState>,
+    events: BcSender<NodeEvent>,
+    profiles: BcSender<DeviceProfiles>,
+    capture: BcSender<bool>,
+    ids: Arc<RwLock<HashMap<String, u32>>>,
 }
 
 struct PipewireThread {
+    mainloop: MainLoopRc,
     registry: RegistryRc,
 
     default: DefaultTracker,
     nodes: Rc<NodeTracker>,
+    capture: Rc<CaptureTracker>,
+    peaks: Rc<PeakTracker>,
 }
 
 impl PipewireThread {
+    /// connects to pipewire and runs the mainloop until it is quit or the
+    /// connection is lost, returns whether it was quit. the action channel is
+    /// handed back so it can be reused when reconnecting
     fn run(
-        sinks: BcSender<Vec<NodeState>>,
-        sources: BcSender<Vec<NodeState>>,
-        defaults: BcSender<DefaultState>,
-        actions: PwReceiver<PipewireAction>,
-    ) -> Result<()> {
+        channels: &PipewireChannels,
+        actions: &mut Option<PwReceiver<PipewireAction>>,
+        peaks: &Rc<PeakTracker>,
+    ) -> Result<bool> {
         let mainloop = MainLoopRc::new(None).context("failed to create new pipewire mainloop")?;
 
         trace!("connecting to pipewire");
@@ -146,13 +372,43 @@ impl PipewireThread {
         let core = context.connect_rc(None).context("failed to connect to pipewire")?;
         let registry = core.get_registry_rc().context("failed to retrieve pipewire registry")?;
 
+        channels.status.send_replace(PipewireStatus::Connected);
+
+        // errors on the core itself mean that the connection is broken
+        let lost = Rc::new(Cell::new(false));
+        let _core = core
+            .add_listener_local()
+            .error({
+                let mainloop = mainloop.clone();
+                let lost = lost.clone();
+                move |id, _seq, res, message| {
+                    if id == PW_ID_CORE {
+                        warn!("pipewire core reported error {res}: {message}");
+                        lost.set(true);
+                        mainloop.quit();
+                    }
+                }
+            })
+            .register();
+
         let state = Rc::new(Self {
+            mainloop: mainloop.clone(),
             registry: registry,
 
-            default: DefaultTracker::new(defaults),
-            nodes: Rc::new(NodeTracker::new(sinks, sources)),
+            default: DefaultTracker::new(channels.defaults.clone()),
+            nodes: Rc::new(NodeTracker::new(
+                channels.sinks.clone(),
+                channels.sources.clone(),
+                channels.events.clone(),
+                channels.profiles.clone(),
+                channels.ids.clone(),
+            )),
+            capture: Rc::new(CaptureTracker::new(channels.capture.clone())),
+            peaks: peaks.clone(),
         });
 
+        state.peaks.connect(core.clone());
+
         let _global = state
             .registry
             .add_listener_local()
@@ -170,20 +426,38 @@ impl PipewireThread {
             })
             .register();
 
-        let _attached = actions.attach(mainloop.loop_(), move |action| {
-            state.action(action);
-        });
+        let attached =
+            actions.take().context("pipewire action channel was lost")?.attach(mainloop.loop_(), {
+                let state = state.clone();
+                move |action| {
+                    state.action(action);
+                }
+            });
 
         trace!("entering pipewire mainloop");
         mainloop.run();
         trace!("exited pipewire mainloop");
 
-        Ok(())
+        *actions = Some(attached.deattach());
+
+        // the listeners of tracked objects reference the tracker, so this cycle has
+        // to be broken for the proxies to be dropped
+        state.nodes.clear();
+        state.capture.clear();
+        state.peaks.clear();
+
+        // the ids are meaningless for the next connection
+        if let Ok(mut ids) = channels.ids.write() {
+            ids.clear();
+        }
+
+        Ok(!lost.get())
     }
 
     fn global_remove(self: &Rc<Self>, id: u32) {
         self.default.detach(id);
         self.nodes.remove(id);
+        self.capture.remove(id);
     }
 
     fn global(self: &Rc<Self>, global: &GlobalObject<&DictRef>) {
@@ -208,6 +482,7 @@ impl PipewireThread {
                 };
 
                 self.nodes.add_node(global.id, props, || self.registry.bind(global).ok());
+                self.capture.add_node(global.id, props, || self.registry.bind(global).ok());
             }
             pipewire::types::ObjectType::Device => {
                 let Some(props) = global.props else {
@@ -230,10 +505,18 @@ impl PipewireThread {
             PipewireAction::DefaultSource(name) => self.default.set_source(Some(&name)),
             PipewireAction::NodeVolume(name, volume) => self.nodes.set_volume(&name, volume),
             PipewireAction::NodeMute(name, mute) => self.nodes.set_mute(&name, mute),
+            PipewireAction::DeviceProfile(name, profile) => self.nodes.set_profile(&name, profile),
+            PipewireAction::AttachPeak(name) => self.peaks.attach(&name),
+            PipewireAction::DetachPeak(name) => self.peaks.detach(&name),
 
             PipewireAction::Update => {
                 self.default.trigger_update();
                 self.nodes.trigger_update();
+                self.capture.trigger_update();
+            }
+            PipewireAction::Quit => {
+                debug!("quitting pipewire mainloop");
+                self.mainloop.quit();
             }
         }
     }
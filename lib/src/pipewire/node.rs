@@ -1,18 +1,26 @@
-use std::{cell::RefCell, cmp::max, collections::HashMap, io::Cursor, rc::Rc};
+use std::{
+    cell::RefCell,
+    cmp::max,
+    collections::HashMap,
+    io::Cursor,
+    rc::Rc,
+    sync::{Arc, RwLock},
+};
 
 use log::{debug, error, trace, warn};
 use pipewire::{
     device::{Device, DeviceListener},
     node::{Node, NodeListener},
     spa::{
-        param::ParamType,
+        param::{ParamInfoFlags, ParamType},
         pod::{
             Object, Pod, Property, PropertyFlags, Value, ValueArray, deserialize::PodDeserializer,
             object, serialize::PodSerializer,
         },
         sys::{
-            self, SPA_PARAM_ROUTE_device, SPA_PARAM_ROUTE_index, SPA_PARAM_ROUTE_props,
-            SPA_PARAM_ROUTE_save, SPA_PROP_channelVolumes, SPA_PROP_mute,
+            self, SPA_PARAM_PROFILE_index, SPA_PARAM_PROFILE_save, SPA_PARAM_ROUTE_device,
+            SPA_PARAM_ROUTE_index, SPA_PARAM_ROUTE_props, SPA_PARAM_ROUTE_save,
+            SPA_PROP_channelVolumes, SPA_PROP_mute,
         },
         utils::{SpaTypes, dict::DictRef},
     },
@@ -40,6 +48,8 @@ struct DeviceTrackerObject {
     _listener: DeviceListener,
 
     indices: HashMap<u32, u32>,
+    profiles: DeviceProfiles,
+    profiles_flags: Option<ParamInfoFlags>, // the serial flag toggles when the profiles change
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -91,11 +101,7 @@ impl NodeState {
         for prop in &params.properties {
             match (prop.key, &prop.value) {
                 (sys::SPA_PROP_channelVolumes, Value::ValueArray(ValueArray::Float(value))) => {
-                    // convert the volume to "visual" form, because linear is not really useful
-                    let mut value = value.clone();
-                    for ele in &mut value {
-                        *ele = ele.powf(1f32 / 3f32);
-                    }
+                    let value = value.iter().copied().map(to_visual).collect::<Vec<_>>();
 
                     changed |= *value != self.volume;
                     self.volume = value.clone();
@@ -127,11 +133,97 @@ impl NodeState {
     pub fn average_volume(&self) -> f32 {
         self.volume.iter().sum::<f32>() / max(self.volume.len(), 1) as f32
     }
+
+    /// current volume of each channel in linear form, like pipewire tracks it
+    pub fn linear_volume(&self) -> Vec<f32> {
+        self.volume.iter().copied().map(from_visual).collect()
+    }
+}
+
+/// a profile a device can be switched to, like the a2dp or hsp mode of a
+/// bluetooth headset
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeviceProfile {
+    /// index of the profile on its device
+    pub index: u32,
+
+    /// name of the profile
+    pub name: String,
+    /// description (human readable name) of the profile
+    pub description: String,
+
+    /// whether the profile can currently be used
+    pub available: bool,
+}
+
+impl DeviceProfile {
+    fn parse(params: Object) -> Option<Self> {
+        let mut index = None;
+        let mut name = String::new();
+        let mut description = String::new();
+        let mut available = true;
+
+        for prop in params.properties {
+            match (prop.key, prop.value) {
+                (sys::SPA_PARAM_PROFILE_index, Value::Int(value)) => index = Some(value as u32),
+                (sys::SPA_PARAM_PROFILE_name, Value::String(value)) => name = value,
+                (sys::SPA_PARAM_PROFILE_description, Value::String(value)) => description = value,
+                (sys::SPA_PARAM_PROFILE_available, Value::Id(value)) => {
+                    available = value.0 != sys::SPA_PARAM_AVAILABILITY_no
+                }
+                _ => {}
+            }
+        }
+
+        Some(Self { index: index?, name, description, available })
+    }
+}
+
+/// the profiles of a device and which one of them is active
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeviceProfiles {
+    /// internal pipewire device id
+    pub id: u32,
+
+    /// name of the device
+    pub name: String,
+    /// description (human readable name) of the device
+    pub description: String,
+
+    /// all profiles of the device, ordered by their index
+    pub profiles: Vec<DeviceProfile>,
+    /// index of the profile which is currently active
+    pub active: Option<u32>,
+}
+
+/// converts a linear volume to "visual" form, because linear is not really
+/// useful for displaying and changing
+fn to_visual(volume: f32) -> f32 {
+    volume.powf(1f32 / 3f32) // the cube root seems what everyone uses
+}
+
+/// converts a volume in "visual" form back to linear
+pub(crate) fn from_visual(volume: f32) -> f32 {
+    volume.max(0f32).powi(3)
+}
+
+/// a change to a single tracked node
+#[derive(Clone, Debug)]
+pub enum NodeEvent {
+    /// a node started being tracked
+    Added(NodeState),
+    /// the node with the given id is no longer tracked
+    Removed(u32),
+    /// the state of a node changed
+    Changed(NodeState),
 }
 
 pub(crate) struct NodeTracker {
     sink_updates: Sender<Vec<NodeState>>,
     source_updates: Sender<Vec<NodeState>>,
+    events: Sender<NodeEvent>,
+    profile_updates: Sender<DeviceProfiles>,
+    ids: Arc<RwLock<HashMap<String, u32>>>, // lookup of node ids by name for other threads
 
     nodes: RefCell<HashMap<u32, NodeTrackerObject>>,
     devices: RefCell<HashMap<u32, DeviceTrackerObject>>,
@@ -141,12 +233,18 @@ impl NodeTracker {
     pub fn new(
         sink_updates: Sender<Vec<NodeState>>,
         source_updates: Sender<Vec<NodeState>>,
+        events: Sender<NodeEvent>,
+        profile_updates: Sender<DeviceProfiles>,
+        ids: Arc<RwLock<HashMap<String, u32>>>,
     ) -> Self {
         Self {
             nodes: RefCell::new(HashMap::new()),
             devices: RefCell::new(HashMap::new()),
             sink_updates,
             source_updates,
+            events,
+            profile_updates,
+            ids,
         }
     }
 
@@ -207,6 +305,8 @@ impl NodeTracker {
             device.map(|a| a.to_string()).unwrap_or("<none>".to_string())
         );
 
+        self.update_id(id, Some(&state.name));
+        self.event(NodeEvent::Added(state.clone()));
         self.nodes.borrow_mut().insert(
             id,
             NodeTrackerObject { proxy: node, _listener: listener, class, state, device },
@@ -216,41 +316,70 @@ impl NodeTracker {
     }
 
     /// adds a device to be tracked
-    pub fn add_device(self: &Rc<Self>, id: u32, _props: &DictRef, device: Device) {
+    pub fn add_device(self: &Rc<Self>, id: u32, props: &DictRef, device: Device) {
         let listener = device
             .add_listener_local()
             .info({
                 let this = self.clone();
                 move |info| {
                     for param in info.params() {
-                        // we enumerate the route param if it changed
+                        // we enumerate the route and profile params if they changed
                         // subscribing doesn't cut it for some reason
-                        if param.id() == ParamType::Route
-                            && let Some(device) = this.devices.borrow().get(&id)
+                        if matches!(
+                            param.id(),
+                            ParamType::Route | ParamType::EnumProfile | ParamType::Profile
+                        ) && let Some(device) = this.devices.borrow_mut().get_mut(&id)
                         {
-                            device.proxy.enum_params(0, Some(ParamType::Route), 0, u32::MAX);
+                            // profiles which disappeared would otherwise stay listed
+                            if param.id() == ParamType::EnumProfile
+                                && device.profiles_flags.replace(param.flags())
+                                    != Some(param.flags())
+                            {
+                                device.profiles.profiles.clear();
+                            }
+
+                            device.proxy.enum_params(0, Some(param.id()), 0, u32::MAX);
                         }
                     }
                 }
             })
             .param({
                 let this = self.clone();
-                move |_, what, _, _, pod| {
-                    if let (ParamType::Route, Some(pod)) = (what, pod) {
-                        this.update_params_device(id, pod);
+                move |_, what, _, _, pod| match (what, pod) {
+                    (ParamType::Route, Some(pod)) => this.update_params_device(id, pod),
+                    (ParamType::EnumProfile | ParamType::Profile, Some(pod)) => {
+                        this.update_profiles(id, what, pod)
                     }
+                    _ => {}
                 }
             })
             .register();
 
-        device.enum_params(0, Some(ParamType::Route), 0, u32::MAX);
-        device.subscribe_params(&[ParamType::Route]); // does nothing but we do it anyways
+        for param in [ParamType::Route, ParamType::EnumProfile, ParamType::Profile] {
+            device.enum_params(0, Some(param), 0, u32::MAX);
+        }
+        // does nothing but we do it anyways
+        device.subscribe_params(&[ParamType::Route, ParamType::EnumProfile, ParamType::Profile]);
+
+        let profiles = DeviceProfiles {
+            id,
+            name: props.get("device.name").unwrap_or_default().to_owned(),
+            description: props.get("device.description").unwrap_or_default().to_owned(),
+            profiles: Vec::new(),
+            active: None,
+        };
 
-        debug!("adding device {id}");
+        debug!("adding device {id} ('{}')", profiles.name);
 
         self.devices.borrow_mut().insert(
             id,
-            DeviceTrackerObject { proxy: device, _listener: listener, indices: HashMap::new() },
+            DeviceTrackerObject {
+                proxy: device,
+                _listener: listener,
+                indices: HashMap::new(),
+                profiles,
+                profiles_flags: None,
+            },
         );
     }
 
@@ -259,6 +388,8 @@ impl NodeTracker {
         let result = self.nodes.borrow_mut().remove(&id); // for borrow lifetime
         if let Some(removed) = result {
             debug!("removing node {id} from tracker");
+            self.update_id(id, None);
+            self.event(NodeEvent::Removed(id));
             self.update(removed.class);
         }
 
@@ -274,14 +405,29 @@ impl NodeTracker {
         if let Some(node) = self.nodes.borrow_mut().get_mut(&id) {
             trace!("updating props for {id}");
             if node.state.update_props(props) {
-                changed = Some(node.class);
+                changed = Some((node.class, node.state.clone()));
             }
         } else {
             warn!("tried to update props for node {id} which is not tracked");
         }
 
-        if let Some(changed) = changed {
-            self.update(changed);
+        if let Some((class, state)) = changed {
+            self.update_id(id, Some(&state.name));
+            self.event(NodeEvent::Changed(state));
+            self.update(class);
+        }
+    }
+
+    /// updates the name under which a node can be looked up, none removes it
+    fn update_id(&self, id: u32, name: Option<&str>) {
+        let Ok(mut ids) = self.ids.write() else {
+            warn!("node id lookup was poisoned, cannot update node {id}");
+            return;
+        };
+
+        ids.retain(|_, other| *other != id);
+        if let Some(name) = name {
+            ids.insert(name.to_owned(), id);
         }
     }
 
@@ -331,20 +477,74 @@ impl NodeTracker {
                 .find(|node| node.device == Some(id) && node.state.route == Some(route))
             {
                 if node.state.update_params(&params) {
-                    changed = Some(node.class);
+                    changed = Some((node.class, node.state.clone()));
                 }
             } else {
                 debug!("received update for route {route} on device {id}, but no node consumed it");
             }
 
-            if let Some(changed) = changed {
-                self.update(changed);
+            if let Some((class, state)) = changed {
+                self.event(NodeEvent::Changed(state));
+                self.update(class);
             }
         } else {
             warn!("received incomplete device route param update")
         }
     }
 
+    /// updates a single available profile or the active profile of a device
+    fn update_profiles(&self, id: u32, what: ParamType, params: &Pod) {
+        trace!("updating device profiles for {id}");
+
+        let profile = match PodDeserializer::deserialize_any_from(params.as_bytes()) {
+            Err(e) => {
+                warn!("failed to deserialize profile for device: {e:?}");
+                return;
+            }
+            Ok((_, Value::Object(obj))) => DeviceProfile::parse(obj),
+            Ok((_, _)) => {
+                warn!("received non-object body for device profile");
+                return;
+            }
+        };
+
+        let Some(profile) = profile else {
+            warn!("received device profile without index for device {id}");
+            return;
+        };
+
+        let mut changed = None;
+
+        if let Some(device) = self.devices.borrow_mut().get_mut(&id) {
+            let profiles = &mut device.profiles;
+
+            if what == ParamType::Profile {
+                if profiles.active != Some(profile.index) {
+                    profiles.active = Some(profile.index);
+                    changed = Some(profiles.clone());
+                }
+            } else {
+                match profiles.profiles.binary_search_by_key(&profile.index, |p| p.index) {
+                    Ok(i) if profiles.profiles[i] == profile => {}
+                    Ok(i) => {
+                        profiles.profiles[i] = profile;
+                        changed = Some(profiles.clone());
+                    }
+                    Err(i) => {
+                        profiles.profiles.insert(i, profile);
+                        changed = Some(profiles.clone());
+                    }
+                }
+            }
+        } else {
+            warn!("received profile update for device {id} that does not exist");
+        }
+
+        if let Some(profiles) = changed {
+            self.update_profile(profiles);
+        }
+    }
+
     /// updates the params of a node if it is tracked
     fn update_params_node(&self, id: u32, params: &Pod) {
         let mut changed = None;
@@ -361,7 +561,7 @@ impl NodeTracker {
                 Err(e) => warn!("failed to deserialize params for {id}: {e:?}"),
                 Ok((_, Value::Object(obj))) => {
                     if node.state.update_params(&obj) {
-                        changed = Some(node.class);
+                        changed = Some((node.class, node.state.clone()));
                     }
                 }
                 Ok((_, _)) => {
@@ -372,8 +572,27 @@ impl NodeTracker {
             warn!("tried to update params for node {id} which is not tracked");
         }
 
-        if let Some(changed) = changed {
-            self.update(changed);
+        if let Some((class, state)) = changed {
+            self.event(NodeEvent::Changed(state));
+            self.update(class);
+        }
+    }
+
+    /// broadcasts an event for a single node
+    fn event(&self, event: NodeEvent) {
+        trace!("sending node event {event:?}");
+
+        if self.events.send(event).is_err() {
+            warn!("failed to send node event to channel");
+        }
+    }
+
+    /// broadcasts the profiles of a device
+    fn update_profile(&self, profiles: DeviceProfiles) {
+        trace!("sending profile update for device {}", profiles.id);
+
+        if self.profile_updates.send(profiles).is_err() {
+            warn!("failed to send profile update to channel");
         }
     }
 
@@ -399,13 +618,8 @@ impl NodeTracker {
         }
     }
 
-    /// set the volume of a node
-    pub fn set_volume(&self, name: &str, mut volume: Vec<f32>) {
-        // we assume the volume is in "visual" form, i.e. not linear like what pw tracks
-        for ele in &mut volume {
-            *ele = ele.max(0f32).powi(3); // the cube root seems what everyone uses
-        }
-
+    /// set the linear volume of a node
+    pub fn set_volume(&self, name: &str, volume: Vec<f32>) {
         self.set(
             name,
             object! {
@@ -436,6 +650,39 @@ impl NodeTracker {
         );
     }
 
+    /// switch the profile of a device to the one with the given index
+    pub fn set_profile(&self, name: &str, profile: u32) {
+        let state = self.devices.borrow();
+        let Some(device) = state.values().find(|obj| obj.profiles.name == name) else {
+            warn!("cannot set profile for device '{name}', it is not tracked");
+            return;
+        };
+
+        trace!("setting profile {profile} on device `{name}`");
+
+        let flags = PropertyFlags::empty();
+        let object = object! {
+            SpaTypes::ObjectParamProfile,
+            ParamType::Profile,
+            Property { key: SPA_PARAM_PROFILE_index, value: Value::Int(profile as i32), flags },
+            Property { key: SPA_PARAM_PROFILE_save, value: Value::Bool(true), flags },
+        };
+
+        let Ok(bytes) = PodSerializer::serialize(Cursor::new(Vec::new()), &Value::Object(object))
+            .map(|(c, _)| c.into_inner())
+        else {
+            error!("failed to serialize profile for device '{name}'");
+            return;
+        };
+
+        let Some(pod) = Pod::from_bytes(&bytes) else {
+            error!("failed to create pod from bytes for device '{name}'");
+            return;
+        };
+
+        device.proxy.set_param(ParamType::Profile, 0, pod);
+    }
+
     fn set(&self, name: &str, object: Object) {
         let state = self.nodes.borrow();
         let Some(node) = state.values().find(|obj| obj.state.name == name) else {
@@ -509,5 +756,26 @@ impl NodeTracker {
     pub fn trigger_update(&self) {
         self.update(NodeClass::Sink);
         self.update(NodeClass::Source);
+
+        let profiles = self
+            .devices
+            .borrow()
+            .values()
+            .map(|device| device.profiles.clone())
+            .collect::<Vec<_>>();
+
+        for profiles in profiles {
+            self.update_profile(profiles);
+        }
+    }
+
+    /// stops tracking all objects, which drops their proxies and listeners
+    pub fn clear(&self) {
+        self.nodes.borrow_mut().clear();
+        self.devices.borrow_mut().clear();
+
+        if let Ok(mut ids) = self.ids.write() {
+            ids.clear();
+        }
     }
 }
@@ -0,0 +1,193 @@
+use std::{cell::RefCell, collections::HashMap, io::Cursor};
+
+use anyhow::{Context, Result, anyhow};
+use log::{debug, trace, warn};
+use pipewire::{
+    core::CoreRc,
+    properties::properties,
+    spa::{
+        param::{
+            ParamType,
+            audio::{AudioFormat, AudioInfoRaw},
+        },
+        pod::{Object, Pod, Value, serialize::PodSerializer},
+        utils::{Direction, SpaTypes},
+    },
+    stream::{StreamFlags, StreamListener, StreamRc},
+};
+use tokio::sync::broadcast::Sender;
+
+struct PeakStream {
+    stream: StreamRc,
+    _listener: StreamListener<()>,
+}
+
+/// measures the peak level of nodes by capturing them with a stream, which is
+/// shared between everyone listening to the same node
+pub(crate) struct PeakTracker {
+    updates: Sender<(String, f32)>,
+
+    /// amount of listeners for each node name, these outlive reconnections
+    listeners: RefCell<HashMap<String, usize>>,
+    streams: RefCell<HashMap<String, PeakStream>>,
+    core: RefCell<Option<CoreRc>>,
+}
+
+impl PeakTracker {
+    pub fn new(updates: Sender<(String, f32)>) -> Self {
+        Self {
+            updates,
+            listeners: RefCell::new(HashMap::new()),
+            streams: RefCell::new(HashMap::new()),
+            core: RefCell::new(None),
+        }
+    }
+
+    /// uses a new connection to pipewire, starting the streams for all nodes
+    /// which are still listened to
+    pub fn connect(&self, core: CoreRc) {
+        *self.core.borrow_mut() = Some(core);
+
+        let names = self.listeners.borrow().keys().cloned().collect::<Vec<_>>();
+        for name in names {
+            self.start(&name);
+        }
+    }
+
+    /// starts measuring the peak of the node with the given name, if it isn't
+    /// measured already
+    pub fn attach(&self, name: &str) {
+        let first = {
+            let mut listeners = self.listeners.borrow_mut();
+            let count = listeners.entry(name.to_owned()).or_default();
+            *count += 1;
+
+            *count == 1
+        };
+
+        if first {
+            self.start(name);
+        }
+    }
+
+    /// stops measuring the peak of a node once nobody listens to it anymore
+    pub fn detach(&self, name: &str) {
+        let last = {
+            let mut listeners = self.listeners.borrow_mut();
+            let Some(count) = listeners.get_mut(name) else { return };
+            *count = count.saturating_sub(1);
+
+            if *count == 0 {
+                listeners.remove(name);
+                true
+            } else {
+                false
+            }
+        };
+
+        if last && let Some(peak) = self.streams.borrow_mut().remove(name) {
+            debug!("stopping to measure peak of node `{name}`");
+
+            if let Err(e) = peak.stream.disconnect() {
+                warn!("failed to disconnect peak stream of `{name}`: {e}");
+            }
+        }
+    }
+
+    fn start(&self, name: &str) {
+        match self.create(name) {
+            Ok(peak) => {
+                debug!("measuring peak of node `{name}`");
+                self.streams.borrow_mut().insert(name.to_owned(), peak);
+            }
+            Err(e) => warn!("failed to measure peak of node `{name}`: {e:#}"),
+        }
+    }
+
+    /// creates a stream which captures the given node (the monitor for sinks)
+    /// and broadcasts the highest sample of every buffer
+    fn create(&self, name: &str) -> Result<PeakStream> {
+        let core = self.core.borrow().clone().context("not connected to pipewire")?;
+
+        let stream = StreamRc::new(
+            core,
+            "liischte-peak",
+            properties! {
+                "media.type" => "Audio",
+                "media.category" => "Capture",
+                "target.object" => name,
+                "stream.capture.sink" => "true",
+                // this stream is not recording anything, see the capture tracker
+                "stream.monitor" => "true",
+                "node.dont-reconnect" => "true",
+            },
+        )
+        .context("failed to create peak stream")?;
+
+        let listener = stream
+            .add_local_listener_with_user_data(())
+            .process({
+                let updates = self.updates.clone();
+                let name = name.to_owned();
+
+                move |stream, _| {
+                    let Some(mut buffer) = stream.dequeue_buffer() else {
+                        trace!("peak stream of `{name}` has no buffer");
+                        return;
+                    };
+
+                    let Some(data) = buffer.datas_mut().first_mut() else { return };
+                    let (offset, size) =
+                        (data.chunk().offset() as usize, data.chunk().size() as usize);
+                    let Some(bytes) = data.data() else { return };
+                    let Some(samples) = bytes.get(offset..(offset + size).min(bytes.len())) else {
+                        return;
+                    };
+
+                    let peak = samples
+                        .chunks_exact(size_of::<f32>())
+                        .map(|sample| {
+                            f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]])
+                        })
+                        .fold(0f32, |peak, sample| peak.max(sample.abs()));
+
+                    // nobody may be listening between two updates, which is fine
+                    let _ = updates.send((name.clone(), peak.min(1f32)));
+                }
+            })
+            .register()
+            .context("failed to register peak stream listener")?;
+
+        // we only care about the format, pipewire converts the rest for us
+        let mut info = AudioInfoRaw::new();
+        info.set_format(AudioFormat::F32LE);
+
+        let object = Object {
+            type_: SpaTypes::ObjectParamFormat.as_raw(),
+            id: ParamType::EnumFormat.as_raw(),
+            properties: info.into(),
+        };
+
+        let bytes = PodSerializer::serialize(Cursor::new(Vec::new()), &Value::Object(object))
+            .map(|(c, _)| c.into_inner())
+            .map_err(|e| anyhow!("failed to serialize peak stream format: {e:?}"))?;
+        let pod = Pod::from_bytes(&bytes).context("failed to create pod for peak stream format")?;
+
+        stream
+            .connect(
+                Direction::Input,
+                None,
+                StreamFlags::AUTOCONNECT | StreamFlags::MAP_BUFFERS,
+                &mut [pod],
+            )
+            .context("failed to connect peak stream")?;
+
+        Ok(PeakStream { stream, _listener: listener })
+    }
+
+    /// stops all streams of the lost connection, but keeps the listeners
+    pub fn clear(&self) {
+        self.streams.borrow_mut().clear();
+        self.core.borrow_mut().take();
+    }
+}
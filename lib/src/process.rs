@@ -4,10 +4,10 @@ use anyhow::{Context, Result};
 use futures::StreamExt;
 use log::{trace, warn};
 use nix::{sys::signal::kill, unistd::Pid};
-use tokio::{fs, time::Instant};
+use tokio::fs;
 use tokio_stream::wrappers::ReadDirStream;
 
-use crate::{StaticStream, StreamContext};
+use crate::{StaticStream, StreamContext, util::polling::polling_interval};
 
 pub use nix::sys::signal::Signal as ProcessSignal;
 
@@ -55,8 +55,7 @@ pub async fn read_running_processes() -> Result<Vec<ProcessInfo>> {
 /// creates a stream which polls for actively running processes at the given
 /// interval
 pub fn listen_running_processes(polling: Duration) -> StaticStream<Vec<ProcessInfo>> {
-    let mut interval = tokio::time::interval_at(Instant::now(), polling);
-    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let interval = polling_interval(polling);
 
     futures::stream::unfold(interval, async |mut interval| {
         interval.tick().await;
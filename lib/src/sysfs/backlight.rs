@@ -1,11 +1,22 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use futures::StreamExt;
 use udev::MonitorBuilder;
+use zbus::{Connection, proxy};
 
 use crate::{StaticStream, StreamContext, util::udev::AsyncMonitorSocket};
 
 use super::Device;
 
+#[proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+trait LogindSession {
+    /// sets the brightness of a device, which is allowed for the active session
+    fn set_brightness(&self, subsystem: &str, name: &str, brightness: u32) -> zbus::Result<()>;
+}
+
 #[derive(Clone)]
 pub struct BacklightDevice {
     pub device: Device,
@@ -40,6 +51,23 @@ impl BacklightDevice {
             .map(|b| b as f64 / self.max as f64)
     }
 
+    /// sets the brightness (from 0 to 1) of the device through logind, as the
+    /// sysfs is only writable by root
+    pub async fn set_brightness(&self, brightness: f64) -> Result<()> {
+        let connection =
+            Connection::system().await.context("failed to connect to dbus system bus")?;
+        let proxy = LogindSessionProxy::new(&connection)
+            .await
+            .context("could not connect to logind session interface")?;
+
+        let value = (brightness.clamp(0f64, 1f64) * self.max as f64).round() as u32;
+
+        proxy
+            .set_brightness("backlight", &self.device.name, value)
+            .await
+            .with_context(|| format!("failed to set brightness of `{}`", self.device.name))
+    }
+
     /// creates a stream which listens to udev events for the given backlight
     /// and then reads the brightness state from the sysfs
     pub fn listen_brightness(self) -> Result<StaticStream<f64>> {
@@ -0,0 +1,71 @@
+use std::{path::Path, time::Duration};
+
+use anyhow::{Result, anyhow};
+use futures::StreamExt;
+use log::trace;
+
+use crate::{StaticStream, StreamContext, util::polling::polling_interval};
+
+use super::Device;
+
+/// reads all cpus which support frequency scaling from the sysfs
+pub async fn read_cpus() -> Result<Vec<Device>> {
+    let cpus = Device::read_devices_in(Path::new("/sys/devices/system/cpu"))
+        .await?
+        .into_iter()
+        // the directory also contains things like `cpuidle` which aren't cpus
+        .filter(|device| {
+            device.name.strip_prefix("cpu").is_some_and(|id| id.parse::<u32>().is_ok())
+        })
+        .filter(|device| device.path.join("cpufreq").exists())
+        .collect::<Vec<_>>();
+
+    if cpus.is_empty() {
+        return Err(anyhow!("`cpufreq` sysfs is required for this information"));
+    }
+
+    Ok(cpus)
+}
+
+/// reads the current frequency in MHz, averaged over the given cpus
+pub async fn read_cpu_frequency(cpus: &[Device]) -> Result<u64> {
+    let frequencies = futures::future::join_all(
+        cpus.iter().map(|cpu| cpu.read_device_attribute_int("cpufreq/scaling_cur_freq")),
+    )
+    .await
+    .into_iter()
+    // offline cpus cannot be read, but we still want the others
+    .filter_map(|result| result.ok())
+    .collect::<Vec<_>>();
+
+    if frequencies.is_empty() {
+        return Err(anyhow!("failed to read the frequency of any cpu"));
+    }
+
+    // the sysfs reports in kHz
+    Ok((frequencies.iter().sum::<i64>() / frequencies.len() as i64 / 1000) as u64)
+}
+
+/// creates a stream which polls the average frequency of the given cpus and
+/// fires if it changed
+pub fn listen_cpu_frequency(cpus: Vec<Device>, polling: Duration) -> StaticStream<u64> {
+    let interval = polling_interval(polling);
+
+    futures::stream::unfold((cpus, interval, None), async |(cpus, mut interval, last)| {
+        let mut next = last;
+
+        while next == last {
+            interval.tick().await;
+
+            trace!("polling frequency of {} cpus", cpus.len());
+            if let Some(frequency) =
+                read_cpu_frequency(&cpus).await.stream_log("cpu frequency stream")
+            {
+                next = Some(frequency);
+            };
+        }
+
+        Some((next?, (cpus, interval, next)))
+    })
+    .boxed()
+}
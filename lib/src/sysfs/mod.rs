@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{Context, Result};
 use futures::StreamExt;
@@ -18,6 +18,16 @@ pub mod power;
 #[cfg(feature = "backlight")]
 pub mod backlight;
 
+/// implementation of temperature information by polling the thermal sysfs
+/// https://www.kernel.org/doc/Documentation/ABI/testing/sysfs-class-thermal
+#[cfg(feature = "thermal")]
+pub mod thermal;
+
+/// implementation of cpu frequency information by polling the cpufreq sysfs
+/// https://www.kernel.org/doc/Documentation/cpu-freq/user-guide.txt
+#[cfg(feature = "cpufreq")]
+pub mod cpufreq;
+
 /// represents a device in the sysfs
 #[derive(Clone)]
 pub struct Device {
@@ -30,9 +40,16 @@ pub struct Device {
 impl Device {
     /// list all devices available in a given sysfs class
     async fn read_devices(class: &str) -> Result<Vec<Self>> {
-        let devices = fs::read_dir(PathBuf::from("/sys/class").join(class))
+        Self::read_devices_in(&PathBuf::from("/sys/class").join(class))
+            .await
+            .with_context(|| format!("`{class}` sysfs is required for this information"))
+    }
+
+    /// list all devices in a given sysfs directory
+    async fn read_devices_in(path: &Path) -> Result<Vec<Self>> {
+        let devices = fs::read_dir(path)
             .await
-            .context("`backlight` sysfs is required for backlight information")?;
+            .with_context(|| format!("failed to read directory `{}`", path.to_string_lossy()))?;
 
         Ok(ReadDirStream::new(devices)
             .filter_map(async |result| result.ok())
@@ -1,12 +1,11 @@
-use std::time::Duration;
+use std::{future, time::Duration};
 
-use anyhow::{Context, Result};
-use futures::StreamExt;
+use anyhow::{Context, Result, anyhow};
+use futures::{StreamExt, stream};
 use log::trace;
-use tokio::time::Instant;
 use udev::MonitorBuilder;
 
-use crate::{StaticStream, StreamContext};
+use crate::{StaticStream, StreamContext, util::polling::polling_interval};
 
 use crate::util::udev::AsyncMonitorSocket;
 
@@ -42,6 +41,29 @@ impl PowerDeviceKind {
     }
 }
 
+/// represents the charging status of a battery read from the sysfs
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BatteryStatus {
+    Charging,
+    Discharging,
+    /// the battery is on mains but not charging, e.g. because of a threshold
+    NotCharging,
+    Full,
+    Unknown,
+}
+
+impl BatteryStatus {
+    pub fn parse(string: &str) -> Self {
+        match string.trim() {
+            "Charging" => Self::Charging,
+            "Discharging" => Self::Discharging,
+            "Not charging" => Self::NotCharging,
+            "Full" => Self::Full,
+            _ => Self::Unknown,
+        }
+    }
+}
+
 impl PowerDevice {
     /// reads all power devices currently available from the sysfs
     pub async fn read_all() -> Result<Vec<Self>> {
@@ -126,28 +148,160 @@ impl BatteryPowerDevice {
             .map(|energy| energy as f64 / 100f64)
     }
 
-    /// creates a stream which polls the battery charge which is read now and
-    /// then from the sysfs
-    pub fn listen_charge(self, polling: Duration) -> StaticStream<f64> {
-        let mut interval = tokio::time::interval_at(Instant::now(), polling);
-        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    /// reads the charge as a percentage (0-1) of the design capacity, so a worn
+    /// battery never reaches full
+    pub async fn read_design_charge(&self) -> Result<f64> {
+        let device = &self.0.device;
+
+        let (now, design) = match device.read_device_attribute_int("energy_now").await {
+            Ok(now) => (now, device.read_device_attribute_int("energy_full_design").await?),
+            Err(_) => (
+                device.read_device_attribute_int("charge_now").await?,
+                device.read_device_attribute_int("charge_full_design").await?,
+            ),
+        };
+
+        if design <= 0 {
+            return Err(anyhow!("design capacity of device `{}` is zero", device.name));
+        }
+
+        // readings may briefly exceed the design capacity
+        Ok((now as f64 / design as f64).min(1f64))
+    }
+
+    /// reads the charge relative to the current or to the design capacity
+    async fn read_charge_relative(&self, design: bool) -> Result<f64> {
+        if design { self.read_design_charge().await } else { self.read_charge().await }
+    }
+
+    /// reads the health as a percentage (0-1), meaning how much of its design
+    /// capacity the battery can still hold, if the driver reports it
+    pub async fn read_health(&self) -> Option<f64> {
+        for (full, design) in
+            [("charge_full", "charge_full_design"), ("energy_full", "energy_full_design")]
+        {
+            let (Ok(full), Ok(design)) = (
+                self.0.device.read_device_attribute_int(full).await,
+                self.0.device.read_device_attribute_int(design).await,
+            ) else {
+                continue;
+            };
+
+            if design > 0 {
+                return Some(full as f64 / design as f64);
+            }
+        }
+
+        None
+    }
+
+    /// reads the amount of charge cycles, if the driver reports it
+    pub async fn read_cycle_count(&self) -> Option<u64> {
+        self.0
+            .device
+            .read_device_attribute_int("cycle_count")
+            .await
+            .ok()
+            .map(|cycles| cycles.max(0) as u64)
+    }
+
+    /// reads the charging status
+    pub async fn read_status(&self) -> Result<BatteryStatus> {
+        self.0
+            .device
+            .read_device_attribute_string("status")
+            .await
+            .map(|status| BatteryStatus::parse(&status))
+    }
+
+    /// creates a stream which polls the battery charge and status which are
+    /// read now and then from the sysfs. the charge is relative to the design
+    /// capacity if `design` is set
+    pub fn listen_charge(
+        self,
+        polling: Duration,
+        design: bool,
+    ) -> StaticStream<(f64, BatteryStatus)> {
+        let interval = polling_interval(polling);
 
         let bat = Box::leak(Box::new(self));
 
-        futures::stream::unfold((interval, -1f64), async |(mut interval, last)| {
+        futures::stream::unfold((interval, None, design), async |(mut interval, last, design)| {
             let mut next = last;
 
             while next == last {
                 interval.tick().await;
 
                 trace!("polling battery charge for device `{}`", bat.0.device.name);
-                if let Some(charge) = bat.read_charge().await.stream_log("battery charge stream") {
-                    next = charge;
+                if let Some(charge) =
+                    bat.read_charge_relative(design).await.stream_log("battery charge stream")
+                {
+                    // not all batteries report a status
+                    let status = bat.read_status().await.unwrap_or(BatteryStatus::Unknown);
+
+                    next = Some((charge, status));
                 };
             }
 
-            Some((next, (interval, next)))
+            next.map(|next| (next, (interval, Some(next), design)))
         })
         .boxed()
     }
+
+    /// creates a stream which reads the battery charge and status whenever
+    /// udev reports a change of the battery. because not all batteries report
+    /// every change, they are also polled now and then. the charge is relative
+    /// to the design capacity if `design` is set
+    pub fn listen_charge_events(
+        self,
+        polling: Duration,
+        design: bool,
+    ) -> Result<StaticStream<(f64, BatteryStatus)>> {
+        let socket = MonitorBuilder::new()?
+            .match_subsystem_devtype("power_supply", "power_supply")?
+            .listen()?;
+
+        let bat: &'static Self = Box::leak(Box::new(self));
+
+        let events = AsyncMonitorSocket::new(socket)?.filter_map(async |r| {
+            if r.context("received invalid udev event")
+                .stream_log("battery charge stream")?
+                .sysname()
+                .to_string_lossy()
+                == *bat.0.device.name
+            {
+                Some(())
+            } else {
+                None
+            }
+        });
+
+        let interval = polling_interval(polling);
+
+        let polls = stream::unfold(interval, async |mut interval| {
+            interval.tick().await;
+            Some(((), interval))
+        });
+
+        let mut last = None;
+
+        Ok(stream::select(events, polls)
+            .then(move |_| async move {
+                trace!("reading battery charge for device `{}`", bat.0.device.name);
+                let charge =
+                    bat.read_charge_relative(design).await.stream_log("battery charge stream")?;
+
+                // not all batteries report a status
+                Some((charge, bat.read_status().await.unwrap_or(BatteryStatus::Unknown)))
+            })
+            .filter_map(future::ready)
+            .filter(move |next| {
+                // we only report actual changes
+                let changed = last != Some(*next);
+                last = Some(*next);
+
+                future::ready(changed)
+            })
+            .boxed())
+    }
 }
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use futures::StreamExt;
+use log::trace;
+
+use crate::{StaticStream, StreamContext, util::polling::polling_interval};
+
+use super::Device;
+
+/// a thermal zone in the `thermal` sysfs
+#[derive(Clone)]
+pub struct ThermalZone {
+    pub device: Device,
+
+    /// the type of the zone, describing what it measures (e.g. `x86_pkg_temp`)
+    pub kind: String,
+}
+
+impl ThermalZone {
+    /// reads all thermal zones currently available from the sysfs
+    pub async fn read_all() -> Result<Vec<Self>> {
+        let devices = Device::read_devices("thermal").await?;
+
+        Ok(futures::future::join_all(
+            devices
+                .into_iter()
+                // the class also contains cooling devices which we don't care about
+                .filter(|this| this.name.starts_with("thermal_zone"))
+                .map(|this| async {
+                    let kind = this.read_device_attribute_string("type").await.ok()?;
+
+                    Some(Self { kind: kind.trim().to_owned(), device: this })
+                }),
+        )
+        .await
+        .into_iter()
+        .flatten()
+        .collect())
+    }
+
+    /// reads the current temperature in degrees celsius
+    pub async fn read_temperature(&self) -> Result<f64> {
+        self.device.read_device_attribute_int("temp").await.map(|temp| temp as f64 / 1000f64)
+    }
+
+    /// creates a stream which polls the temperature of the zone and fires if
+    /// it changed
+    pub fn listen_temperature(self, polling: Duration) -> StaticStream<f64> {
+        let interval = polling_interval(polling);
+
+        futures::stream::unfold((self, interval, None), async |(zone, mut interval, last)| {
+            let mut next = last;
+
+            while next == last {
+                interval.tick().await;
+
+                trace!("polling temperature for zone `{}`", zone.device.name);
+                if let Some(temp) =
+                    zone.read_temperature().await.stream_log("thermal temperature stream")
+                {
+                    next = Some(temp);
+                };
+            }
+
+            Some((next?, (zone, interval, next)))
+        })
+        .boxed()
+    }
+}
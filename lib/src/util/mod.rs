@@ -1,6 +1,7 @@
 use futures::Stream;
 use scan::ScanOwning;
 
+pub mod polling;
 pub mod scan;
 #[cfg(feature = "power")]
 pub mod udev;
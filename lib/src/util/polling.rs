@@ -0,0 +1,61 @@
+use std::{
+    hash::{BuildHasher, RandomState},
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+
+/// largest delay in milliseconds which is added to the start of polling
+/// intervals, zero disables it
+static JITTER: AtomicU64 = AtomicU64::new(0);
+
+/// sets the largest random delay added to the start of polling intervals, so
+/// that streams created at the same time don't all wake up at once. zero (the
+/// default) starts them immediately
+pub fn set_polling_jitter(jitter: Duration) {
+    JITTER.store(jitter.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// picks a delay below the given maximum from a random value
+fn jitter(max: Duration, random: u64) -> Duration {
+    let max = max.as_millis() as u64;
+
+    if max == 0 { Duration::ZERO } else { Duration::from_millis(random % max) }
+}
+
+/// creates an interval for polling at the given rate, whose first tick is
+/// delayed by a random jitter. missed ticks are delayed
+pub fn polling_interval(polling: Duration) -> Interval {
+    // the std hasher is randomly seeded, which is random enough for this
+    let random = RandomState::new().hash_one(Instant::now());
+    let delay = jitter(Duration::from_millis(JITTER.load(Ordering::Relaxed)), random);
+
+    let mut interval = tokio::time::interval_at(Instant::now() + delay, polling);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    interval
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::jitter;
+
+    #[test]
+    fn zero_jitter_starts_immediately() {
+        for random in [0, 1, 499, u64::MAX] {
+            assert_eq!(jitter(Duration::ZERO, random), Duration::ZERO);
+        }
+    }
+
+    #[test]
+    fn jitter_stays_below_maximum() {
+        let max = Duration::from_millis(500);
+
+        for random in [0, 1, 499, 500, 12345, u64::MAX] {
+            assert!(jitter(max, random) < max);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use futures::StreamExt;
+use log::{trace, warn};
+use reqwest::Client;
+use serde_json::Value;
+
+use crate::{StaticStream, StreamContext, util::polling::polling_interval};
+
+/// polling faster than this would only get us rate limited
+pub const MIN_POLLING: Duration = Duration::from_mins(5);
+/// time after which a request is given up
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// rough weather conditions which are distinguished
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeatherCondition {
+    Clear,
+    PartlyCloudy,
+    Cloudy,
+    Fog,
+    Drizzle,
+    Rain,
+    Snow,
+    Thunderstorm,
+    Unknown,
+}
+
+impl WeatherCondition {
+    /// maps a wmo weather code (used by open-meteo) to a condition
+    fn from_wmo(code: u64) -> Self {
+        match code {
+            0 => Self::Clear,
+            1 | 2 => Self::PartlyCloudy,
+            3 => Self::Cloudy,
+            45 | 48 => Self::Fog,
+            51..=57 => Self::Drizzle,
+            61..=67 | 80..=82 => Self::Rain,
+            71..=77 | 85 | 86 => Self::Snow,
+            95..=99 => Self::Thunderstorm,
+            _ => Self::Unknown,
+        }
+    }
+
+    /// maps a worldweatheronline code (used by wttr.in) to a condition
+    fn from_wwo(code: u64) -> Self {
+        match code {
+            113 => Self::Clear,
+            116 => Self::PartlyCloudy,
+            119 | 122 => Self::Cloudy,
+            143 | 248 | 260 => Self::Fog,
+            185 | 263 | 266 | 281 | 284 => Self::Drizzle,
+            176 | 293..=314 | 353..=359 => Self::Rain,
+            179 | 182 | 227 | 230 | 317..=350 | 362..=377 => Self::Snow,
+            200 | 386..=395 => Self::Thunderstorm,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// current weather conditions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weather {
+    /// temperature in degrees celsius
+    pub temperature: f64,
+    /// rough condition of the weather
+    pub condition: WeatherCondition,
+}
+
+impl Weather {
+    /// parses the current weather from the json of either open-meteo (with
+    /// `current=temperature_2m,weather_code` or `current_weather=true`) or
+    /// wttr.in (with `format=j1`)
+    fn parse(json: &Value) -> Option<Self> {
+        if let Some(current) = json.get("current") {
+            Some(Self {
+                temperature: current.get("temperature_2m")?.as_f64()?,
+                condition: WeatherCondition::from_wmo(current.get("weather_code")?.as_u64()?),
+            })
+        } else if let Some(current) = json.get("current_weather") {
+            Some(Self {
+                temperature: current.get("temperature")?.as_f64()?,
+                condition: WeatherCondition::from_wmo(current.get("weathercode")?.as_u64()?),
+            })
+        } else {
+            // wttr.in reports everything as strings
+            let current = json.get("current_condition")?.get(0)?;
+
+            Some(Self {
+                temperature: current.get("temp_C")?.as_str()?.parse().ok()?,
+                condition: WeatherCondition::from_wwo(
+                    current.get("weatherCode")?.as_str()?.parse().ok()?,
+                ),
+            })
+        }
+    }
+}
+
+/// fetches the current weather from the given url
+pub async fn read_weather(client: &Client, url: &str) -> Result<Weather> {
+    let json = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("failed to request weather from `{url}`"))?
+        .json::<Value>()
+        .await
+        .context("weather response is not valid json")?;
+
+    Weather::parse(&json)
+        .ok_or(anyhow!("weather response is neither in the open-meteo nor wttr.in format"))
+}
+
+/// creates a stream which polls the weather from the given url and fires if it
+/// changed. failed requests are skipped, polling is limited to `MIN_POLLING`
+pub fn listen_weather(url: String, polling: Duration) -> Result<StaticStream<Weather>> {
+    let client = Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .context("failed to create http client")?;
+
+    if polling < MIN_POLLING {
+        warn!("weather polling rate is too fast, using {}s instead", MIN_POLLING.as_secs());
+    }
+
+    let interval = polling_interval(polling.max(MIN_POLLING));
+
+    Ok(futures::stream::unfold(
+        (client, url, interval, None),
+        async |(client, url, mut interval, last)| {
+            let mut next = last;
+
+            while next == last {
+                interval.tick().await;
+
+                trace!("polling weather from `{url}`");
+                if let Some(weather) =
+                    read_weather(&client, &url).await.stream_log("weather stream")
+                {
+                    next = Some(weather);
+                };
+            }
+
+            Some((next?, (client, url, interval, next)))
+        },
+    )
+    .boxed())
+}
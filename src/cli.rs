@@ -20,6 +20,46 @@ pub enum Command {
         message: String,
     },
 
+    /// print the current state of a given module
+    Get {
+        /// module to query the state of
+        module: String,
+    },
+
+    /// print the current state of a given module in waybar's json format
+    Json {
+        /// module to query the state of
+        module: String,
+    },
+
+    /// run a hyprland dispatcher and print its reply
+    Dispatch {
+        /// dispatcher and its arguments, like `togglefloating` or `workspace 2`
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        dispatcher: Vec<String>,
+    },
+
+    /// reload the config and rebuild all modules
+    Reload,
+
+    /// show the osd of a given module
+    Osd {
+        /// module to show the osd of
+        module: String,
+    },
+
+    /// check the config file and the config of all enabled modules for errors
+    Check,
+
+    /// list the power, backlight and thermal devices which can be configured
+    Devices,
+
+    /// change which modules are shown and in what order
+    Modules {
+        /// identifiers of the modules to show
+        modules: Vec<String>,
+    },
+
     /// change the layer the bar occupies
     Layer {
         /// name of the layer, empty for the default one
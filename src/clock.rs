@@ -1,14 +1,27 @@
-use std::time::Duration;
+use std::{any::TypeId, hash::Hasher as _, time::Duration};
 
-use chrono::{DateTime, Local, Timelike};
+use chrono::{DateTime, Datelike, Local, Months, NaiveDate, Timelike};
+use futures::{StreamExt, stream};
 use iced::{
-    Subscription, Task, Theme, time,
-    widget::{column, text},
+    Background, Border, Length, Subscription, Task, Theme,
+    advanced::subscription::{EventStream, Hasher, Recipe, from_recipe},
+    alignment::Horizontal,
+    widget::{Column, Row, column, container, mouse_area, row, text},
 };
+use iced_winit::futures::BoxStream;
+use log::debug;
+use tokio::time::sleep;
 
-use crate::config::CONFIG;
+use crate::{config::CONFIG, module::ModuleId};
 
-pub type ClockMessage = DateTime<Local>;
+/// width of a single day in the calendar
+const CALENDAR_CELL: f32 = 24f32;
+
+#[derive(Debug, Clone)]
+pub enum ClockMessage {
+    Tick(DateTime<Local>),
+    ShowCalendar,
+}
 
 pub struct Clock {
     seconds: bool,
@@ -20,17 +33,50 @@ impl Clock {
         Self { time: Local::now(), seconds: CONFIG.clock.seconds }
     }
 
+    /// id under which the calendar is shown in the osd, it does not collide
+    /// with modules as they use the type of their messages
+    pub fn id() -> ModuleId {
+        TypeId::of::<ClockMessage>()
+    }
+
     pub fn subscribe(&self) -> Subscription<ClockMessage> {
-        time::every(Duration::from_secs(if self.seconds { 1 } else { 60 })).map(|_| Local::now())
+        from_recipe(ClockTicker(Duration::from_secs(if self.seconds { 1 } else { 60 })))
+            .map(ClockMessage::Tick)
     }
 
+    /// updates the clock, the calendar is shown by the caller
     pub fn update(&mut self, message: ClockMessage) -> Task<ClockMessage> {
-        self.time = message;
+        if let ClockMessage::Tick(time) = message {
+            self.time = time;
+        }
 
         Task::none()
     }
 
     pub fn render(&self) -> iced::Element<'_, ClockMessage, Theme, iced::Renderer> {
+        if CONFIG.clock.calendar {
+            mouse_area(self.render_time()).on_release(ClockMessage::ShowCalendar).into()
+        } else {
+            self.render_time()
+        }
+    }
+
+    fn render_time(&self) -> iced::Element<'_, ClockMessage, Theme, iced::Renderer> {
+        // there is enough space to show the time on one line
+        if CONFIG.looks.horizontal() {
+            return if self.seconds {
+                row![text!(
+                    "{:0>2}:{:0>2}:{:0>2}",
+                    self.time.hour(),
+                    self.time.minute(),
+                    self.time.second()
+                )]
+            } else {
+                row![text!("{:0>2}:{:0>2}", self.time.hour(), self.time.minute())]
+            }
+            .into();
+        }
+
         if self.seconds {
             column![
                 text!("{:0>2}", self.time.hour()),
@@ -42,4 +88,149 @@ impl Clock {
         }
         .into()
     }
+
+    /// renders the full date with a calendar of the current month, in which
+    /// today is highlighted
+    pub fn render_calendar(&self) -> iced::Element<'_, ClockMessage, Theme, iced::Renderer> {
+        fn cell<'a>(
+            content: impl ToString,
+            highlighted: bool,
+        ) -> iced::Element<'a, ClockMessage, Theme, iced::Renderer> {
+            container(text(content.to_string()).size(12))
+                .style(move |_| container::Style {
+                    background: highlighted.then_some(Background::Color(CONFIG.looks.semi)),
+                    border: Border::default().rounded(6),
+                    ..Default::default()
+                })
+                .width(CALENDAR_CELL)
+                .padding([2, 0])
+                .align_x(Horizontal::Center)
+                .into()
+        }
+
+        let today = self.time.date_naive();
+
+        let mut rows: Vec<iced::Element<'_, ClockMessage, Theme, iced::Renderer>> = vec![
+            Row::from_iter(
+                ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"].map(|weekday| cell(weekday, false)),
+            )
+            .into(),
+        ];
+
+        rows.extend(month_weeks(today).into_iter().map(|week| {
+            Row::from_iter(week.map(|day| match day {
+                Some(day) => cell(day, day == today.day()),
+                None => cell("", false),
+            }))
+            .into()
+        }));
+
+        column![
+            text(self.time.format("%H:%M").to_string()).size(20),
+            text(self.time.format("%A, %-d %B %Y").to_string()).size(12),
+            Column::from_vec(rows).spacing(2),
+        ]
+        .spacing(8)
+        .padding(12)
+        .width(Length::Fill)
+        .align_x(Horizontal::Center)
+        .into()
+    }
+}
+
+/// lays out the days of the month of the given date in weeks starting on
+/// monday, with the days outside of the month left empty
+fn month_weeks(date: NaiveDate) -> Vec<[Option<u32>; 7]> {
+    let first = date.with_day(1).expect("every month has a first day");
+    let days = first
+        .checked_add_months(Months::new(1))
+        .map(|next| (next - first).num_days() as u32)
+        .unwrap_or(31);
+
+    let offset = first.weekday().num_days_from_monday();
+
+    (0..(offset + days).div_ceil(7))
+        .map(|week| {
+            std::array::from_fn(|weekday| {
+                let day = (week * 7 + weekday as u32 + 1).checked_sub(offset)?;
+                (1..=days).contains(&day).then_some(day)
+            })
+        })
+        .collect()
+}
+
+/// returns the time until the wall clock reaches the next multiple of the period
+fn until_next_tick(now: DateTime<Local>, period: Duration) -> Duration {
+    let period = period.as_millis().max(1) as i64;
+    let elapsed = now.timestamp_millis().rem_euclid(period);
+
+    Duration::from_millis((period - elapsed) as u64)
+}
+
+/// fires with the current time whenever the wall clock passes a multiple of
+/// the period, so that the shown time never lags behind
+struct ClockTicker(Duration);
+
+impl Recipe for ClockTicker {
+    type Output = DateTime<Local>;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str("clock ticks");
+        state.write_u64(self.0.as_millis() as u64);
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("starting clock ticker");
+
+        // we sleep until each tick, so we don't drift away from the wall clock
+        stream::unfold(self.0, async |period| {
+            sleep(until_next_tick(Local::now(), period)).await;
+            Some((Local::now(), period))
+        })
+        .boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{Local, NaiveDate, TimeZone};
+
+    use super::{month_weeks, until_next_tick};
+
+    #[test]
+    fn ticks_on_the_next_boundary() {
+        let now = Local.timestamp_millis_opt(1_700_000_012_250).unwrap();
+
+        assert_eq!(until_next_tick(now, Duration::from_secs(1)), Duration::from_millis(750));
+        assert_eq!(until_next_tick(now, Duration::from_secs(60)), Duration::from_millis(27_750));
+    }
+
+    #[test]
+    fn month_is_laid_out_in_weeks() {
+        // october 2026 starts on a thursday
+        let weeks = month_weeks(NaiveDate::from_ymd_opt(2026, 10, 16).unwrap());
+
+        assert_eq!(weeks.len(), 5);
+        assert_eq!(weeks[0], [None, None, None, Some(1), Some(2), Some(3), Some(4)]);
+        assert_eq!(weeks[4], [Some(26), Some(27), Some(28), Some(29), Some(30), Some(31), None]);
+    }
+
+    #[test]
+    fn month_starting_on_monday_has_no_leading_gap() {
+        // february 2027 starts on a monday and has exactly four weeks
+        let weeks = month_weeks(NaiveDate::from_ymd_opt(2027, 2, 1).unwrap());
+
+        assert_eq!(weeks.len(), 4);
+        assert_eq!(weeks[0][0], Some(1));
+        assert_eq!(weeks[3][6], Some(28));
+    }
+
+    #[test]
+    fn ticks_a_full_period_later_on_a_boundary() {
+        let now = Local.timestamp_millis_opt(1_700_000_040_000).unwrap();
+
+        assert_eq!(until_next_tick(now, Duration::from_secs(60)), Duration::from_secs(60));
+    }
 }
@@ -2,17 +2,29 @@ use std::{
     collections::HashMap,
     env,
     fs::{self},
-    path::PathBuf,
+    hash::Hasher as _,
+    path::{Path, PathBuf},
     process::exit,
-    sync::LazyLock,
+    sync::{Arc, LazyLock, RwLock},
     time::Duration,
 };
 
 use anyhow::{Context, Result, anyhow};
-use iced::{Color, color};
+use futures::{StreamExt, stream};
+use iced::{
+    Color, Subscription,
+    advanced::subscription::{EventStream, Hasher, Recipe, from_recipe},
+    color,
+};
+use iced_winit::futures::BoxStream;
 use log::{debug, error, info};
 use lucide_icons::Icon;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::{Deserialize, Deserializer};
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver},
+    time::timeout,
+};
 use toml::Table;
 
 use crate::{
@@ -20,7 +32,8 @@ use crate::{
         audio::AUDIO_MODULE_IDENTIFIER, network::NETWORK_MODULE_IDENTIFIER,
         power::POWER_MODULE_IDENTIFIER,
     },
-    ui::window::WindowLayer,
+    osd::OsdPosition,
+    ui::window::{BarAnchor, WindowLayer},
 };
 
 /// path where the config is read from
@@ -36,6 +49,86 @@ fn config_path() -> Result<PathBuf> {
     }
 }
 
+/// path of a file in which state is kept across restarts
+pub fn state_path(name: &str) -> Result<PathBuf> {
+    if let Ok(state) = env::var("XDG_STATE_HOME") {
+        Ok(PathBuf::from(state).join("liischte").join(name))
+    } else if let Ok(home) = env::var("HOME") {
+        Ok(PathBuf::from(home).join(".local/state/liischte").join(name))
+    } else {
+        Err(anyhow!("$XDG_STATE_HOME and $HOME are both not defined"))
+    }
+}
+
+/// time the config file has to stay untouched before it is reloaded, as editors
+/// often write to it multiple times when saving
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// returns a subscription which fires whenever the config file was changed
+pub fn watch() -> Subscription<()> {
+    from_recipe(ConfigWatcher)
+}
+
+struct ConfigWatcher;
+
+impl Recipe for ConfigWatcher {
+    type Output = ();
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str("config file events");
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("staring config file watcher");
+
+        let (watcher, receiver) = match watch_config_file() {
+            Ok(watch) => watch,
+            Err(e) => {
+                error!("failed to watch config file: {e:#}");
+                return stream::empty().boxed();
+            }
+        };
+
+        // the watcher is kept in the state, as dropping it stops watching
+        stream::unfold((watcher, receiver), |(watcher, mut receiver)| async move {
+            receiver.recv().await?;
+            while let Ok(Some(())) = timeout(WATCH_DEBOUNCE, receiver.recv()).await {}
+
+            Some(((), (watcher, receiver)))
+        })
+        .boxed()
+    }
+}
+
+/// starts watching the config file, which is done through its directory such
+/// that saves which replace the file are noticed too
+fn watch_config_file() -> Result<(RecommendedWatcher, UnboundedReceiver<()>)> {
+    let path = config_path()?;
+
+    let name = path.file_name().context("config path has no file name")?.to_owned();
+    let directory =
+        path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        if let Ok(event) = event
+            && !event.kind.is_access()
+            && event.paths.iter().any(|path| path.file_name() == Some(&name))
+        {
+            sender.send(()).ok();
+        }
+    })
+    .context("failed to create file watcher")?;
+
+    watcher
+        .watch(directory, RecursiveMode::NonRecursive)
+        .with_context(|| format!("failed to watch `{}`", directory.to_string_lossy()))?;
+
+    info!("watching config file at `{}` for changes", path.to_string_lossy());
+    Ok((watcher, receiver))
+}
+
 /// deserializes a color from a toml string
 pub fn deserialize_color<'de, D>(deserializer: D) -> Result<Color, D::Error>
 where
@@ -57,6 +150,24 @@ where
     Icon::from_name(&string).ok_or(serde::de::Error::custom("not a valid lucide icon name"))
 }
 
+/// deserializes a map with integer keys from a toml table (whose keys are
+/// always strings)
+pub fn deserialize_int_map<'de, D, T>(deserializer: D) -> Result<HashMap<i64, T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    HashMap::<String, T>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(key, value)| {
+            key.trim()
+                .parse::<i64>()
+                .map(|key| (key, value))
+                .map_err(|_| serde::de::Error::custom(format!("`{key}` is not an integer key")))
+        })
+        .collect()
+}
+
 /// deserializes a duration from a toml integer as seconds
 pub fn deserialize_duration_seconds<'de, D>(deserializer: D) -> Result<Duration, D::Error>
 where
@@ -65,6 +176,16 @@ where
     u64::deserialize(deserializer).map(Duration::from_secs)
 }
 
+/// deserializes a duration from a toml integer as milliseconds
+pub fn deserialize_duration_millis<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    u64::deserialize(deserializer).map(Duration::from_millis)
+}
+
+/// the config read on startup, which is used for things that cannot change at
+/// runtime
 pub static CONFIG: LazyLock<Config> = LazyLock::new(|| {
     debug!("starting configuration read");
 
@@ -78,23 +199,63 @@ pub static CONFIG: LazyLock<Config> = LazyLock::new(|| {
     }
 });
 
-#[derive(Deserialize)]
+/// the config currently in use, which starts out as `CONFIG` and is swapped
+/// when the config is reloaded
+pub static RUNTIME_CONFIG: LazyLock<RuntimeConfig> =
+    LazyLock::new(|| RuntimeConfig(RwLock::new(Arc::new(CONFIG.clone()))));
+
+/// holds a config which can be swapped at runtime
+pub struct RuntimeConfig(RwLock<Arc<Config>>);
+
+impl RuntimeConfig {
+    /// returns the config currently in use
+    pub fn get(&self) -> Arc<Config> {
+        self.0.read().expect("config lock was poisoned").clone()
+    }
+
+    /// re-reads the config file and uses it from now on, the old one stays in
+    /// use if it cannot be read
+    pub fn reload(&self) -> Result<()> {
+        let config = Config::read()?.unwrap_or_default();
+        *self.0.write().expect("config lock was poisoned") = Arc::new(config);
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Clone)]
 #[serde(default)]
 pub struct Config {
     /// layer namespace to use (with `-osd` for the osd)
     pub namespace: String,
     /// layer to show bar on
     pub layer: WindowLayer,
-    /// whether to show the bar on the left instead of the right
+    /// whether to show the bar on the left instead of the right (or on the
+    /// bottom instead of the top for horizontal bars)
     pub right: bool,
-    /// output to show the bar on (name, or description with a `desc:` prefix)
+    /// output to show the bar on (name, description with a `desc:` prefix, or a
+    /// pattern matching either with a `regex:` prefix)
     /// `active` for the active monitor
     pub output: String,
+    /// whether to show the bar on all outputs matching `output` instead of
+    /// only the first one
+    pub all_outputs: bool,
+    /// whether to show the bar on the active output if no output matches
+    /// `output` for a while
+    pub output_fallback: bool,
     /// whether the ipc socket is enabled
     pub ipc: bool,
+    /// whether the config is reloaded when the config file changes
+    pub watch: bool,
+    /// largest random delay in milliseconds before polling starts, so not all
+    /// modules wake up at once
+    #[serde(deserialize_with = "deserialize_duration_millis")]
+    pub polling_jitter: Duration,
 
     /// looks of the bar
     pub looks: ConfigLooks,
+    /// colors for certain states shown by modules
+    pub colors: ConfigColors,
 
     /// parameters for the osd
     pub osd: ConfigOsd,
@@ -117,8 +278,13 @@ impl Default for Config {
             layer: WindowLayer::Top,
             right: false,
             output: "active".to_string(),
+            all_outputs: false,
+            output_fallback: false,
             ipc: true,
+            watch: true,
+            polling_jitter: Duration::from_millis(500),
             looks: ConfigLooks::default(),
+            colors: ConfigColors::default(),
             osd: ConfigOsd::default(),
             hyprland: ConfigHyprland::default(),
             clock: ConfigClock::default(),
@@ -143,33 +309,43 @@ impl Config {
 
         info!("reading config file from `{}`", path.to_string_lossy());
 
-        Ok(Some(
+        let table: Table =
             toml::from_str(&fs::read_to_string(path).context("failed to read config file")?)
-                .context("cannot deserialize config file")?,
-        ))
+                .context("cannot deserialize config file")?;
+
+        // we need to know which colors were set explicitly to apply the theme
+        let looks = table.get("looks").and_then(|looks| looks.as_table()).cloned();
+        let colors = table.get("colors").and_then(|colors| colors.as_table()).cloned();
+
+        let mut config: Config = table.try_into().context("cannot deserialize config file")?;
+        config.looks.apply_theme(&looks.unwrap_or_default())?;
+        if let Some(ref theme) = config.looks.theme {
+            config.colors.apply_theme(theme, &colors.unwrap_or_default());
+        }
+
+        Ok(Some(config))
     }
 
-    pub fn module<'de, T>(&self, name: &str) -> T
+    /// reads the config of a given module, the default is used if it is absent
+    pub fn module<'de, T>(&self, name: &str) -> Result<T>
     where
         T: Deserialize<'de> + Default,
     {
         if let Some(config) = self.module.get(name) {
             Table::try_into(config.clone())
-                .with_context(|| format!("cannot deserialize status config for `{name}`"))
-                .map_err(|e| {
-                    error!("{e:?}");
-                    exit(1)
-                })
-                .expect("should have exit")
+                .with_context(|| format!("cannot deserialize module config for `{name}`"))
         } else {
-            T::default()
+            Ok(T::default())
         }
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 #[serde(default)]
 pub struct ConfigLooks {
+    /// built-in theme providing the colors which are not set explicitly
+    pub theme: Option<String>,
+
     /// main foreground color
     #[serde(deserialize_with = "deserialize_color")]
     pub foreground: Color,
@@ -188,16 +364,203 @@ pub struct ConfigLooks {
 
     /// font to use for text on the bar
     pub font: String,
+    /// default size of text on the bar
+    pub text_size: f32,
+    /// size of icons on the bar
+    pub icon_size: f32,
 
     /// padding of the bar to the side
     pub padding: u32,
-    /// width of the bar
+    /// width of the bar (or its height for horizontal bars)
     pub width: u32,
+    /// whether the bar is shown vertically on the side or horizontally
+    pub orientation: Orientation,
+    /// where the bar is placed along the edge of the screen
+    pub anchor: BarAnchor,
+    /// length of the bar if it does not fill the whole edge
+    pub length: u32,
+
+    /// spacing between the modules within a group
+    pub module_spacing: f32,
+    /// spacing between the groups of the bar
+    pub group_spacing: f32,
+    /// whether to show separators between the groups
+    pub separators: bool,
+    /// how the separators are drawn
+    pub separator_style: SeparatorStyle,
+    /// width of the separators
+    pub separator_width: f32,
+}
+
+impl ConfigLooks {
+    /// returns whether the bar is laid out horizontally
+    pub fn horizontal(&self) -> bool {
+        self.orientation == Orientation::Horizontal
+    }
+
+    /// returns the length of the bar, none if it fills the whole edge
+    pub fn extent(&self) -> Option<u32> {
+        if self.anchor == BarAnchor::Fill { None } else { Some(self.length) }
+    }
+
+    /// replaces the colors which are not contained in the given looks table
+    /// with the ones of the configured theme
+    fn apply_theme(&mut self, explicit: &Table) -> Result<()> {
+        let Some(ref name) = self.theme else { return Ok(()) };
+
+        let theme = match name.as_str() {
+            "nord" => Self::nord(),
+            "gruvbox" => Self::gruvbox(),
+            "mono" => Self::mono(),
+            _ => {
+                return Err(anyhow!(
+                    "theme `{name}` does not exist, available themes are `{}`",
+                    THEMES.join("`, `")
+                ));
+            }
+        };
+
+        debug!("using colors of theme `{name}`");
+
+        if !explicit.contains_key("foreground") {
+            self.foreground = theme.foreground;
+        }
+        if !explicit.contains_key("semi") {
+            self.semi = theme.semi;
+        }
+        if !explicit.contains_key("background") {
+            self.background = theme.background;
+        }
+        if !explicit.contains_key("border") {
+            self.border = theme.border;
+        }
+
+        Ok(())
+    }
+
+    /// colors of the nord palette
+    fn nord() -> Self {
+        Self {
+            foreground: color!(0xECEFF4),
+            semi: color!(0xD8DEE9, 0.6),
+            background: color!(0x2E3440, 0.8),
+            border: color!(0x4C566A),
+            ..Default::default()
+        }
+    }
+
+    /// colors of the gruvbox dark palette
+    fn gruvbox() -> Self {
+        Self {
+            foreground: color!(0xEBDBB2),
+            semi: color!(0xEBDBB2, 0.6),
+            background: color!(0x282828, 0.8),
+            border: color!(0x665C54),
+            ..Default::default()
+        }
+    }
+
+    /// opaque black and white colors
+    fn mono() -> Self {
+        Self {
+            foreground: color!(0xFFFFFF),
+            semi: color!(0xFFFFFF, 0.5),
+            background: color!(0x000000),
+            border: color!(0xFFFFFF),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct ConfigColors {
+    /// color for states which need attention right away
+    #[serde(deserialize_with = "deserialize_color")]
+    pub critical: Color,
+    /// color for states which might need attention
+    #[serde(deserialize_with = "deserialize_color")]
+    pub warning: Color,
+    /// color for indicators which should stand out
+    #[serde(deserialize_with = "deserialize_color")]
+    pub accent: Color,
+}
+
+impl ConfigColors {
+    /// replaces the colors which are not contained in the given colors table
+    /// with the ones of the given theme, which has been checked to exist
+    fn apply_theme(&mut self, name: &str, explicit: &Table) {
+        let theme = match name {
+            "nord" => Self::nord(),
+            "gruvbox" => Self::gruvbox(),
+            "mono" => Self::mono(),
+            _ => return,
+        };
+
+        if !explicit.contains_key("critical") {
+            self.critical = theme.critical;
+        }
+        if !explicit.contains_key("warning") {
+            self.warning = theme.warning;
+        }
+        if !explicit.contains_key("accent") {
+            self.accent = theme.accent;
+        }
+    }
+
+    /// colors of the nord palette
+    fn nord() -> Self {
+        Self { critical: color!(0xBF616A), warning: color!(0xEBCB8B), accent: color!(0xBF616A) }
+    }
+
+    /// colors of the gruvbox dark palette
+    fn gruvbox() -> Self {
+        Self { critical: color!(0xFB4934), warning: color!(0xFABD2F), accent: color!(0xFB4934) }
+    }
+
+    /// no colors at all
+    fn mono() -> Self {
+        Self { critical: color!(0xFFFFFF), warning: color!(0xFFFFFF), accent: color!(0xFFFFFF) }
+    }
+}
+
+impl Default for ConfigColors {
+    fn default() -> Self {
+        Self { critical: color!(0xF05050), warning: color!(0xF0B040), accent: color!(0xF05050) }
+    }
+}
+
+/// names of the built-in themes
+const THEMES: [&str; 3] = ["nord", "gruvbox", "mono"];
+
+/// direction in which the bar spans the screen
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Orientation {
+    #[default]
+    Vertical,
+    Horizontal,
+}
+
+/// how the separators between the groups are drawn
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SeparatorStyle {
+    /// a rule across the bar
+    #[default]
+    Line,
+    /// a small dot
+    Dots,
+    /// only the spacing around the separator
+    Space,
+    /// no separators at all, same as disabling them
+    None,
 }
 
 impl Default for ConfigLooks {
     fn default() -> Self {
         Self {
+            theme: None,
             foreground: color!(0xFFFFFF),
             semi: color!(0xFFFFFF, 0.6),
             background: color!(0x000000, 0.6),
@@ -205,12 +568,22 @@ impl Default for ConfigLooks {
             tone_opacity: 0.25,
             padding: 10,
             width: 40,
+            orientation: Orientation::Vertical,
+            anchor: BarAnchor::Fill,
+            length: 600,
+            module_spacing: 4.0,
+            group_spacing: 12.0,
+            separators: true,
+            separator_style: SeparatorStyle::Line,
+            separator_width: 32.0,
             font: "JetBrains Mono".to_string(),
+            text_size: 16.0,
+            icon_size: 24.0,
         }
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 #[serde(default)]
 pub struct ConfigOsd {
     /// is the osd enabled
@@ -225,22 +598,44 @@ pub struct ConfigOsd {
     /// time the osd hides when respawning in millis
     /// this is used such that the compositor has time to show an animation
     pub respawn_time: u64,
+
+    /// time the osd takes to fade in and out in millis, `0` disables fading
+    pub fade_time: u64,
+
+    /// width of interactive osds (like pickers) and tooltips
+    pub interactive_width: u32,
+
+    /// show the tooltip of a module in the osd when hovering it
+    pub tooltips: bool,
+
+    /// where the osd is shown on the screen
+    pub position: OsdPosition,
 }
 
 impl Default for ConfigOsd {
     fn default() -> Self {
-        Self { enabled: true, layer: WindowLayer::Overlay, timeout: 4000, respawn_time: 200 }
+        Self {
+            enabled: true,
+            layer: WindowLayer::Overlay,
+            timeout: 4000,
+            respawn_time: 200,
+            fade_time: 150,
+            interactive_width: 200,
+            tooltips: true,
+            position: OsdPosition::FollowBar,
+        }
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 #[serde(default)]
 pub struct ConfigHyprland {
     /// enable hyprland workspace indicator
     pub enabled: bool,
 
-    /// id of the monitor to show workspaces for
-    pub monitor: u64,
+    /// id or name of the monitor to show workspaces for, the monitor the bar is
+    /// shown on is used if not set
+    pub monitor: Option<HyprlandMonitor>,
     /// whether to show fullscreen status in bar
     pub fullscreen: bool,
 
@@ -250,31 +645,52 @@ pub struct ConfigHyprland {
     pub border: f32,
     /// radius of the indicators
     pub rounding: f32,
+
+    /// labels to show inside the indicators of certain workspace ids
+    #[serde(deserialize_with = "deserialize_int_map")]
+    pub labels: HashMap<i64, String>,
+    /// workspace ids which are always shown, even if they don't exist
+    pub persistent: Vec<i64>,
+    /// whether to show the amount of windows in the corner of the indicators
+    pub show_counts: bool,
+}
+
+/// a hyprland monitor, by its id or by its name which stays the same across
+/// restarts
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum HyprlandMonitor {
+    Id(u64),
+    Name(String),
 }
 
 impl Default for ConfigHyprland {
     fn default() -> Self {
         Self {
             enabled: true,
-            monitor: 0,
+            monitor: None,
             fullscreen: true,
             size: 17f32,
             border: 1.5f32,
             rounding: 6f32,
+            labels: HashMap::new(),
+            persistent: Vec::new(),
+            show_counts: false,
         }
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 #[serde(default)]
 pub struct ConfigClock {
     /// whether to show the seconds indicator
-    /// (minutes might be inaccurate if disabled)
     pub seconds: bool,
+    /// whether clicking the clock shows the date and a calendar in the osd
+    pub calendar: bool,
 }
 
 impl Default for ConfigClock {
     fn default() -> Self {
-        Self { seconds: true }
+        Self { seconds: true, calendar: true }
     }
 }
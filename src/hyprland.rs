@@ -1,25 +1,31 @@
-use std::hash::Hasher as _;
+use std::{collections::HashMap, hash::Hasher as _};
 
 use anyhow::{Context, Result};
-use futures::{StreamExt, stream};
 use iced::Task;
 use iced::mouse::ScrollDelta;
-use iced::widget::{Column, container, mouse_area};
+use iced::alignment::{Horizontal, Vertical};
+use iced::widget::{Column, Row, container, mouse_area, stack, text};
 use iced::{
     Background, Border, Color, Radius, Subscription, Theme,
     advanced::subscription::{EventStream, Hasher, Recipe, from_recipe},
-    widget::{Space, container::Style},
+    widget::container::Style,
+    window::Id as SurfaceId,
 };
 use iced_winit::futures::BoxStream;
 use liischte_lib::StreamContext;
-use liischte_lib::hyprland::{HyprlandInstance, WorkspaceState};
-use log::debug;
+use liischte_lib::hyprland::{HyprlandInstance, MonitorState, WorkspaceState};
+use log::{debug, error, info, warn};
+use serde_json::Value;
 
-use crate::config::{CONFIG, ConfigHyprland};
+use crate::config::{CONFIG, ConfigHyprland, HyprlandMonitor};
+use crate::ipc::IpcRequest;
+use crate::ui::empty;
 
 #[derive(Debug, Clone)]
 pub enum HyprlandMessage {
-    State(i64, Vec<WorkspaceState>),
+    State(u64, i64, Vec<WorkspaceState>),
+    Monitor(SurfaceId, u64),
+    Monitors(Vec<MonitorState>),
     SelectAbsolute(i64),
     SelectRelative(i64),
     Ok,
@@ -29,8 +35,10 @@ pub struct Hyprland {
     config: &'static ConfigHyprland,
     instance: HyprlandInstance,
 
-    selected: i64,
-    workspaces: Vec<WorkspaceState>,
+    fallback: u64,           // monitor shown on bars whose output is not resolved yet
+    configured: Option<u64>, // id of the configured monitor, if it exists
+    monitors: HashMap<SurfaceId, u64>,
+    workspaces: HashMap<u64, (i64, Vec<WorkspaceState>)>, // selected and all workspaces per monitor
 }
 
 impl Hyprland {
@@ -41,28 +49,127 @@ impl Hyprland {
             "failed read environment for hyprland instance signature, are you running inside it?",
         )?;
 
-        let selected = instance.get_active_workspace().await?.id;
+        let active = instance.get_active_workspace().await?;
 
-        let mut workspaces = instance.get_all_workspaces().await?;
-        workspaces.retain(|state| state.monitor_id == Some(config.monitor) && state.id >= 0);
+        let configured = match &config.monitor {
+            Some(HyprlandMonitor::Id(id)) => Some(*id),
+            Some(HyprlandMonitor::Name(name)) => {
+                let monitors = instance.get_all_monitors().await?;
+                let id = find_monitor(&monitors, name);
+
+                if id.is_none() {
+                    warn!("hyprland monitor `{name}` does not exist, using the bar's monitor");
+                }
+
+                id
+            }
+            None => None,
+        };
+
+        // until we know the output of the bar, we use the focused monitor
+        let monitor = configured.or(active.monitor_id).unwrap_or_default();
+
+        let mut workspaces = instance.get_monitor_workspaces(monitor).await?;
         workspaces.sort_by(|a, b| a.id.cmp(&b.id));
 
-        Ok(Self { config, instance, selected, workspaces })
+        Ok(Self {
+            config,
+            instance,
+            fallback: monitor,
+            configured,
+            monitors: HashMap::new(),
+            workspaces: HashMap::from([(monitor, (active.id, workspaces))]),
+        })
+    }
+
+    /// resolves the monitor to show the workspaces for on a bar based on the
+    /// name of the output it is shown on (`None` for the focused one)
+    /// the configured monitor takes precedence, but a named one may not exist
+    pub fn set_output(&self, surface: SurfaceId, output: Option<String>) -> Task<HyprlandMessage> {
+        let instance = self.instance.clone();
+
+        Task::future(async move {
+            let Some(monitors) = instance.get_all_monitors().await.stream_log("hl monitors") else {
+                return HyprlandMessage::Ok;
+            };
+
+            monitors
+                .iter()
+                .find(|monitor| match output {
+                    Some(ref name) => monitor.name == *name,
+                    None => monitor.focused,
+                })
+                .map(|monitor| HyprlandMessage::Monitor(surface, monitor.id))
+                .unwrap_or(HyprlandMessage::Ok)
+        })
+    }
+
+    /// forgets about a bar which was closed
+    pub fn remove_surface(&mut self, surface: SurfaceId) {
+        self.monitors.remove(&surface);
+    }
+
+    /// returns the monitor whose workspaces are shown on a bar
+    fn monitor(&self, surface: SurfaceId) -> u64 {
+        self.configured.or(self.monitors.get(&surface).copied()).unwrap_or(self.fallback)
     }
 
     pub fn subscribe(&self) -> Subscription<HyprlandMessage> {
-        from_recipe(WorkspaceMonitor(self.instance.clone(), self.config.monitor))
-            .map(|(selected, state)| HyprlandMessage::State(selected, state))
+        let mut monitors =
+            self.monitors.keys().map(|surface| self.monitor(*surface)).collect::<Vec<_>>();
+        monitors.push(self.configured.unwrap_or(self.fallback));
+        monitors.sort();
+        monitors.dedup();
+
+        let mut subscriptions = monitors
+            .into_iter()
+            .map(|monitor| {
+                from_recipe(WorkspaceMonitor(self.instance.clone(), monitor)).with(monitor).map(
+                    |(monitor, (selected, state))| HyprlandMessage::State(monitor, selected, state),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // a named monitor gets a new id when it is reconnected
+        if let Some(HyprlandMonitor::Name(_)) = self.config.monitor {
+            subscriptions.push(
+                from_recipe(MonitorListMonitor(self.instance.clone()))
+                    .map(HyprlandMessage::Monitors),
+            );
+        }
+
+        Subscription::batch(subscriptions)
     }
 
     pub fn update(&mut self, message: HyprlandMessage) -> Task<HyprlandMessage> {
         match message {
-            HyprlandMessage::State(selected, mut workspaces) => {
+            HyprlandMessage::State(monitor, selected, mut workspaces) => {
                 // sort by id if they are created out of order
                 workspaces.sort_by(|a, b| a.id.cmp(&b.id));
 
-                self.selected = selected;
-                self.workspaces = workspaces;
+                self.workspaces.insert(monitor, (selected, workspaces));
+            }
+            HyprlandMessage::Monitor(surface, monitor) => {
+                if monitor != self.monitor(surface) {
+                    debug!("showing workspaces of hyprland monitor {monitor}");
+                }
+
+                // a subscription is started and reports the state for this monitor
+                self.monitors.insert(surface, monitor);
+            }
+            HyprlandMessage::Monitors(monitors) => {
+                if let Some(HyprlandMonitor::Name(ref name)) = self.config.monitor {
+                    let configured = find_monitor(&monitors, name);
+
+                    if configured != self.configured {
+                        match configured {
+                            Some(id) => info!("hyprland monitor `{name}` now has id {id}"),
+                            None => info!("hyprland monitor `{name}` was removed"),
+                        }
+                    }
+
+                    self.configured = configured;
+                }
             }
             HyprlandMessage::SelectAbsolute(id) => {
                 let instance = self.instance.clone();
@@ -90,11 +197,19 @@ impl Hyprland {
     fn render_indicator(
         &self,
         state: &WorkspaceState,
+        selected: i64,
     ) -> iced::Element<'_, HyprlandMessage, Theme, iced::Renderer> {
-        let (background, border) = match (state.id == self.selected, state.window_amount > 0) {
-            (true, _) => (CONFIG.looks.semi, self.config.border),
-            (false, true) => (CONFIG.looks.foreground, 0f32),
-            _ => (Color::TRANSPARENT, self.config.border),
+        let (background, border, label) = match (state.id == selected, state.window_amount > 0) {
+            (true, _) => (CONFIG.looks.semi, self.config.border, CONFIG.looks.foreground),
+            // urgent workspaces are filled with the critical color to stand out
+            (false, _) if state.urgent => {
+                (CONFIG.colors.critical, 0f32, Color { a: 1f32, ..CONFIG.looks.background })
+            }
+            // the label has to stand out from the filled indicator
+            (false, true) => {
+                (CONFIG.looks.foreground, 0f32, Color { a: 1f32, ..CONFIG.looks.background })
+            }
+            _ => (Color::TRANSPARENT, self.config.border, CONFIG.looks.foreground),
         };
 
         let radius = if state.fullscreen && self.config.fullscreen {
@@ -103,8 +218,19 @@ impl Hyprland {
             self.config.rounding
         };
 
-        mouse_area(container(Space::new(self.config.size, self.config.size)).style(move |_| {
-            Style {
+        let content: iced::Element<'_, HyprlandMessage, Theme, iced::Renderer> =
+            if let Some(name) = self.config.labels.get(&state.id) {
+                text(name).size(self.config.size * 0.6).color(label).into()
+            } else {
+                empty().into()
+            };
+
+        let indicator = container(content)
+            .width(self.config.size)
+            .height(self.config.size)
+            .align_x(Horizontal::Center)
+            .align_y(Vertical::Center)
+            .style(move |_| Style {
                 background: Some(Background::Color(background)),
                 border: Border {
                     color: CONFIG.looks.foreground,
@@ -112,19 +238,86 @@ impl Hyprland {
                     radius: Radius::new(radius),
                 },
                 ..Default::default()
+            });
+
+        // the count sits in the corner, so it doesn't collide with labels
+        let indicator: iced::Element<'_, HyprlandMessage, Theme, iced::Renderer> =
+            if self.config.show_counts && state.window_amount > 0 {
+                stack![
+                    indicator,
+                    container(
+                        text(state.window_amount.to_string())
+                            .size(self.config.size * 0.45)
+                            .color(label)
+                    )
+                    .width(self.config.size)
+                    .height(self.config.size)
+                    .padding([0f32, self.config.size * 0.12])
+                    .align_x(Horizontal::Right)
+                    .align_y(Vertical::Bottom)
+                ]
+                .into()
+            } else {
+                indicator.into()
+            };
+
+        mouse_area(indicator).on_release(HyprlandMessage::SelectAbsolute(state.id)).into()
+    }
+
+    /// runs a dispatcher requested over ipc and responds with hyprland's reply
+    pub fn dispatch(&self, dispatcher: String, request: IpcRequest) -> Task<HyprlandMessage> {
+        let instance = self.instance.clone();
+
+        Task::future(async move {
+            match instance.run_dispatch(&dispatcher).await {
+                Ok(reply) => {
+                    if reply != "ok" {
+                        warn!("hyprland rejected dispatcher `{dispatcher}`: {reply}");
+                    }
+
+                    request.respond(Value::String(reply));
+                }
+                Err(e) => {
+                    error!("{e:#}");
+                    request.respond(Value::Null);
+                }
             }
-        }))
-        .on_release(HyprlandMessage::SelectAbsolute(state.id))
-        .into()
+
+            HyprlandMessage::Ok
+        })
     }
 
-    pub fn render(&self) -> iced::Element<'_, HyprlandMessage, Theme, iced::Renderer> {
-        mouse_area(
-            Column::from_vec(
-                self.workspaces.iter().map(|state| self.render_indicator(state)).collect(),
-            )
-            .spacing(8),
-        )
+    /// renders the workspaces of the monitor shown on the given bar
+    pub fn render(
+        &self,
+        surface: SurfaceId,
+    ) -> iced::Element<'_, HyprlandMessage, Theme, iced::Renderer> {
+        let indicators = self
+            .workspaces
+            .get(&self.monitor(surface))
+            .map(|(selected, workspaces)| {
+                // persistent workspaces which don't exist are shown as empty ones
+                let mut states = workspaces.clone();
+                states.extend(
+                    self.config
+                        .persistent
+                        .iter()
+                        .filter(|id| !workspaces.iter().any(|state| state.id == **id))
+                        .map(|id| WorkspaceState::empty(*id)),
+                );
+
+                states.sort_by_key(|state| state.id);
+                states.dedup_by_key(|state| state.id);
+
+                states.iter().map(|state| self.render_indicator(state, *selected)).collect()
+            })
+            .unwrap_or_default();
+
+        mouse_area(if CONFIG.looks.horizontal() {
+            iced::Element::from(Row::from_vec(indicators).spacing(8))
+        } else {
+            Column::from_vec(indicators).spacing(8).into()
+        })
         .on_scroll(|event| match event {
             ScrollDelta::Lines { y, .. } if y > 0f32 => HyprlandMessage::SelectRelative(-1),
             ScrollDelta::Lines { y, .. } if y < 0f32 => HyprlandMessage::SelectRelative(1),
@@ -134,6 +327,27 @@ impl Hyprland {
     }
 }
 
+/// finds the id of the monitor with the given name
+fn find_monitor(monitors: &[MonitorState], name: &str) -> Option<u64> {
+    monitors.iter().find(|monitor| monitor.name == name).map(|monitor| monitor.id)
+}
+
+struct MonitorListMonitor(HyprlandInstance);
+
+impl Recipe for MonitorListMonitor {
+    type Output = Vec<MonitorState>;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str("hyprland monitor events");
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("staring hyprland monitor listener");
+
+        self.0.listen_monitors()
+    }
+}
+
 struct WorkspaceMonitor(HyprlandInstance, u64);
 
 impl Recipe for WorkspaceMonitor {
@@ -141,14 +355,12 @@ impl Recipe for WorkspaceMonitor {
 
     fn hash(&self, state: &mut Hasher) {
         state.write_str("hyprland workspace events");
+        state.write_u64(self.1);
     }
 
     fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
         debug!("staring hyprland workspace listener");
 
-        stream::once(self.0.listen_workspaces(self.1))
-            .filter_map(async |res| res.stream_log("hyprland workspace stream"))
-            .flatten()
-            .boxed()
+        self.0.listen_workspaces(self.1)
     }
 }
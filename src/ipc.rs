@@ -1,4 +1,11 @@
-use std::{env, hash::Hasher as _, path::PathBuf, sync::Arc};
+use std::{
+    fs::Permissions,
+    hash::Hasher as _,
+    io::{self, ErrorKind},
+    os::unix::fs::PermissionsExt,
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::{Context, Result};
 use futures::StreamExt;
@@ -7,40 +14,52 @@ use iced::{
     advanced::subscription::{EventStream, Hasher, Recipe, from_recipe},
 };
 use iced_winit::futures::BoxStream;
-use liischte_lib::StreamContext;
+use liischte_lib::{StreamContext, ipc::socket_path};
 use log::{debug, info, trace, warn};
-use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tokio::{
     fs,
-    io::{AsyncReadExt, AsyncWriteExt},
     net::{UnixListener, UnixStream},
-    sync::broadcast::{self, Receiver},
+    sync::{
+        broadcast::{self, Receiver},
+        mpsc,
+    },
+    time::timeout,
 };
-use tokio_stream::wrappers::BroadcastStream;
-
-use crate::ui::window::WindowLayer;
-
-/// path where the unix socket is located
-fn socket_path() -> PathBuf {
-    if let Ok(path) = env::var("LIISCHTE_SOCKET") {
-        PathBuf::from(path)
-    } else if let Ok(runtime) = env::var("XDG_RUNTIME_DIR") {
-        PathBuf::from(runtime).join("liischte.sock")
-    } else {
-        PathBuf::from("/tmp/liischte.sock")
-    }
+use tokio_stream::wrappers::{BroadcastStream, errors::BroadcastStreamRecvError};
+
+// the message and client live in the lib, so other programs can use them too
+pub use liischte_lib::ipc::{IpcMessage, query, send};
+use liischte_lib::ipc::{read_frame, write_frame};
+
+/// time the bar has to respond to a query
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(1);
+/// amount of messages which can be queued before the oldest are dropped
+const IPC_CAPACITY: usize = 64;
+
+/// a message received over ipc, which can be responded to if the client
+/// expects it
+#[derive(Clone, Debug)]
+pub struct IpcRequest {
+    pub message: IpcMessage,
+    response: Option<mpsc::Sender<Value>>,
 }
 
-/// message passed over ipc
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub enum IpcMessage {
-    ModuleUpdate(String, String),
-    LayerChange(Option<WindowLayer>),
+impl IpcRequest {
+    /// responds to the client with a value, does nothing if the client does not
+    /// expect a response
+    pub fn respond(&self, value: Value) {
+        if let Some(ref response) = self.response
+            && response.try_send(value).is_err()
+        {
+            warn!("failed to pass response to ipc client");
+        }
+    }
 }
 
 /// this implements an ipc server which can receive messages
 pub struct IpcServer {
-    broadcast: Arc<Receiver<IpcMessage>>,
+    broadcast: Arc<Receiver<IpcRequest>>,
 }
 
 impl IpcServer {
@@ -51,12 +70,18 @@ impl IpcServer {
         info!("opening ipc socket at `{}`", path.to_string_lossy());
         _ = fs::remove_file(&path).await;
 
-        let (tx, rx) = broadcast::channel(8);
+        let (tx, rx) = broadcast::channel(IPC_CAPACITY);
+
+        let listener = UnixListener::bind(&path).context("failed to bind ipc socket")?;
+
+        // only the user running the bar may control it
+        fs::set_permissions(&path, Permissions::from_mode(0o600))
+            .await
+            .context("failed to restrict permissions of ipc socket")?;
 
-        let listener = UnixListener::bind(path)?;
         tokio::spawn(async move {
             loop {
-                let Some((mut stream, a)) = listener
+                let Some((stream, a)) = listener
                     .accept()
                     .await
                     .stream_context("unix socket stream", "failed to accept listener")
@@ -69,26 +94,8 @@ impl IpcServer {
                     a.as_pathname().and_then(|p| p.to_str()).unwrap_or("<unknown>")
                 );
 
-                let mut buf = [0u8; 1024];
-
-                let Some(len) = stream
-                    .read(&mut buf)
-                    .await
-                    .stream_context("unix socket stream", "failed to read from listener")
-                else {
-                    continue;
-                };
-
-                let Some(msg) = serde_json::from_slice(&buf[0..len])
-                    .stream_context("unix socket stream", "failed to deserialize from listener")
-                else {
-                    continue;
-                };
-
-                if let Err(e) = tx.send(msg) {
-                    warn!("failed to send to ipc stream, closing ipc: {e:#}");
-                    return;
-                }
+                // every client is handled on its own, so a slow one doesn't block the others
+                tokio::spawn(handle_client(stream, tx.clone()));
             }
         });
 
@@ -96,25 +103,79 @@ impl IpcServer {
     }
 
     /// returns a subscription which will fire on ipc events
-    pub fn get_subscription(&self) -> Subscription<IpcMessage> {
+    pub fn get_subscription(&self) -> Subscription<IpcRequest> {
         from_recipe(IpcMonitor(self.broadcast.clone()))
     }
 }
 
-/// sends to the ipc socket as a client
-pub async fn send(msg: IpcMessage) -> Result<()> {
-    UnixStream::connect(socket_path())
-        .await
-        .context("failed to connect to ipc socket")?
-        .write_all(&serde_json::to_vec(&msg).context("failed to serialize message")?)
-        .await
-        .context("failed to write to ipc socket")
+/// reads messages from a client until it disconnects, responses are written
+/// back in the order of the messages
+async fn handle_client(mut stream: UnixStream, tx: broadcast::Sender<IpcRequest>) {
+    loop {
+        let buf = match read_frame(&mut stream).await {
+            Ok(buf) => buf,
+            // the client closing the connection is how it ends normally
+            Err(e)
+                if e.downcast_ref::<io::Error>()
+                    .is_some_and(|e| e.kind() == ErrorKind::UnexpectedEof) =>
+            {
+                return;
+            }
+            // this also drops clients sending frames above the maximum size
+            Err(e) => {
+                warn!("failed to read from ipc client: {e:#}");
+                return;
+            }
+        };
+
+        let Some(message) = serde_json::from_slice::<IpcMessage>(&buf)
+            .stream_context("unix socket stream", "failed to deserialize from listener")
+        else {
+            return;
+        };
+
+        // queries and passed messages are answered on the same stream
+        let (response, response_rx) = if message.expects_response() {
+            let (response_tx, response_rx) = mpsc::channel(1);
+            (Some(response_tx), Some(response_rx))
+        } else {
+            (None, None)
+        };
+
+        if let Err(e) = tx.send(IpcRequest { message, response }) {
+            warn!("failed to send to ipc stream: {e:#}");
+            return;
+        }
+
+        let Some(mut response_rx) = response_rx else {
+            continue;
+        };
+
+        let Ok(Some(value)) = timeout(RESPONSE_TIMEOUT, response_rx.recv()).await else {
+            warn!("no response for ipc query was produced in time");
+            return;
+        };
+
+        let Some(bytes) = serde_json::to_vec(&value)
+            .stream_context("unix socket stream", "failed to serialize response")
+        else {
+            return;
+        };
+
+        if write_frame(&mut stream, &bytes)
+            .await
+            .stream_context("unix socket stream", "failed to write response")
+            .is_none()
+        {
+            return;
+        }
+    }
 }
 
-struct IpcMonitor(Arc<Receiver<IpcMessage>>);
+struct IpcMonitor(Arc<Receiver<IpcRequest>>);
 
 impl Recipe for IpcMonitor {
-    type Output = IpcMessage;
+    type Output = IpcRequest;
 
     fn hash(&self, state: &mut Hasher) {
         state.write_str("ipc stream");
@@ -124,7 +185,14 @@ impl Recipe for IpcMonitor {
         debug!("staring ipc stream subscription");
 
         BroadcastStream::new(self.0.resubscribe())
-            .filter_map(async |r| r.stream_context("ipc", "failed to receive from broadcast"))
+            .filter_map(async |r| match r {
+                Ok(request) => Some(request),
+                // a burst of messages should not stop the ipc, the newer ones are kept
+                Err(BroadcastStreamRecvError::Lagged(n)) => {
+                    warn!("dropped {n} ipc messages, as they were received too fast");
+                    None
+                }
+            })
             .boxed()
     }
 }
@@ -1,9 +1,13 @@
 #![feature(hasher_prefixfree_extras)]
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow};
 use clock::{Clock, ClockMessage};
-use config::CONFIG;
+use config::{CONFIG, Config, RUNTIME_CONFIG, SeparatorStyle};
 use hyprland::{Hyprland, HyprlandMessage};
 use iced::{
     Background, Border, Color, Font, Length, Limits, Padding, Subscription, Task, Theme,
@@ -12,41 +16,45 @@ use iced::{
     runtime::platform_specific::wayland::layer_surface::{
         IcedMargin, IcedOutput, SctkLayerSurfaceSettings,
     },
-    widget::{Column, column, container::Style, vertical_space},
+    widget::{Column, Row, column, container::Style, horizontal_space, text, vertical_space},
     window::Id as SurfaceId,
 };
 use iced_winit::commands::{
-    layer_surface::{get_layer_surface, set_layer},
+    layer_surface::{destroy_layer_surface, get_layer_surface, set_layer},
     subsurface::Anchor,
 };
 use indexmap::IndexMap;
-use log::{error, info};
+use liischte_lib::{
+    StreamContext,
+    sysfs::{
+        backlight::BacklightDevice,
+        power::{BatteryPowerDevice, MainsPowerDevice, PowerDevice, PowerDeviceKind},
+        thermal::ThermalZone,
+    },
+};
+use log::{debug, error, info, warn};
 use lucide_icons::LUCIDE_FONT_BYTES;
-use module::{
-    AbstractModule, ModuleMessage,
-    audio::{AUDIO_MODULE_IDENTIFIER, AudioModule},
-    backlight::{BACKLIGHT_MODULE_IDENTIFIER, BacklightModule},
-    network::{NETWORK_MODULE_IDENTIFIER, NewtorkModule},
-    power::{POWER_MODULE_IDENTIFIER, PowerModule},
-    process::{PROCESS_MODULE_IDENTIFIER, ProcessModule},
-    timer::{TIMER_MODULE_IDENTIFIER, TimerModule},
+use module::{AbstractModule, ModuleMessage};
+use notify_rust::Notification;
+use tokio::time::sleep;
+use ui::{
+    empty, separator, tooltip,
+    window::{WindowLayer, layer_window, shell_layer},
 };
-use ui::{empty, separator, window::layer_window};
 
 use iced::widget::container as create_container;
 
 use crate::{
     cli::{Command, read_command},
-    ipc::{IpcMessage, IpcServer},
-    module::mako::{MAKO_MODULE_IDENTIFIER, MakoModule},
+    ipc::{IpcMessage, IpcRequest, IpcServer},
     ui::{
-        outputs::{OutputHandler, OutputMessage},
+        outputs::{OUTPUT_TIMEOUT, OutputHandler, OutputMessage},
         runtime::ExistingRuntime,
     },
 };
 use crate::{
     module::ModuleId,
-    osd::{OsdHandler, OsdMessage},
+    osd::{OsdHandler, OsdId, OsdMessage, TOOLTIP_OSD},
     ui::PILL_RADIUS,
 };
 
@@ -67,13 +75,60 @@ async fn main() -> Result<()> {
     // read command from the cli
     match read_command() {
         Some(Command::Pass { module, message }) => {
-            ipc::send(IpcMessage::ModuleUpdate(module, message)).await?;
+            let response = ipc::query(IpcMessage::ModuleUpdate(module, message)).await?;
+            if !response.is_null() {
+                println!("{}", serde_json::to_string_pretty(&response)?);
+            }
             return Ok(());
         }
         Some(Command::Layer { layer }) => {
             ipc::send(IpcMessage::LayerChange(layer)).await?;
             return Ok(());
         }
+        Some(Command::Modules { modules }) => {
+            ipc::send(IpcMessage::SetModules(modules)).await?;
+            return Ok(());
+        }
+        Some(Command::Reload) => {
+            ipc::send(IpcMessage::Reload).await?;
+            return Ok(());
+        }
+        Some(Command::Osd { module }) => {
+            ipc::send(IpcMessage::ShowOsd(module)).await?;
+            return Ok(());
+        }
+        Some(Command::Dispatch { dispatcher }) => {
+            let response = ipc::query(IpcMessage::Dispatch(dispatcher.join(" "))).await?;
+            let Some(reply) = response.as_str() else {
+                return Err(anyhow!("failed to run dispatcher, is hyprland enabled?"));
+            };
+
+            println!("{reply}");
+            return Ok(());
+        }
+        Some(Command::Check) => {
+            check_config()?;
+            return Ok(());
+        }
+        Some(Command::Devices) => {
+            list_devices().await;
+            return Ok(());
+        }
+        Some(Command::Get { module }) => {
+            let state = ipc::query(IpcMessage::Query(module)).await?;
+            println!("{}", serde_json::to_string_pretty(&state)?);
+            return Ok(());
+        }
+        Some(Command::Json { module }) => {
+            let state = ipc::query(IpcMessage::QueryWaybar(module.clone())).await?;
+            if state.is_null() {
+                return Err(anyhow!("module `{module}` is not running"));
+            }
+
+            // waybar reads one object per line
+            println!("{}", serde_json::to_string(&state)?);
+            return Ok(());
+        }
         None => {}
     }
 
@@ -89,18 +144,26 @@ async fn main() -> Result<()> {
     })
     .settings(iced::Settings {
         default_font: Font::with_name(&CONFIG.looks.font),
-        default_text_size: 16.into(),
+        default_text_size: CONFIG.looks.text_size.into(),
         antialiasing: true,
         fonts: vec![LUCIDE_FONT_BYTES.into()],
         ..Default::default()
     });
 
     info!("starting liischte");
+    liischte_lib::set_polling_jitter(CONFIG.polling_jitter);
+
     let mut liischte = Liischte::new();
     liischte.init().await;
 
+    // the configured output should have appeared by then
+    let timeout = Task::future(async {
+        sleep(OUTPUT_TIMEOUT).await;
+        Message::OutputTimeout
+    });
+
     // run iced app with surface
-    app.run_with(move || (liischte, Task::none())).context("failed to start iced application")
+    app.run_with(move || (liischte, timeout)).context("failed to start iced application")
 }
 
 #[derive(Debug, Clone)]
@@ -108,16 +171,136 @@ enum Message {
     Clock(ClockMessage),
     Hyprland(HyprlandMessage),
     Module(Box<dyn ModuleMessage>),
+    Tooltip(ModuleId, bool),
 
     Osd(OsdMessage),
     Output(OutputMessage),
-    Ipc(IpcMessage),
+    OutputTimeout,
+    Ipc(IpcRequest),
+    ConfigChanged,
+    ModulesConstructed(ConstructedModules),
+}
+
+/// modules in the order they are shown in, where modules without a constructed
+/// instance reuse the running one
+type ModuleList = Vec<(String, Option<Result<Box<dyn AbstractModule>>>)>;
+
+/// modules which were constructed in the background, these can only be taken
+/// out once
+#[derive(Clone)]
+struct ConstructedModules(Arc<Mutex<Option<ModuleList>>>);
+
+impl ConstructedModules {
+    fn new(modules: ModuleList) -> Self {
+        Self(Arc::new(Mutex::new(Some(modules))))
+    }
+
+    fn take(&self) -> Option<ModuleList> {
+        self.0.lock().expect("constructed modules lock was poisoned").take()
+    }
+}
+
+impl Debug for ConstructedModules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ConstructedModules")
+    }
+}
+
+/// reads the config and the config of all enabled modules, which would
+/// otherwise only be read once the modules are constructed
+fn check_config() -> Result<()> {
+    let config = match Config::read()? {
+        Some(config) => config,
+        None => {
+            println!("no config file exists, checking the default config");
+            Config::default()
+        }
+    };
+
+    for identifier in &config.modules {
+        module::check_config(identifier, &config)
+            .ok_or(anyhow!("module `{identifier}` does not exist in this version"))??;
+    }
+
+    println!("config is valid, enabled modules: {}", config.modules.join(", "));
+    Ok(())
+}
+
+/// prints the devices in the sysfs which modules can be configured to use, with
+/// the attributes which help to tell them apart
+async fn list_devices() {
+    fn print_row(name: &str, kind: &str, details: String) {
+        println!("  {name:<24} {kind:<16} {details}");
+    }
+
+    println!("power (`mains` and `batteries` of `power`)");
+    match PowerDevice::read_all().await {
+        Ok(devices) => {
+            for device in devices {
+                let name = device.device.name.clone();
+
+                match device.kind {
+                    PowerDeviceKind::Mains => {
+                        let online = MainsPowerDevice(device).read_online().await;
+                        let details = match online {
+                            Ok(online) => format!("online: {online}"),
+                            Err(_) => "online: unknown".to_string(),
+                        };
+
+                        print_row(&name, "mains", details);
+                    }
+                    PowerDeviceKind::Battery => {
+                        let battery = BatteryPowerDevice(device);
+                        let details = match battery.read_charge().await {
+                            Ok(charge) => format!("charge: {:.0}%", charge * 100f64),
+                            Err(_) => "charge: unknown".to_string(),
+                        };
+
+                        print_row(&name, "battery", details);
+                    }
+                    PowerDeviceKind::Unknown => print_row(&name, "unsupported", String::new()),
+                }
+            }
+        }
+        Err(e) => println!("  {e:#}"),
+    }
+
+    println!("backlight (`device` of `backlight`)");
+    match BacklightDevice::read_all().await {
+        Ok(devices) => {
+            for device in devices {
+                let details = match device.read_brightness().await {
+                    Ok(brightness) => format!("brightness: {:.0}%", brightness * 100f64),
+                    Err(_) => "brightness: unknown".to_string(),
+                };
+
+                print_row(&device.device.name, "backlight", details);
+            }
+        }
+        Err(e) => println!("  {e:#}"),
+    }
+
+    println!("thermal (`zone` of `thermal` matches the type)");
+    match ThermalZone::read_all().await {
+        Ok(zones) => {
+            for zone in zones {
+                let details = match zone.read_temperature().await {
+                    Ok(temperature) => format!("temperature: {temperature:.0}°C"),
+                    Err(_) => "temperature: unknown".to_string(),
+                };
+
+                print_row(&zone.device.name, &zone.kind, details);
+            }
+        }
+        Err(e) => println!("  {e:#}"),
+    }
 }
 
 struct Liischte {
     clock: Clock,
     hyprland: Option<Hyprland>,
     modules: IndexMap<ModuleId, Box<dyn AbstractModule>>,
+    generation: u64, // incremented whenever the modules are rebuilt
 
     osd: Option<OsdHandler>,
 
@@ -125,7 +308,13 @@ struct Liischte {
     ipc: Option<IpcServer>,
 
     outputs: OutputHandler,
-    alive: bool, // whether the surface is alive
+    bars: Vec<Bar>,
+    layer: WindowLayer, // layer the surfaces are currently on
+}
+
+/// a bar surface shown on a single output
+struct Bar {
+    output: IcedOutput,
     surface: SurfaceId,
 }
 
@@ -133,6 +322,7 @@ impl Liischte {
     pub fn new() -> Self {
         Self {
             modules: IndexMap::new(),
+            generation: 0,
             clock: Clock::new(),
             hyprland: None,
 
@@ -142,8 +332,8 @@ impl Liischte {
             ipc: None,
 
             outputs: OutputHandler::new(),
-            alive: false,
-            surface: SurfaceId::unique(),
+            bars: Vec::new(),
+            layer: CONFIG.layer,
         }
     }
 
@@ -167,16 +357,14 @@ impl Liischte {
             }
         }
 
-        for status in CONFIG.modules.iter().rev() {
-            let module = match status.as_str() {
-                POWER_MODULE_IDENTIFIER => PowerModule::new().await.map(module::boxed),
-                BACKLIGHT_MODULE_IDENTIFIER => BacklightModule::new().await.map(module::boxed),
-                NETWORK_MODULE_IDENTIFIER => NewtorkModule::new().await.map(module::boxed),
-                MAKO_MODULE_IDENTIFIER => MakoModule::new().await.map(module::boxed),
-                PROCESS_MODULE_IDENTIFIER => ProcessModule::new().map(module::boxed),
-                TIMER_MODULE_IDENTIFIER => Ok(module::boxed(TimerModule::new())),
-                AUDIO_MODULE_IDENTIFIER => Ok(module::boxed(AudioModule::new())),
-                status => panic!("status `{status}` does not exist in this version"),
+        let config = RUNTIME_CONFIG.get();
+        let mut unknown = vec![];
+
+        for status in config.modules.iter().rev() {
+            let Some(module) = module::construct(status).await else {
+                error!("module `{status}` does not exist in this version");
+                unknown.push(status.as_str());
+                continue;
             };
 
             match module {
@@ -191,32 +379,211 @@ impl Liischte {
                 }
             }
         }
+
+        // a typo in the config should not go unnoticed
+        if !unknown.is_empty() {
+            unknown.reverse();
+
+            Notification::new()
+                .summary("Unknown liischte modules")
+                .body(&format!(
+                    "The modules `{}` do not exist and were skipped.",
+                    unknown.join("`, `")
+                ))
+                .show_async()
+                .await
+                .stream_log("failed to send notification");
+        }
     }
 
-    fn open(&mut self, output: IcedOutput) -> Task<Message> {
-        info!("opening bar layer surface");
-        self.alive = true;
+    /// re-reads the config and constructs all configured modules in the
+    /// background
+    fn reload(&mut self) -> Task<Message> {
+        if let Err(e) = RUNTIME_CONFIG.reload() {
+            error!("failed to reload config, keeping the current one: {e:#}");
+            return Task::none();
+        }
+
+        info!("reloading modules with the new config");
+        self.construct_modules(RUNTIME_CONFIG.get().modules.clone(), false)
+    }
+
+    /// shows the given osd of a module, if the osd is enabled
+    fn request_osd(&mut self, id: ModuleId, osd_id: OsdId) -> Task<Message> {
+        let (Some(module), Some(osd)) = (self.modules.get(&id), &mut self.osd) else {
+            return Task::none();
+        };
+
+        let interactive = module.osd_interactive(osd_id);
+        let timeout = module.osd_timeout(osd_id);
+
+        osd.request_osd(id, osd_id, interactive, timeout).map(Message::Osd)
+    }
+
+    /// constructs the given modules in the background, already running modules
+    /// are reused if `reuse` is set
+    fn construct_modules(&self, identifiers: Vec<String>, reuse: bool) -> Task<Message> {
+        // modules are stored in reverse, just like on startup
+        let identifiers = identifiers
+            .into_iter()
+            .rev()
+            .map(|identifier| (reuse && self.module_names.contains_key(&identifier), identifier))
+            .collect::<Vec<_>>();
+
+        Task::future(async move {
+            let mut modules = vec![];
+
+            for (running, identifier) in identifiers {
+                if running {
+                    modules.push((identifier, None));
+                    continue;
+                }
+
+                match module::construct(&identifier).await {
+                    Some(module) => modules.push((identifier, Some(module))),
+                    None => error!("module `{identifier}` does not exist in this version"),
+                }
+            }
+
+            Message::ModulesConstructed(ConstructedModules::new(modules))
+        })
+    }
+
+    /// replaces the current modules with the given ones, the previous instance
+    /// of a module is kept if it failed to construct
+    fn replace_modules(&mut self, list: ModuleList) {
+        let mut modules = IndexMap::new();
+        let mut module_names = HashMap::new();
+        let mut rebuilt = false;
+
+        for (identifier, module) in list {
+            let previous =
+                self.module_names.get(&identifier).and_then(|id| self.modules.shift_remove(id));
+
+            let module = match (module, previous) {
+                (Some(Ok(module)), previous) => {
+                    info!("adding module `{identifier}` to bar");
+                    rebuilt |= previous.is_some();
+
+                    module
+                }
+                (Some(Err(e)), previous) => {
+                    error!("failed to initialize module `{identifier}`: {e:#}");
+
+                    let Some(previous) = previous else { continue };
+                    warn!("keeping previous instance of module `{identifier}`");
+
+                    previous
+                }
+                (None, Some(previous)) => previous,
+                (None, None) => continue, // listed twice
+            };
+
+            module_names.insert(identifier, module.message_type());
+            modules.insert(module.message_type(), module);
+        }
+
+        for identifier in self.module_names.keys() {
+            if !module_names.contains_key(identifier) {
+                info!("removing module `{identifier}` from bar");
+            }
+        }
 
+        self.modules = modules;
+        self.module_names = module_names;
+
+        // rebuilt modules would otherwise keep the streams of their previous instance
+        if rebuilt {
+            self.generation += 1;
+        }
+    }
+
+    /// opens bars on the configured outputs which don't have one yet and closes
+    /// the bars on outputs which are no longer configured
+    fn sync_bars(&mut self) -> Task<Message> {
+        let mut configured = self.outputs.get_configured();
+
+        if !CONFIG.all_outputs {
+            // the bar stays on its output as long as it matches, even if others do too
+            let current = configured
+                .iter()
+                .position(|output| {
+                    self.bars.iter().any(|bar| OutputHandler::same(&bar.output, output))
+                })
+                .unwrap_or_default();
+
+            configured = configured.into_iter().skip(current).take(1).collect();
+        }
+
+        let (bars, closed): (Vec<_>, Vec<_>) = self.bars.drain(..).partition(|bar| {
+            configured.iter().any(|output| OutputHandler::same(&bar.output, output))
+        });
+        self.bars = bars;
+
+        let mut tasks = vec![];
+
+        for bar in closed {
+            info!("closing bar layer surface");
+
+            if let Some(ref mut hl) = self.hyprland {
+                hl.remove_surface(bar.surface);
+            }
+            tasks.push(destroy_layer_surface(bar.surface));
+        }
+
+        for output in configured {
+            if !self.bars.iter().any(|bar| OutputHandler::same(&bar.output, &output)) {
+                tasks.push(self.open(output));
+            }
+        }
+
+        // the osd is shown next to the first bar
         if let Some(ref mut osd) = self.osd {
-            osd.output = Some(output.clone());
+            osd.output = self.bars.first().map(|bar| bar.output.clone());
         }
 
-        get_layer_surface(SctkLayerSurfaceSettings {
+        Task::batch(tasks)
+    }
+
+    fn open(&mut self, output: IcedOutput) -> Task<Message> {
+        info!("opening bar layer surface");
+        let id = SurfaceId::unique();
+
+        let hyprland = self
+            .hyprland
+            .as_ref()
+            .map(|hl| hl.set_output(id, self.outputs.get_name(&output)).map(Message::Hyprland))
+            .unwrap_or(Task::none());
+
+        let padding = CONFIG.looks.padding as i32;
+
+        let (anchor, margin, size) = if CONFIG.looks.horizontal() {
+            (
+                CONFIG.looks.anchor.anchors(Anchor::LEFT, Anchor::RIGHT)
+                    | if CONFIG.right { Anchor::BOTTOM } else { Anchor::TOP },
+                IcedMargin { bottom: 0, left: padding, top: padding, right: padding },
+                (CONFIG.looks.extent(), Some(CONFIG.looks.width)),
+            )
+        } else {
+            (
+                CONFIG.looks.anchor.anchors(Anchor::TOP, Anchor::BOTTOM)
+                    | if CONFIG.right { Anchor::RIGHT } else { Anchor::LEFT },
+                IcedMargin { bottom: padding, left: padding, top: padding, right: 0 },
+                (Some(CONFIG.looks.width), CONFIG.looks.extent()),
+            )
+        };
+
+        self.bars.push(Bar { output: output.clone(), surface: id });
+
+        let surface = get_layer_surface(SctkLayerSurfaceSettings {
             output,
-            id: self.surface,
-
-            layer: CONFIG.layer.into(),
-            anchor: Anchor::TOP
-                | if CONFIG.right { Anchor::RIGHT } else { Anchor::LEFT }
-                | Anchor::BOTTOM,
-
-            margin: IcedMargin {
-                bottom: CONFIG.looks.padding as i32,
-                left: CONFIG.looks.padding as i32,
-                top: CONFIG.looks.padding as i32,
-                right: 0,
-            },
-            size: Some((Some(CONFIG.looks.width), None)),
+            id,
+
+            layer: shell_layer(self.layer),
+            anchor,
+
+            margin,
+            size: Some(size),
             exclusive_zone: CONFIG.looks.width as i32,
             size_limits: Limits::NONE,
 
@@ -224,11 +591,18 @@ impl Liischte {
             namespace: CONFIG.namespace.clone(),
 
             ..Default::default()
-        })
+        });
+
+        Task::batch(vec![surface, hyprland])
     }
 
     fn update(&mut self, message: Message) -> Task<Message> {
         match message {
+            Message::Clock(ClockMessage::ShowCalendar) => {
+                let Some(osd) = &mut self.osd else { return Task::none() };
+
+                osd.request_wide_osd(Clock::id(), 0).map(Message::Osd)
+            }
             Message::Clock(msg) => self.clock.update(msg).map(Message::Clock),
 
             Message::Hyprland(msg) => self
@@ -240,24 +614,33 @@ impl Liischte {
             Message::Module(msg) => {
                 let id = (*msg).type_id();
 
-                let (task, osd) = self
-                    .modules
-                    .get_mut(&id)
-                    .expect("received status message for non-existent status")
-                    .update(msg);
-
-                if let Some(osd_id) = osd
-                    && let Some(osd) = &mut self.osd
-                {
-                    Task::batch(vec![
-                        task.map(Message::Module),
-                        osd.request_osd(id, osd_id).map(Message::Osd),
-                    ])
+                // modules might have been removed by a reload in the meantime
+                let Some(module) = self.modules.get_mut(&id) else {
+                    debug!("dropping module message for removed module");
+                    return Task::none();
+                };
+
+                let (task, osd) = module.update(msg);
+
+                if let Some(osd_id) = osd {
+                    Task::batch(vec![task.map(Message::Module), self.request_osd(id, osd_id)])
                 } else {
                     task.map(Message::Module)
                 }
             }
 
+            Message::Tooltip(id, hovered) => {
+                let Some(osd) = &mut self.osd else { return Task::none() };
+
+                if !hovered {
+                    osd.dismiss_tooltip(id).map(Message::Osd)
+                } else if self.modules.get(&id).is_some_and(|module| module.tooltip().is_some()) {
+                    osd.request_tooltip(id).map(Message::Osd)
+                } else {
+                    Task::none()
+                }
+            }
+
             Message::Osd(msg) => self
                 .osd
                 .as_mut()
@@ -265,37 +648,150 @@ impl Liischte {
                 .update(msg)
                 .map(Message::Osd),
 
+            Message::Output(OutputMessage::Closed(surface)) => {
+                let Some(index) = self.bars.iter().position(|bar| bar.surface == surface) else {
+                    return Task::none();
+                };
+
+                // bars on the active output are not bound to a known output, so the
+                // compositor closing them is the only way to notice that output is gone
+                info!("bar layer surface was closed by the compositor, reopening it");
+                let bar = self.bars.remove(index);
+
+                if let Some(ref mut hl) = self.hyprland {
+                    hl.remove_surface(bar.surface);
+                }
+
+                Task::batch(vec![destroy_layer_surface(bar.surface), self.sync_bars()])
+            }
             Message::Output(msg) => {
                 self.outputs.update(msg);
+                self.sync_bars()
+            }
+
+            Message::OutputTimeout => {
+                if !self.bars.is_empty() {
+                    return Task::none();
+                }
+
+                warn!(
+                    "no output matches `{}`, available outputs are {}",
+                    CONFIG.output,
+                    self.outputs.get_available().join(", ")
+                );
 
-                if !self.alive
-                    && let Some(output) = self.outputs.get_configured()
-                {
-                    self.open(output)
+                if CONFIG.output_fallback {
+                    info!("falling back to the active output");
+                    self.outputs.fall_back();
+                    self.sync_bars()
                 } else {
                     Task::none()
                 }
             }
 
-            Message::Ipc(msg) => match msg {
-                IpcMessage::ModuleUpdate(module, msg) => {
+            Message::Ipc(request) => match request.message {
+                IpcMessage::ModuleUpdate(ref module, ref msg) => {
+                    let Some(module) =
+                        self.module_names.get(module).and_then(|id| self.modules.get(id))
+                    else {
+                        info!("module `{module}` not found when passing message");
+                        request.respond(serde_json::Value::Null);
+                        return Task::none();
+                    };
+
+                    let Some(message) = module.pass_message(msg) else {
+                        request.respond(serde_json::Value::Null);
+                        return Task::none();
+                    };
+
+                    // the message is handled right away, so the module can respond to it
+                    let id = (*message).type_id();
+                    let task = self.update(Message::Module(message.clone()));
+
+                    request.respond(
+                        self.modules
+                            .get(&id)
+                            .map(|module| module.pass_response(&*message))
+                            .unwrap_or_default(),
+                    );
+
+                    task
+                }
+                IpcMessage::LayerChange(layer) => {
+                    self.layer = layer.unwrap_or(CONFIG.layer);
+                    info!("changing layer of bar to `{:?}`", self.layer);
+
+                    // the layer is also kept when the surfaces are re-created
+                    Task::batch(
+                        self.bars.iter().map(|bar| set_layer(bar.surface, shell_layer(self.layer))),
+                    )
+                }
+                IpcMessage::Reload => self.reload(),
+                IpcMessage::Dispatch(ref dispatcher) => {
+                    if let Some(ref hl) = self.hyprland {
+                        hl.dispatch(dispatcher.clone(), request.clone()).map(Message::Hyprland)
+                    } else {
+                        info!("hyprland is not enabled when running dispatcher");
+                        request.respond(serde_json::Value::Null);
+                        Task::none()
+                    }
+                }
+                IpcMessage::SetModules(ref identifiers) => {
+                    info!("changing modules of bar to `{}`", identifiers.join(", "));
+                    self.construct_modules(identifiers.clone(), true)
+                }
+                IpcMessage::ShowOsd(ref module) => {
+                    let Some(id) = self.module_names.get(module).copied() else {
+                        info!("module `{module}` not found when showing its osd");
+                        return Task::none();
+                    };
+
+                    let Some(osd_id) = self.modules.get(&id).and_then(|module| module.main_osd())
+                    else {
+                        info!("module `{module}` has no osd to show");
+                        return Task::none();
+                    };
+
+                    self.request_osd(id, osd_id)
+                }
+                IpcMessage::Query(ref module) => {
                     if let Some(module) =
-                        self.module_names.get(&module).and_then(|id| self.modules.get(id))
+                        self.module_names.get(module).and_then(|id| self.modules.get(id))
                     {
-                        if let Some(message) = module.pass_message(&msg) {
-                            Task::done(Message::Module(message))
-                        } else {
-                            Task::none()
-                        }
+                        request.respond(module.snapshot());
                     } else {
-                        info!("module `{module}` not found when passing message");
-                        Task::none()
+                        info!("module `{module}` not found when querying state");
+                        request.respond(serde_json::Value::Null);
                     }
+
+                    Task::none()
                 }
-                IpcMessage::LayerChange(layer) => {
-                    set_layer(self.surface, layer.unwrap_or(CONFIG.layer).into())
+                IpcMessage::QueryWaybar(ref module) => {
+                    if let Some(module) =
+                        self.module_names.get(module).and_then(|id| self.modules.get(id))
+                    {
+                        request.respond(serde_json::to_value(module.waybar()).unwrap_or_default());
+                    } else {
+                        info!("module `{module}` not found when querying state");
+                        request.respond(serde_json::Value::Null);
+                    }
+
+                    Task::none()
                 }
             },
+
+            Message::ConfigChanged => {
+                info!("config file has changed");
+                self.reload()
+            }
+
+            Message::ModulesConstructed(constructed) => {
+                if let Some(constructed) = constructed.take() {
+                    self.replace_modules(constructed);
+                }
+
+                Task::none()
+            }
         }
     }
 
@@ -307,19 +803,27 @@ impl Liischte {
                 .map(|hl| hl.subscribe().map(Message::Hyprland))
                 .unwrap_or(Subscription::none()),
             Subscription::batch(
-                self.modules.values().map(|status| status.subscribe().map(Message::Module)),
+                // the generation makes sure rebuilt modules get fresh subscriptions
+                self.modules.values().map(|status| {
+                    status.subscribe().with(self.generation).map(|(_, msg)| Message::Module(msg))
+                }),
             ),
             self.outputs.subscribe().map(Message::Output),
             self.ipc
                 .as_ref()
                 .map(|s| s.get_subscription().map(Message::Ipc))
                 .unwrap_or(Subscription::none()),
+            if CONFIG.watch {
+                config::watch().map(|_| Message::ConfigChanged)
+            } else {
+                Subscription::none()
+            },
         ])
     }
 
     fn view(&self, id: SurfaceId) -> iced::Element<'_, Message, Theme, iced::Renderer> {
-        if id == self.surface {
-            self.view_bar()
+        if self.bars.iter().any(|bar| bar.surface == id) {
+            self.view_bar(id)
         } else if let Some(osd) = &self.osd
             && id == osd.surface
         {
@@ -330,69 +834,141 @@ impl Liischte {
         }
     }
 
-    fn view_bar(&self) -> iced::Element<'_, Message, Theme, iced::Renderer> {
+    fn view_bar(&self, surface: SurfaceId) -> iced::Element<'_, Message, Theme, iced::Renderer> {
         let mut infos = self
             .modules
             .values()
-            .flat_map(|module| module.render_info().into_iter())
-            .map(|info| info.map(Message::Module))
+            .flat_map(|module| {
+                let id = module.message_type();
+
+                module
+                    .render_info()
+                    .into_iter()
+                    .map(move |info| self.with_tooltip(id, info.map(Message::Module)))
+            })
             .peekable();
         let has_infos = infos.peek().is_some();
 
-        let status = self
-            .modules
-            .values()
-            .filter(|module| module.has_status())
-            .map(|module| module.render_status().map(Message::Module));
+        let status = self.modules.values().filter(|module| module.has_status()).map(|module| {
+            self.with_tooltip(module.message_type(), module.render_status().map(Message::Module))
+        });
+
+        let hyprland = self
+            .hyprland
+            .as_ref()
+            .map(|hl| hl.render(surface).map(Message::Hyprland))
+            .unwrap_or_else(|| column![].into());
+
+        let looks = &CONFIG.looks;
+
+        type Group<'a> = iced::Element<'a, Message, Theme, iced::Renderer>;
+
+        let (space, infos, status): (Group<'_>, Group<'_>, Group<'_>) = if looks.horizontal() {
+            (
+                horizontal_space().into(),
+                Row::from_iter(infos)
+                    .spacing(looks.module_spacing)
+                    .align_y(Vertical::Center)
+                    .into(),
+                Row::from_iter(status)
+                    .spacing(looks.module_spacing)
+                    .align_y(Vertical::Center)
+                    .into(),
+            )
+        } else {
+            (
+                vertical_space().into(),
+                Column::from_iter(infos).spacing(looks.module_spacing).into(),
+                Column::from_iter(status).spacing(looks.module_spacing).into(),
+            )
+        };
+
+        // without separators, an empty group would leave a double gap
+        let separators = looks.separators && looks.separator_style != SeparatorStyle::None;
+        let mut groups = vec![hyprland, space];
+        if separators {
+            groups.extend([infos, separator(has_infos)]);
+        } else if has_infos {
+            groups.push(infos);
+        }
+        groups.push(status);
+        if separators {
+            groups.push(separator(true));
+        }
+        groups.push(self.clock.render().map(Message::Clock));
+
+        if looks.horizontal() {
+            Row::from_vec(groups)
+                .padding(Padding::ZERO.left(10).right(10))
+                .spacing(looks.group_spacing)
+                .align_y(Vertical::Center)
+                .height(Length::Fill)
+                .into()
+        } else {
+            Column::from_vec(groups)
+                .padding(Padding::ZERO.top(10).bottom(5)) // gives some visual balance
+                .spacing(looks.group_spacing)
+                .align_x(Horizontal::Center)
+                .width(Length::Fill)
+                .into()
+        }
+    }
 
-        column![
-            self.hyprland
-                .as_ref()
-                .map(|hl| hl.render().map(Message::Hyprland))
-                .unwrap_or_else(|| column![].into()),
-            vertical_space(),
-            Column::from_iter(infos).spacing(4),
-            separator(has_infos),
-            Column::from_iter(status).spacing(4),
-            separator(true),
-            self.clock.render().map(Message::Clock)
-        ]
-        .padding(Padding::ZERO.top(10).bottom(5)) // gives some visual balance
-        .spacing(12)
-        .align_x(Horizontal::Center)
-        .width(Length::Fill)
-        .into()
+    /// shows the tooltip of a module in the osd when the element is hovered
+    fn with_tooltip<'a>(
+        &self,
+        id: ModuleId,
+        element: iced::Element<'a, Message, Theme, iced::Renderer>,
+    ) -> iced::Element<'a, Message, Theme, iced::Renderer> {
+        if CONFIG.osd.tooltips && self.osd.is_some() {
+            tooltip(element, Message::Tooltip(id, true), Message::Tooltip(id, false)).into()
+        } else {
+            element
+        }
     }
 
     fn view_osd(&self) -> iced::Element<'_, Message, Theme, iced::Renderer> {
+        let handler = self.osd.as_ref().expect("rendering osd without enabled");
+
         let widget: iced::Element<'_, Message, Theme, iced::Renderer> =
-            if let Some((ref id, ref osd)) =
-                self.osd.as_ref().expect("rendering osd without enabled").get_active()
+            if handler.get_active().is_some_and(|(id, _)| id == Clock::id()) {
+                self.clock.render_calendar().map(Message::Clock)
+            } else if let Some((ref id, ref osd)) = handler.get_active()
+                && let Some(module) = self.modules.get(id)
             {
-                self.modules
-                    .get(id)
-                    .expect("tried to show osd from non-existent status")
-                    .render_osd(*osd)
-                    .map(Message::Module)
+                if *osd == TOOLTIP_OSD {
+                    create_container(text(module.tooltip().unwrap_or_default()).size(12))
+                        .padding(Padding::from([8, 12]))
+                        .into()
+                } else {
+                    module.render_osd(*osd).map(Message::Module)
+                }
             } else {
                 empty().into()
             };
 
+        let alpha = handler.get_alpha();
+
         create_container(
             create_container(widget)
                 .style(move |_| Style {
-                    background: Some(Background::Color(CONFIG.looks.background)),
-                    border: Border { color: CONFIG.looks.border, width: 1f32, radius: PILL_RADIUS },
+                    background: Some(Background::Color(CONFIG.looks.background.scale_alpha(alpha))),
+                    border: Border {
+                        color: CONFIG.looks.border.scale_alpha(alpha),
+                        width: 1f32,
+                        radius: PILL_RADIUS,
+                    },
+                    text_color: Some(CONFIG.looks.foreground.scale_alpha(alpha)),
                     ..Default::default()
                 })
-                .width(CONFIG.looks.width as f32)
+                .width(handler.get_width() as f32)
                 .align_x(Horizontal::Center)
                 .align_y(Vertical::Center),
         )
         .height(Length::Fill)
         .width(Length::Fill)
         .align_x(Horizontal::Center)
-        .align_y(Vertical::Center)
+        .align_y(CONFIG.osd.position.alignment())
         .into()
     }
 }
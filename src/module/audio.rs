@@ -1,69 +1,202 @@
-use std::{hash::Hasher as _, sync::Arc};
+use std::{
+    collections::HashMap,
+    fs,
+    hash::Hasher as _,
+    mem,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use anyhow::{Context, Result};
 use iced::{
-    Element, Padding, Renderer, Subscription, Task, Theme,
+    Background, Border, Element, Length, Padding, Renderer, Subscription, Task, Theme,
     advanced::subscription::{EventStream, Hasher, Recipe, from_recipe},
     alignment::Horizontal,
-    mouse::ScrollDelta,
-    widget::{column, mouse_area, stack},
+    widget::{Column, column, container, mouse_area, stack, text},
 };
 use iced_winit::futures::BoxStream;
 use liischte_lib::{
     StreamContext,
-    pipewire::{PipewireInstance, default::DefaultState, node::NodeState},
+    pipewire::{PipewireInstance, PipewireStatus, default::DefaultState, node::NodeState},
 };
-use log::{debug, info};
+use log::{debug, error, info, warn};
 use lucide_icons::Icon;
+use serde::Deserialize;
+use serde_json::{Value, json};
 
-use super::{Module, ModuleMessage};
+use super::{
+    Module, ModuleMessage, ModuleRegistry, PassedMessage, WaybarState, deserialize_config,
+};
 use crate::{
-    config::CONFIG,
+    config::{CONFIG, RUNTIME_CONFIG, state_path},
     osd::OsdId,
-    ui::{icon, progress::vertical_progress},
+    ui::{icon, progress::vertical_progress, scroll_change},
 };
 
 pub const AUDIO_MODULE_IDENTIFIER: &str = "audio";
 
-const OSD_SOURCE_FLAG: u32 = 1u32 << 30;
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    registry.add_sync(
+        AUDIO_MODULE_IDENTIFIER,
+        AudioModule::new,
+        deserialize_config::<AudioModuleConfig>,
+    );
+}
+
+/// name of the state file the preferred sink is stored in
+const PREFERRED_SINK_STATE: &str = "audio-sink";
+
+/// time after which an optimistic volume is dropped if pipewire never confirmed it
+const OPTIMISTIC_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// kinds of osds, which are stored in the upper half of the osd id, such that
+/// they can't collide with the node id in the lower half
+const OSD_SINK: OsdId = 0;
+const OSD_SOURCE: OsdId = 1 << 32;
+const OSD_PICKER: OsdId = 2 << 32;
+const OSD_KIND_MASK: OsdId = !(u32::MAX as OsdId);
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct AudioModuleConfig {
+    /// which mouse button triggers which action on the status
+    buttons: HashMap<AudioAction, MouseButton>,
+    /// whether to invert the direction of scrolling to change the volume
+    invert_scroll: bool,
+    /// how much faster scrolls change the volume more, `1.0` is linear
+    scroll_acceleration: f32,
+    /// highest volume the bar sets on a channel, `1.0` is 100%
+    max_volume: f32,
+    /// whether to remember the sink picked in the bar and make it the default
+    /// again whenever it appears
+    remember: bool,
+    /// name prefixes of sinks which are made the default when they appear,
+    /// regardless of what the session manager decides
+    follow: Vec<String>,
+    /// time the volume osd is shown in milliseconds, none for the osd timeout
+    osd_timeout: Option<u64>,
+}
+
+impl Default for AudioModuleConfig {
+    fn default() -> Self {
+        Self {
+            buttons: HashMap::from([(AudioAction::Mute, MouseButton::Left)]),
+            invert_scroll: false,
+            scroll_acceleration: 1.0,
+            max_volume: 1.0,
+            remember: false,
+            follow: vec![],
+            osd_timeout: None,
+        }
+    }
+}
+
+#[derive(Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+enum AudioAction {
+    /// toggle mute of the default sink
+    Mute,
+    /// show the osd of the default sink
+    Osd,
+    /// show an osd to pick the default sink
+    Picker,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
 
 impl ModuleMessage for AudioMessage {}
 #[derive(Clone, Debug)]
 pub enum AudioMessage {
+    Status(PipewireStatus),
     DefaultState(DefaultState),
     SinkState(Vec<NodeState>),
     SourceState(Vec<NodeState>),
 
     ToggleMute,
+    SetMute(bool),
     ChangeVolume(f32),
+    ShowOsd,
+    ShowPicker,
+    SelectSink(String),
 
     Ok,
 }
 
 pub struct AudioModule {
+    config: AudioModuleConfig,
+
     pipewire: Arc<PipewireInstance>, // this is an arc to implement efficient subscriptions
+    status: PipewireStatus,
 
     defaults: DefaultState,
     sinks: Vec<NodeState>,
     sources: Vec<NodeState>,
 
+    // ids of the defaults, which stay valid if the nodes are renamed
+    sink_id: Option<u32>,
+    source_id: Option<u32>,
+
     selected_sink: Option<NodeState>,
     selected_source: Option<NodeState>,
+
+    /// volume we just set on a sink (by id), shown until pipewire confirms it
+    optimistic: Option<(u32, Vec<f32>, Instant)>,
+
+    /// sink picked in the bar, only if it is remembered
+    preferred: Option<String>,
+    /// whether the preferred sink was present on the last update
+    preferred_present: bool,
+
+    /// whether the sinks were received since connecting, so that the
+    /// existing ones aren't mistaken for new ones
+    sinks_known: bool,
 }
 
 impl AudioModule {
-    pub fn new() -> Self {
+    pub fn new() -> Result<Self> {
+        let config: AudioModuleConfig = RUNTIME_CONFIG.get().module(AUDIO_MODULE_IDENTIFIER)?;
+
+        let preferred = if config.remember {
+            read_preferred_sink()
+                .map_err(|e| warn!("failed to read preferred sink: {e:#}"))
+                .ok()
+                .flatten()
+        } else {
+            None
+        };
+
         info!("starting pipewire integration thread");
 
-        Self {
+        Ok(Self {
+            config,
+
             pipewire: Arc::new(PipewireInstance::start()),
+            status: PipewireStatus::Connecting,
 
             defaults: DefaultState::default(),
             sinks: Vec::new(),
             sources: Vec::new(),
 
+            sink_id: None,
+            source_id: None,
+
             selected_sink: None,
             selected_source: None,
-        }
+
+            optimistic: None,
+
+            preferred,
+            preferred_present: false,
+
+            sinks_known: false,
+        })
     }
 }
 
@@ -72,51 +205,138 @@ impl Module for AudioModule {
 
     fn subscribe(&self) -> Subscription<Self::Message> {
         Subscription::batch(vec![
+            from_recipe(StatusMonitor(self.pipewire.clone())).map(AudioMessage::Status),
             from_recipe(DefaultMonitor(self.pipewire.clone())).map(AudioMessage::DefaultState),
             from_recipe(SinksMonitor(self.pipewire.clone())).map(AudioMessage::SinkState),
             from_recipe(SourcesMonitor(self.pipewire.clone())).map(AudioMessage::SourceState),
         ])
     }
 
+    fn pass_message(&self, message: &str) -> Option<Self::Message> {
+        let passed = PassedMessage::parse(message);
+
+        match (passed.action, passed.get("mute")) {
+            (Some("mute" | "mute-toggle"), _) => Some(AudioMessage::ToggleMute),
+            (_, Some("on")) => Some(AudioMessage::SetMute(true)),
+            (_, Some("off")) => Some(AudioMessage::SetMute(false)),
+            (Some("osd"), _) => Some(AudioMessage::ShowOsd),
+            (Some("picker"), _) => Some(AudioMessage::ShowPicker),
+            _ => {
+                // the volume is set in percent, or changed relatively if signed
+                let current = self
+                    .selected_sink
+                    .as_ref()
+                    .and_then(|sink| sink.volume.first())
+                    .map(|volume| *volume as f64 * 100f64)
+                    .unwrap_or_default();
+
+                if let Some(number) = passed.number("volume") {
+                    let target = number.apply(current).max(0f64);
+                    Some(AudioMessage::ChangeVolume(((target - current) / 100f64) as f32))
+                } else {
+                    info!("passed unknown message {message} to audio");
+                    None
+                }
+            }
+        }
+    }
+
     fn update(&mut self, message: &Self::Message) -> (Task<Self::Message>, Option<OsdId>) {
         match (message, &self.selected_sink) {
-            (AudioMessage::DefaultState(defaults), _) => self.defaults = defaults.clone(),
-            (AudioMessage::SinkState(nodes), _) => self.sinks = nodes.clone(),
+            (AudioMessage::Status(status), _) => {
+                if *status == PipewireStatus::Failed {
+                    // nothing of the lost connection is valid anymore
+                    self.sinks.clear();
+                    self.sources.clear();
+                    self.sinks_known = false;
+                }
+
+                self.status = *status;
+            }
+            (AudioMessage::DefaultState(defaults), _) => {
+                if defaults.sink != self.defaults.sink {
+                    self.sink_id = None;
+                }
+                if defaults.source != self.defaults.source {
+                    self.source_id = None;
+                }
+
+                self.defaults = defaults.clone()
+            }
+            (AudioMessage::SinkState(nodes), _) => {
+                let previous = mem::replace(&mut self.sinks, nodes.clone());
+                self.apply_optimistic();
+                self.assert_preferred();
+                self.follow_new(&previous);
+            }
             (AudioMessage::SourceState(nodes), _) => self.sources = nodes.clone(),
 
             (AudioMessage::ToggleMute, Some(selected)) => {
                 self.pipewire.set_mute(&selected.name, !selected.mute).ok();
             }
+            (AudioMessage::SetMute(mute), Some(selected)) => {
+                self.pipewire.set_mute(&selected.name, *mute).ok();
+            }
+            (AudioMessage::SelectSink(name), _) => {
+                self.pipewire.set_default_sink(name).ok();
+
+                if self.config.remember {
+                    if let Err(e) = write_preferred_sink(name) {
+                        error!("failed to store preferred sink: {e:#}");
+                    }
+
+                    self.preferred = Some(name.clone());
+                    self.preferred_present = true;
+                }
+            }
             (AudioMessage::ChangeVolume(offset), Some(selected)) => {
-                self.pipewire
-                    .set_volume(
-                        &selected.name,
-                        &selected.volume.iter().map(|v| v + offset).collect::<Vec<_>>(),
-                    )
-                    .ok();
+                // nodes briefly report no channels while they are being reconfigured
+                if selected.volume.is_empty() {
+                    debug!("not changing volume of `{}`, it has no channels", selected.name);
+                } else {
+                    let volume = selected
+                        .volume
+                        .iter()
+                        .map(|v| (v + offset).clamp(0f32, self.config.max_volume))
+                        .collect::<Vec<_>>();
+
+                    if self.pipewire.set_volume(&selected.name, &volume).is_ok() {
+                        // show the new volume right away instead of waiting for pipewire
+                        self.optimistic = Some((selected.id, volume, Instant::now()));
+                        self.apply_optimistic();
+                    }
+                }
             }
             _ => {}
         };
 
+        self.sink_id = self.resolve_id(self.sink_id, &self.sinks, &self.defaults.sink);
+        self.source_id = self.resolve_id(self.source_id, &self.sources, &self.defaults.source);
+
         let sink = self.selected_sink.take();
         let source = self.selected_source.take();
-        self.selected_sink =
-            self.sinks.iter().find(|sink| sink.name == self.defaults.sink).cloned();
+        self.selected_sink = self.sinks.iter().find(|sink| Some(sink.id) == self.sink_id).cloned();
         self.selected_source =
-            self.sources.iter().find(|source| source.name == self.defaults.source).cloned();
+            self.sources.iter().find(|source| Some(source.id) == self.source_id).cloned();
 
-        let osd = if sink.is_some()
+        let osd = if let AudioMessage::ShowPicker = message {
+            Some(OSD_PICKER)
+        } else if let AudioMessage::ShowOsd = message
+            && let Some(ref selected) = self.selected_sink
+        {
+            Some(selected.id as OsdId) // sinks have no kind bits
+        } else if sink.is_some()
             && self.selected_sink.is_some()
             && self.selected_sink != sink
             && let Some(ref selected) = self.selected_sink
         {
-            Some(selected.id)
+            Some(selected.id as OsdId)
         } else if source.is_some()
             && self.selected_source.is_some()
             && self.selected_source != source
             && let Some(ref selected) = self.selected_source
         {
-            Some(OSD_SOURCE_FLAG | selected.id)
+            Some(OSD_SOURCE | selected.id as OsdId)
         } else {
             None
         };
@@ -124,11 +344,61 @@ impl Module for AudioModule {
         (Task::none(), osd)
     }
 
+    fn snapshot(&self) -> Value {
+        fn node(node: &Option<NodeState>) -> Value {
+            node.as_ref()
+                .map(|node| {
+                    json!({
+                        "name": node.name,
+                        "description": node.description,
+                        "volume": node.average_volume(),
+                        "mute": node.mute,
+                    })
+                })
+                .unwrap_or(Value::Null)
+        }
+
+        json!({
+            "available": self.status != PipewireStatus::Failed,
+            "sink": node(&self.selected_sink),
+            "source": node(&self.selected_source),
+        })
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        if self.status == PipewireStatus::Failed {
+            return Some("Audio is unavailable".to_string());
+        }
+
+        let sink = self.selected_sink.as_ref()?;
+
+        Some(if sink.mute {
+            format!("{} (muted)", sink.description)
+        } else {
+            format!("{} ({:.0}%)", sink.description, sink.average_volume() * 100f32)
+        })
+    }
+
+    fn waybar(&self) -> WaybarState {
+        let state = WaybarState::new(self.tooltip());
+
+        match self.selected_sink.as_ref() {
+            _ if self.status == PipewireStatus::Failed => state.class("unavailable"),
+            Some(sink) if sink.mute => state.fraction(sink.average_volume() as f64).class("muted"),
+            Some(sink) => state.fraction(sink.average_volume() as f64),
+            None => state,
+        }
+    }
+
     fn has_status(&self) -> bool {
         true
     }
 
     fn render_status(&self) -> Element<'_, Self::Message, Theme, Renderer> {
+        if self.status == PipewireStatus::Failed {
+            return icon(Icon::VolumeOff).color(CONFIG.colors.warning).into();
+        }
+
         let Some(sink) = self.selected_sink.as_ref() else {
             return icon(Icon::VolumeOff).into();
         };
@@ -152,27 +422,58 @@ impl Module for AudioModule {
             .into()
         };
 
-        mouse_area(icon)
-            .on_scroll(|event| match event {
-                ScrollDelta::Lines { y, .. } => AudioMessage::ChangeVolume(y * 0.05),
-                ScrollDelta::Pixels { y, .. } => AudioMessage::ChangeVolume(y * -0.005), // natural scrolling, fear me
-            })
-            .on_release(AudioMessage::ToggleMute)
-            .into()
+        let direction = if self.config.invert_scroll { -1f32 } else { 1f32 };
+        let acceleration = self.config.scroll_acceleration;
+
+        let mut area = mouse_area(icon).on_scroll(move |delta| {
+            AudioMessage::ChangeVolume(direction * scroll_change(delta, 0.05, acceleration))
+        });
+
+        for (action, button) in &self.config.buttons {
+            let message = match action {
+                AudioAction::Mute => AudioMessage::ToggleMute,
+                AudioAction::Osd => AudioMessage::ShowOsd,
+                AudioAction::Picker => AudioMessage::ShowPicker,
+            };
+
+            area = match button {
+                MouseButton::Left => area.on_release(message),
+                MouseButton::Right => area.on_right_release(message),
+                MouseButton::Middle => area.on_middle_release(message),
+            };
+        }
+
+        area.into()
+    }
+
+    fn osd_interactive(&self, id: OsdId) -> bool {
+        id & OSD_KIND_MASK == OSD_PICKER
+    }
+
+    fn main_osd(&self) -> Option<OsdId> {
+        self.selected_sink.as_ref().map(|sink| sink.id as OsdId) // sinks have no kind bits
+    }
+
+    fn osd_timeout(&self, id: OsdId) -> Option<Duration> {
+        // the picker is shown as long as any other osd
+        self.config
+            .osd_timeout
+            .filter(|_| id & OSD_KIND_MASK != OSD_PICKER)
+            .map(Duration::from_millis)
     }
 
     fn render_osd(&self, id: OsdId) -> Element<'_, Self::Message, Theme, Renderer> {
-        let (volume, symbol) = if id & OSD_SOURCE_FLAG == 0
-            && let Some(sink) = self.selected_sink.as_ref()
-        {
-            (sink.average_volume(), if sink.mute { Icon::VolumeX } else { Icon::Volume2 })
-        } else if id & OSD_SOURCE_FLAG != 0
-            && let Some(source) = self.selected_source.as_ref()
-        {
-            (source.average_volume(), if source.mute { Icon::MicOff } else { Icon::Mic })
-        } else {
-            (0f32, Icon::VolumeOff)
-        };
+        let (volume, symbol) =
+            match (id & OSD_KIND_MASK, &self.selected_sink, &self.selected_source) {
+                (OSD_PICKER, _, _) => return self.render_picker(),
+                (OSD_SINK, Some(sink), _) => {
+                    (sink.average_volume(), if sink.mute { Icon::VolumeX } else { sink_icon(sink) })
+                }
+                (OSD_SOURCE, _, Some(source)) => {
+                    (source.average_volume(), if source.mute { Icon::MicOff } else { Icon::Mic })
+                }
+                _ => (0f32, Icon::VolumeOff),
+            };
 
         column![vertical_progress(volume, 100f32, 4f32, 6f32), icon(symbol).size(20)]
             .padding(Padding::ZERO.top(CONFIG.looks.width as f32 / 2f32 - 2f32).bottom(8))
@@ -182,6 +483,110 @@ impl Module for AudioModule {
     }
 }
 
+impl AudioModule {
+    /// returns the id of the default node with the given name, keeping the
+    /// previous one as long as that node exists
+    fn resolve_id(&self, previous: Option<u32>, nodes: &[NodeState], name: &str) -> Option<u32> {
+        previous
+            .filter(|id| nodes.iter().any(|node| node.id == *id))
+            .or_else(|| self.pipewire.node_id(name))
+    }
+
+    /// makes the preferred sink the default once it (re)appears, as it may be
+    /// missing on startup or be unplugged in between
+    fn assert_preferred(&mut self) {
+        let Some(ref preferred) = self.preferred else { return };
+
+        let present = self.sinks.iter().any(|sink| sink.name == *preferred);
+        if present && !self.preferred_present && self.defaults.sink != *preferred {
+            info!("making preferred sink `{preferred}` the default");
+            self.pipewire.set_default_sink(preferred).ok();
+        }
+
+        self.preferred_present = present;
+    }
+
+    /// makes the newest of the sinks which just appeared the default, if it
+    /// matches one of the followed prefixes
+    fn follow_new(&mut self, previous: &[NodeState]) {
+        let known = mem::replace(&mut self.sinks_known, true);
+        if !known || self.config.follow.is_empty() {
+            return;
+        }
+
+        let Some(sink) = self
+            .sinks
+            .iter()
+            .filter(|sink| !previous.iter().any(|other| other.id == sink.id))
+            .filter(|sink| self.config.follow.iter().any(|prefix| sink.name.starts_with(prefix)))
+            .max_by_key(|sink| sink.id)
+        else {
+            return;
+        };
+
+        if self.defaults.sink != sink.name {
+            info!("following new sink `{}` as the default", sink.name);
+            self.pipewire.set_default_sink(&sink.name).ok();
+        }
+    }
+
+    /// overrides the volume of the sink we last set with the optimistic one,
+    /// dropping it once pipewire reports it or it timed out
+    fn apply_optimistic(&mut self) {
+        let Some((id, volume, since)) = &self.optimistic else { return };
+
+        let Some(node) = self.sinks.iter_mut().find(|node| node.id == *id) else {
+            self.optimistic = None;
+            return;
+        };
+
+        let confirmed = node.volume.len() == volume.len()
+            && node.volume.iter().zip(volume).all(|(a, b)| (a - b).abs() < 0.001);
+
+        if confirmed || since.elapsed() > OPTIMISTIC_TIMEOUT {
+            self.optimistic = None;
+        } else {
+            node.volume = volume.clone();
+        }
+    }
+
+    /// renders a list of all sinks which can be clicked to make them the default
+    fn render_picker(&self) -> Element<'_, AudioMessage, Theme, Renderer> {
+        Column::from_iter(self.sinks.iter().map(|sink| {
+            let selected = Some(sink.id) == self.sink_id;
+
+            mouse_area(
+                container(text(&sink.description).size(12))
+                    .style(move |_| container::Style {
+                        background: selected.then_some(Background::Color(CONFIG.looks.semi)),
+                        border: Border::default().rounded(6),
+                        ..Default::default()
+                    })
+                    .padding(6)
+                    .width(Length::Fill),
+            )
+            .on_release(AudioMessage::SelectSink(sink.name.clone()))
+            .into()
+        }))
+        .padding(8)
+        .spacing(4)
+        .into()
+    }
+}
+
+/// guesses a fitting icon for a sink based on its name
+fn sink_icon(sink: &NodeState) -> Icon {
+    let name = format!("{} {}", sink.name, sink.description).to_lowercase();
+
+    if ["headphone", "headset", "bluez"].iter().any(|pattern| name.contains(pattern)) {
+        Icon::Headphones
+    } else if name.contains("hdmi") || name.contains("displayport") {
+        Icon::MonitorSpeaker
+    } else {
+        Icon::Volume2
+    }
+}
+
 struct SinksMonitor(Arc<PipewireInstance>);
 
 impl Recipe for SinksMonitor {
@@ -201,6 +606,22 @@ impl Recipe for SinksMonitor {
     }
 }
 
+struct StatusMonitor(Arc<PipewireInstance>);
+
+impl Recipe for StatusMonitor {
+    type Output = PipewireStatus;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str("audio connection status");
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("staring audio connection status listener");
+
+        self.0.listen_status()
+    }
+}
+
 struct SourcesMonitor(Arc<PipewireInstance>);
 
 impl Recipe for SourcesMonitor {
@@ -220,6 +641,30 @@ impl Recipe for SourcesMonitor {
     }
 }
 
+/// reads the sink which was last picked from the state file
+fn read_preferred_sink() -> Result<Option<String>> {
+    let path = state_path(PREFERRED_SINK_STATE)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let name = fs::read_to_string(&path)
+        .with_context(|| format!("failed to read `{}`", path.to_string_lossy()))?;
+
+    Ok(Some(name.trim().to_string()).filter(|name| !name.is_empty()))
+}
+
+/// stores the sink which was picked in the state file
+fn write_preferred_sink(name: &str) -> Result<()> {
+    let path = state_path(PREFERRED_SINK_STATE)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("failed to create state directory")?;
+    }
+
+    fs::write(&path, name).with_context(|| format!("failed to write `{}`", path.to_string_lossy()))
+}
+
 struct DefaultMonitor(Arc<PipewireInstance>);
 
 impl Recipe for DefaultMonitor {
@@ -238,3 +683,28 @@ impl Recipe for DefaultMonitor {
         stream
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{OSD_KIND_MASK, OSD_PICKER, OSD_SINK, OSD_SOURCE};
+    use crate::osd::OsdId;
+
+    #[test]
+    fn osd_kind_survives_node_id() {
+        for node in [0, 1 << 30, u32::MAX] {
+            for kind in [OSD_SINK, OSD_SOURCE, OSD_PICKER] {
+                let id = kind | node as OsdId;
+
+                assert_eq!(id & OSD_KIND_MASK, kind);
+                assert_eq!(id & !OSD_KIND_MASK, node as OsdId);
+            }
+        }
+    }
+
+    #[test]
+    fn osd_kinds_are_distinct() {
+        assert_ne!(OSD_SINK, OSD_SOURCE);
+        assert_ne!(OSD_SINK, OSD_PICKER);
+        assert_ne!(OSD_SOURCE, OSD_PICKER);
+    }
+}
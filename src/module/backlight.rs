@@ -1,4 +1,4 @@
-use std::hash::Hasher;
+use std::{hash::Hasher, time::Duration};
 
 use anyhow::{Context, Result, anyhow};
 use async_trait::async_trait;
@@ -7,63 +7,109 @@ use iced::{
     Element, Padding, Renderer, Subscription, Task, Theme,
     advanced::subscription::{EventStream, Recipe, from_recipe},
     alignment::Horizontal,
-    widget::column,
+    widget::{column, text},
 };
 use iced_winit::futures::BoxStream;
-use liischte_lib::sysfs::backlight::BacklightDevice;
+use liischte_lib::{StreamContext, sysfs::backlight::BacklightDevice};
 use log::{debug, error, info};
 use lucide_icons::Icon;
 use serde::Deserialize;
+use serde_json::{Value, json};
 
 use crate::{
-    config::CONFIG,
+    config::{CONFIG, RUNTIME_CONFIG},
     osd::OsdId,
     ui::{icon, progress::vertical_progress},
 };
 
-use super::{Module, ModuleMessage};
+use super::{
+    Module, ModuleMessage, ModuleRegistry, PassedMessage, WaybarState, deserialize_config,
+};
 
 pub const BACKLIGHT_MODULE_IDENTIFIER: &str = "backlight";
 
-#[derive(Deserialize, Default)]
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    registry.add(
+        BACKLIGHT_MODULE_IDENTIFIER,
+        BacklightModule::new,
+        deserialize_config::<BacklightModuleConfig>,
+    );
+}
+
+#[derive(Deserialize)]
 #[serde(default)]
-struct BacklightModuleConfig {
-    /// force the use of a specific backlight (we use the first one otherwise)
+pub struct BacklightModuleConfig {
+    /// force the use of a specific backlight (we use a preferred one otherwise)
     device: Option<String>,
+    /// backlights which are tried in order before falling back to the first
+    prefer: Vec<String>,
+
+    /// whether to show the brightness as a percentage in the osd
+    show_percent: bool,
+    /// time the osd is shown in milliseconds, none for the osd timeout
+    osd_timeout: Option<u64>,
+}
+
+impl Default for BacklightModuleConfig {
+    fn default() -> Self {
+        Self {
+            device: None,
+            prefer: vec!["intel_backlight".to_string(), "amdgpu_bl1".to_string()],
+            show_percent: false,
+            osd_timeout: None,
+        }
+    }
 }
 
 impl ModuleMessage for BacklightModulemessage {}
 #[derive(Clone, Debug)]
 pub enum BacklightModulemessage {
     Brightness(f64),
+    SetBrightness(f64),
+    Ok,
 }
 
 pub struct BacklightModule {
+    config: BacklightModuleConfig,
+
     backlight: BacklightDevice,
     brightness: f64,
 }
 
 impl BacklightModule {
     pub async fn new() -> Result<Self> {
-        let config: BacklightModuleConfig = CONFIG.module(BACKLIGHT_MODULE_IDENTIFIER);
+        let config: BacklightModuleConfig =
+            RUNTIME_CONFIG.get().module(BACKLIGHT_MODULE_IDENTIFIER)?;
 
         info!("reading available backlight devices from sysfs");
-        let mut selected = None;
-
-        for device in BacklightDevice::read_all().await.context("failed to read power devices")? {
-            debug!("checking backlight device with name `{}`", device.device.name);
+        let mut devices =
+            BacklightDevice::read_all().await.context("failed to read backlight devices")?;
 
-            if selected.is_none()
-                && (config.device.as_ref() == Some(&device.device.name) || config.device.is_none())
-            {
-                selected = Some(device);
-            }
+        for device in &devices {
+            debug!("found backlight device with name `{}`", device.device.name);
         }
 
+        // the forced device is the only one allowed, otherwise the preferred ones come first
+        let order = match config.device {
+            Some(ref device) => vec![device.clone()],
+            None => config.prefer.clone(),
+        };
+
+        let selected = order
+            .iter()
+            .find_map(|name| devices.iter().position(|device| device.device.name == *name))
+            .or((config.device.is_none() && !devices.is_empty()).then_some(0))
+            .map(|index| devices.swap_remove(index));
+
         if let Some(selected) = selected {
-            info!("using backlight {}", selected.device.name);
+            if order.contains(&selected.device.name) {
+                info!("using backlight {}", selected.device.name);
+            } else {
+                info!("using backlight {}, as no preferred one is available", selected.device.name);
+            }
 
-            Ok(Self { brightness: selected.read_brightness().await?, backlight: selected })
+            Ok(Self { brightness: selected.read_brightness().await?, backlight: selected, config })
         } else {
             Err(anyhow!("desired backlight device was not found"))
         }
@@ -78,14 +124,59 @@ impl Module for BacklightModule {
         from_recipe(BrightnessMonitor(self.backlight.clone())).map(Self::Message::Brightness)
     }
 
+    fn pass_message(&self, message: &str) -> Option<Self::Message> {
+        // brightness is set in percent, or changed relatively if signed
+        if let Some(number) = PassedMessage::parse(message).number("set") {
+            let percent = number.apply(self.brightness * 100f64);
+            Some(BacklightModulemessage::SetBrightness(percent / 100f64))
+        } else {
+            info!("passed unknown message {message} to backlight");
+            None
+        }
+    }
+
     fn update(&mut self, message: &Self::Message) -> (Task<Self::Message>, Option<OsdId>) {
         match message {
             BacklightModulemessage::Brightness(b) => self.brightness = *b,
+            BacklightModulemessage::SetBrightness(brightness) => {
+                let backlight = self.backlight.clone();
+                let brightness = *brightness;
+
+                return (
+                    Task::future(async move {
+                        backlight
+                            .set_brightness(brightness)
+                            .await
+                            .stream_log("failed to set brightness");
+
+                        BacklightModulemessage::Ok
+                    }),
+                    None,
+                );
+            }
+            BacklightModulemessage::Ok => return (Task::none(), None),
         }
 
         (Task::none(), Some(0))
     }
 
+    fn snapshot(&self) -> Value {
+        json!({ "device": self.backlight.device.name, "brightness": self.brightness })
+    }
+
+    fn waybar(&self) -> WaybarState {
+        WaybarState::new(Some(format!("{:.0}%", self.brightness * 100f64)))
+            .fraction(self.brightness)
+    }
+
+    fn main_osd(&self) -> Option<OsdId> {
+        Some(0)
+    }
+
+    fn osd_timeout(&self, _id: OsdId) -> Option<Duration> {
+        self.config.osd_timeout.map(Duration::from_millis)
+    }
+
     fn render_osd(&self, _id: OsdId) -> Element<'_, Self::Message, Theme, Renderer> {
         let symbol = match () {
             _ if self.brightness > 0.66 => Icon::Sun,
@@ -93,14 +184,20 @@ impl Module for BacklightModule {
             _ => Icon::SunDim,
         };
 
-        column![
+        let mut content = column![
             vertical_progress(self.brightness as f32, 100f32, 4f32, 6f32),
             icon(symbol).size(20)
-        ]
-        .padding(Padding::ZERO.top(CONFIG.looks.width as f32 / 2f32 - 2f32).bottom(8))
-        .spacing(8)
-        .align_x(Horizontal::Center)
-        .into()
+        ];
+
+        if self.config.show_percent {
+            content = content.push(text!("{:.0}%", (self.brightness * 100f64).round()).size(12));
+        }
+
+        content
+            .padding(Padding::ZERO.top(CONFIG.looks.width as f32 / 2f32 - 2f32).bottom(8))
+            .spacing(8)
+            .align_x(Horizontal::Center)
+            .into()
     }
 }
 
@@ -0,0 +1,130 @@
+use std::{hash::Hasher as _, time::Duration};
+
+use anyhow::{Context, Result};
+use iced::{
+    Element, Renderer, Subscription, Task, Theme,
+    advanced::subscription::{EventStream, Hasher, Recipe, from_recipe},
+    alignment::Horizontal,
+    widget::{column, text},
+};
+use iced_winit::futures::BoxStream;
+use liischte_lib::sysfs::{
+    Device,
+    cpufreq::{listen_cpu_frequency, read_cpu_frequency, read_cpus},
+};
+use log::{debug, info};
+use lucide_icons::Icon;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    config::{RUNTIME_CONFIG, deserialize_duration_seconds},
+    module::{Module, ModuleMessage, ModuleRegistry, deserialize_config},
+    osd::OsdId,
+    ui::icon,
+};
+
+pub const CPUFREQ_MODULE_IDENTIFIER: &str = "cpufreq";
+
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    registry.add(
+        CPUFREQ_MODULE_IDENTIFIER,
+        CpuFreqModule::new,
+        deserialize_config::<CpuFreqModuleConfig>,
+    );
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct CpuFreqModuleConfig {
+    /// polling rate to poll the frequency in seconds
+    #[serde(deserialize_with = "deserialize_duration_seconds")]
+    polling_rate: Duration,
+}
+
+impl Default for CpuFreqModuleConfig {
+    fn default() -> Self {
+        Self { polling_rate: Duration::from_secs(2) }
+    }
+}
+
+impl ModuleMessage for CpuFreqMessage {}
+#[derive(Clone, Debug)]
+pub enum CpuFreqMessage {
+    Frequency(u64),
+}
+
+pub struct CpuFreqModule {
+    config: CpuFreqModuleConfig,
+
+    cpus: Vec<Device>,
+    frequency: u64, // in MHz
+}
+
+impl CpuFreqModule {
+    pub async fn new() -> Result<Self> {
+        let config: CpuFreqModuleConfig = RUNTIME_CONFIG.get().module(CPUFREQ_MODULE_IDENTIFIER)?;
+
+        info!("reading available cpus from sysfs");
+        let cpus = read_cpus().await.context("failed to read cpus with frequency scaling")?;
+        debug!("averaging the frequency of {} cpus", cpus.len());
+
+        Ok(Self { frequency: read_cpu_frequency(&cpus).await?, cpus, config })
+    }
+}
+
+impl Module for CpuFreqModule {
+    type Message = CpuFreqMessage;
+
+    fn subscribe(&self) -> Subscription<Self::Message> {
+        from_recipe(FrequencyMonitor(self.cpus.clone(), self.config.polling_rate))
+            .map(CpuFreqMessage::Frequency)
+    }
+
+    fn update(&mut self, message: &Self::Message) -> (Task<Self::Message>, Option<OsdId>) {
+        match message {
+            CpuFreqMessage::Frequency(frequency) => self.frequency = *frequency,
+        }
+
+        (Task::none(), None)
+    }
+
+    fn snapshot(&self) -> Value {
+        json!({ "frequency": self.frequency, "cpus": self.cpus.len() })
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        Some(format!("Average cpu frequency of {} MHz", self.frequency))
+    }
+
+    fn has_status(&self) -> bool {
+        true
+    }
+
+    fn render_status(&self) -> Element<'_, Self::Message, Theme, Renderer> {
+        column![icon(Icon::Cpu), text(format_frequency(self.frequency)).size(12)]
+            .align_x(Horizontal::Center)
+            .into()
+    }
+}
+
+/// formats a frequency in MHz to a short string which fits the bar
+fn format_frequency(mhz: u64) -> String {
+    if mhz >= 1000 { format!("{:.1}G", mhz as f64 / 1000f64) } else { format!("{mhz}M") }
+}
+
+struct FrequencyMonitor(Vec<Device>, Duration);
+
+impl Recipe for FrequencyMonitor {
+    type Output = u64;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str("cpu frequency events");
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("starting cpu frequency listener");
+        listen_cpu_frequency(self.0, self.1)
+    }
+}
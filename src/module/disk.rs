@@ -0,0 +1,166 @@
+use std::{hash::Hasher as _, path::PathBuf, time::Duration};
+
+use anyhow::{Context, Result};
+use iced::{
+    Element, Padding, Renderer, Subscription, Task, Theme,
+    advanced::subscription::{EventStream, Hasher, Recipe, from_recipe},
+    alignment::Horizontal,
+    widget::{column, mouse_area, text},
+};
+use iced_winit::futures::BoxStream;
+use liischte_lib::disk::{DiskUsage, listen_disk_usage, read_disk_usage};
+use log::{debug, info};
+use lucide_icons::Icon;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    config::{CONFIG, RUNTIME_CONFIG, deserialize_duration_seconds},
+    module::{Module, ModuleMessage, ModuleRegistry, deserialize_config},
+    osd::OsdId,
+    ui::{
+        icon,
+        progress::{bar_progress, vertical_progress},
+    },
+};
+
+pub const DISK_MODULE_IDENTIFIER: &str = "disk";
+
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    registry.add(DISK_MODULE_IDENTIFIER, DiskModule::new, deserialize_config::<DiskModuleConfig>);
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct DiskModuleConfig {
+    /// mount point of the filesystem to show
+    mount: PathBuf,
+
+    /// polling rate to poll the usage in seconds
+    #[serde(deserialize_with = "deserialize_duration_seconds")]
+    polling_rate: Duration,
+}
+
+impl Default for DiskModuleConfig {
+    fn default() -> Self {
+        Self { mount: PathBuf::from("/"), polling_rate: Duration::from_mins(1) }
+    }
+}
+
+impl ModuleMessage for DiskMessage {}
+#[derive(Clone, Debug)]
+pub enum DiskMessage {
+    Usage(DiskUsage),
+    ShowDetails,
+}
+
+pub struct DiskModule {
+    config: DiskModuleConfig,
+
+    usage: DiskUsage,
+}
+
+impl DiskModule {
+    pub async fn new() -> Result<Self> {
+        let config: DiskModuleConfig = RUNTIME_CONFIG.get().module(DISK_MODULE_IDENTIFIER)?;
+
+        info!("showing disk usage of `{}`", config.mount.to_string_lossy());
+        let usage = read_disk_usage(&config.mount).await.context("failed to read disk usage")?;
+
+        Ok(Self { config, usage })
+    }
+}
+
+impl Module for DiskModule {
+    type Message = DiskMessage;
+
+    fn subscribe(&self) -> Subscription<Self::Message> {
+        from_recipe(DiskUsageMonitor(self.config.mount.clone(), self.config.polling_rate))
+            .map(DiskMessage::Usage)
+    }
+
+    fn update(&mut self, message: &Self::Message) -> (Task<Self::Message>, Option<OsdId>) {
+        match message {
+            DiskMessage::Usage(usage) => {
+                self.usage = *usage;
+                (Task::none(), None)
+            }
+            DiskMessage::ShowDetails => (Task::none(), Some(0)),
+        }
+    }
+
+    fn snapshot(&self) -> Value {
+        json!({
+            "mount": self.config.mount,
+            "total": self.usage.total,
+            "used": self.usage.used,
+            "available": self.usage.available,
+        })
+    }
+
+    fn has_status(&self) -> bool {
+        true
+    }
+
+    fn render_status(&self) -> Element<'_, Self::Message, Theme, Renderer> {
+        mouse_area(bar_progress(self.usage.fraction() as f32, CONFIG.looks.icon_size, 4f32, 6f32))
+            .on_release(DiskMessage::ShowDetails)
+            .into()
+    }
+
+    fn main_osd(&self) -> Option<OsdId> {
+        Some(0)
+    }
+
+    fn render_osd(&self, _id: OsdId) -> Element<'_, Self::Message, Theme, Renderer> {
+        column![
+            vertical_progress(self.usage.fraction() as f32, 100f32, 4f32, 6f32),
+            icon(Icon::HardDrive).size(20),
+            column![
+                text(format_bytes(self.usage.used)).size(12),
+                text(format_bytes(self.usage.total)).size(12)
+            ]
+            .align_x(Horizontal::Center)
+        ]
+        .padding(Padding::ZERO.top(CONFIG.looks.width as f32 / 2f32 - 2f32).bottom(8))
+        .spacing(8)
+        .align_x(Horizontal::Center)
+        .into()
+    }
+}
+
+/// formats an amount of bytes to a short human readable string
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "K", "M", "G", "T", "P"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    while value >= 1024f64 && unit < UNITS.len() - 1 {
+        value /= 1024f64;
+        unit += 1;
+    }
+
+    if value < 10f64 && unit > 0 {
+        format!("{value:.1}{}", UNITS[unit])
+    } else {
+        format!("{value:.0}{}", UNITS[unit])
+    }
+}
+
+struct DiskUsageMonitor(PathBuf, Duration);
+
+impl Recipe for DiskUsageMonitor {
+    type Output = DiskUsage;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str("disk usage events");
+        state.write_str(&self.0.to_string_lossy());
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("starting disk usage listener for `{}`", self.0.to_string_lossy());
+        listen_disk_usage(self.0, self.1)
+    }
+}
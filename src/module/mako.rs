@@ -15,21 +15,32 @@ use liischte_lib::{StreamContext, mako::Mako};
 use log::debug;
 use lucide_icons::Icon;
 use serde::Deserialize;
+use serde_json::{Value, json};
 
 use crate::{
-    config::{CONFIG, deserialize_icon},
-    module::{Module, ModuleMessage},
+    config::{RUNTIME_CONFIG, deserialize_icon},
+    module::{Module, ModuleMessage, ModuleRegistry, deserialize_config},
     osd::OsdId,
     ui::icon,
 };
 
 pub const MAKO_MODULE_IDENTIFIER: &str = "mako";
 
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    registry.add(MAKO_MODULE_IDENTIFIER, MakoModule::new, deserialize_config::<MakoModuleConfig>);
+}
+
 #[derive(Deserialize)]
 #[serde(default)]
-struct MakoModuleConfig {
+pub struct MakoModuleConfig {
     /// modes to show an indicator for
     modes: Vec<MakoModuleConfigMode>,
+
+    /// whether to show a status which toggles do not disturb
+    status: bool,
+    /// mode which is toggled for do not disturb
+    dnd_mode: String,
 }
 
 #[derive(Deserialize)]
@@ -48,6 +59,9 @@ impl Default for MakoModuleConfig {
                 name: "do-not-disturb".into(),
                 icon: Icon::CircleMinus,
             }],
+
+            status: true,
+            dnd_mode: "do-not-disturb".into(),
         }
     }
 }
@@ -59,6 +73,8 @@ pub enum MakoMessage {
     Modes(Vec<String>),
     /// disable a given mode
     Disable(String),
+    /// toggle the do not disturb mode
+    ToggleDnd,
 }
 
 pub struct MakoModule {
@@ -71,13 +87,30 @@ pub struct MakoModule {
 impl MakoModule {
     pub async fn new() -> Result<Self> {
         Ok(Self {
-            config: CONFIG.module(MAKO_MODULE_IDENTIFIER),
+            config: RUNTIME_CONFIG.get().module(MAKO_MODULE_IDENTIFIER)?,
             mako: Mako::connnect().await.context("failed to connect to mako")?,
             modes: vec![],
         })
     }
 }
 
+impl MakoModule {
+    /// returns whether the do not disturb mode is active
+    fn dnd(&self) -> bool {
+        self.modes.contains(&self.config.dnd_mode)
+    }
+
+    /// sets the active modes of mako
+    fn set_modes(&self, modes: Vec<String>) -> Task<MakoMessage> {
+        let mako = self.mako.clone();
+
+        Task::future(async move {
+            mako.set_modes(&modes).await.stream_log("failed to change modes for mako")
+        })
+        .discard()
+    }
+}
+
 impl Module for MakoModule {
     type Message = MakoMessage;
 
@@ -94,19 +127,37 @@ impl Module for MakoModule {
             MakoMessage::Disable(mode) => {
                 let modes =
                     self.modes.iter().filter(|active| *active != mode).cloned().collect::<Vec<_>>();
-                let mako = self.mako.clone();
-
-                (
-                    Task::future(async move {
-                        mako.set_modes(&modes).await.stream_log("failed to change modes for mako")
-                    })
-                    .discard(),
-                    None,
-                )
+
+                (self.set_modes(modes), None)
+            }
+            MakoMessage::ToggleDnd => {
+                let mut modes = self.modes.clone();
+
+                if self.dnd() {
+                    modes.retain(|mode| *mode != self.config.dnd_mode);
+                } else {
+                    modes.push(self.config.dnd_mode.clone());
+                }
+
+                (self.set_modes(modes), None)
             }
         }
     }
 
+    fn snapshot(&self) -> Value {
+        json!({ "modes": self.modes, "dnd": self.dnd() })
+    }
+
+    fn has_status(&self) -> bool {
+        self.config.status
+    }
+
+    fn render_status(&self) -> Element<'_, Self::Message, Theme, Renderer> {
+        mouse_area(icon(if self.dnd() { Icon::BellOff } else { Icon::Bell }))
+            .on_release(MakoMessage::ToggleDnd)
+            .into()
+    }
+
     fn render_info(&self) -> Vec<Element<'_, Self::Message, Theme, Renderer>> {
         self.modes
             .iter()
@@ -0,0 +1,199 @@
+use std::{hash::Hasher as _, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use futures::{FutureExt, StreamExt, stream};
+use iced::{
+    Element, Renderer, Subscription, Task, Theme,
+    advanced::subscription::{EventStream, Hasher, Recipe, from_recipe},
+};
+use iced_winit::futures::BoxStream;
+use liischte_lib::{
+    StreamContext,
+    pipewire::{PipewireInstance, default::DefaultState},
+};
+use log::{debug, info};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::time::{MissedTickBehavior, interval};
+
+use crate::{
+    config::{CONFIG, RUNTIME_CONFIG},
+    module::{Module, ModuleMessage, ModuleRegistry, deserialize_config},
+    osd::OsdId,
+    ui::progress::vertical_progress,
+};
+
+pub const METER_MODULE_IDENTIFIER: &str = "meter";
+
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    registry.add_sync(
+        METER_MODULE_IDENTIFIER,
+        MeterModule::new,
+        deserialize_config::<MeterModuleConfig>,
+    );
+}
+
+/// level below which the meter is hidden, which is about -60db
+const SILENT_LEVEL: f32 = 0.001;
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct MeterModuleConfig {
+    /// how many times per second the meter is updated
+    rate: u32,
+    /// fraction of the level which is kept on each update while it falls
+    falloff: f32,
+}
+
+impl Default for MeterModuleConfig {
+    fn default() -> Self {
+        Self { rate: 30, falloff: 0.8 }
+    }
+}
+
+impl ModuleMessage for MeterMessage {}
+#[derive(Clone, Debug)]
+pub enum MeterMessage {
+    DefaultState(DefaultState),
+    Level(f32),
+}
+
+pub struct MeterModule {
+    config: MeterModuleConfig,
+
+    pipewire: Arc<PipewireInstance>, // this is an arc to implement efficient subscriptions
+
+    /// name of the default sink, which is measured
+    sink: Option<String>,
+    /// linear level the meter shows
+    level: f32,
+}
+
+impl MeterModule {
+    pub fn new() -> Result<Self> {
+        let config = RUNTIME_CONFIG.get().module(METER_MODULE_IDENTIFIER)?;
+
+        info!("starting pipewire integration thread");
+
+        Ok(Self { config, pipewire: Arc::new(PipewireInstance::start()), sink: None, level: 0f32 })
+    }
+}
+
+impl Module for MeterModule {
+    type Message = MeterMessage;
+
+    fn subscribe(&self) -> Subscription<Self::Message> {
+        let mut subscriptions = vec![
+            from_recipe(DefaultMonitor(self.pipewire.clone())).map(MeterMessage::DefaultState),
+        ];
+
+        // this restarts the measurement whenever the default sink changes
+        if let Some(sink) = &self.sink {
+            subscriptions.push(
+                from_recipe(LevelMonitor {
+                    pipewire: self.pipewire.clone(),
+                    sink: sink.clone(),
+                    rate: self.config.rate,
+                    falloff: self.config.falloff,
+                })
+                .map(MeterMessage::Level),
+            );
+        }
+
+        Subscription::batch(subscriptions)
+    }
+
+    fn update(&mut self, message: &Self::Message) -> (Task<Self::Message>, Option<OsdId>) {
+        match message {
+            MeterMessage::DefaultState(state) => {
+                let sink = (!state.sink.is_empty()).then(|| state.sink.clone());
+
+                if sink != self.sink {
+                    self.sink = sink;
+                    self.level = 0f32;
+                }
+            }
+            MeterMessage::Level(level) => self.level = *level,
+        }
+
+        (Task::none(), None)
+    }
+
+    fn snapshot(&self) -> Value {
+        json!({ "sink": self.sink, "level": self.level })
+    }
+
+    fn render_info(&self) -> Vec<Element<'_, Self::Message, Theme, Renderer>> {
+        if self.level < SILENT_LEVEL {
+            return vec![];
+        }
+
+        // like the volume, the level is shown in its visual form
+        vec![vertical_progress(self.level.cbrt(), CONFIG.looks.icon_size, 4f32, 6f32).into()]
+    }
+}
+
+struct DefaultMonitor(Arc<PipewireInstance>);
+
+impl Recipe for DefaultMonitor {
+    type Output = DefaultState;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str("meter default events");
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("staring meter default listener");
+
+        let stream = self.0.listen_defaults();
+        self.0.trigger_update().stream_log("pipewire meter default listener"); // we want to get values immediately
+
+        stream
+    }
+}
+
+/// fires with the level of a sink at the configured rate while it plays,
+/// falling off smoothly once it gets quieter
+struct LevelMonitor {
+    pipewire: Arc<PipewireInstance>,
+    sink: String,
+
+    rate: u32,
+    falloff: f32,
+}
+
+impl Recipe for LevelMonitor {
+    type Output = f32;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str("meter level events");
+        state.write_str(&self.sink);
+        state.write_u32(self.rate);
+        state.write_u32(self.falloff.to_bits());
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("staring meter level listener for `{}`", self.sink);
+
+        let mut ticks = interval(Duration::from_secs(1) / self.rate.max(1));
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let state = (self.pipewire.listen_peak(&self.sink), ticks, self.falloff, 0f32);
+
+        stream::unfold(state, async |(mut peaks, mut ticks, falloff, level)| {
+            // the meter rests once it has fallen, until the sink plays again
+            let mut peak = if level < SILENT_LEVEL { peaks.next().await? } else { 0f32 };
+            ticks.tick().await;
+
+            // buffers arrive faster than we redraw, so the highest one is shown
+            while let Some(next) = peaks.next().now_or_never() {
+                peak = peak.max(next?);
+            }
+
+            let level = peak.max(level * falloff);
+            Some((level, (peaks, ticks, falloff, level)))
+        })
+        .boxed()
+    }
+}
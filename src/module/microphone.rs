@@ -0,0 +1,120 @@
+use std::{hash::Hasher as _, sync::Arc};
+
+use anyhow::Result;
+use iced::{
+    Element, Renderer, Subscription, Task, Theme,
+    advanced::subscription::{EventStream, Hasher, Recipe, from_recipe},
+};
+use iced_winit::futures::BoxStream;
+use liischte_lib::{StreamContext, pipewire::PipewireInstance};
+use log::{debug, info};
+use lucide_icons::Icon;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    config::{CONFIG, RUNTIME_CONFIG, deserialize_icon},
+    module::{Module, ModuleMessage, ModuleRegistry, deserialize_config},
+    osd::OsdId,
+    ui::icon,
+};
+
+pub const MICROPHONE_MODULE_IDENTIFIER: &str = "microphone";
+
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    registry.add_sync(
+        MICROPHONE_MODULE_IDENTIFIER,
+        MicrophoneModule::new,
+        deserialize_config::<MicrophoneModuleConfig>,
+    );
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct MicrophoneModuleConfig {
+    /// icon to show while audio is captured
+    #[serde(deserialize_with = "deserialize_icon")]
+    icon: Icon,
+}
+
+impl Default for MicrophoneModuleConfig {
+    fn default() -> Self {
+        Self { icon: Icon::Mic }
+    }
+}
+
+impl ModuleMessage for MicrophoneMessage {}
+#[derive(Clone, Debug)]
+pub enum MicrophoneMessage {
+    Capturing(bool),
+}
+
+pub struct MicrophoneModule {
+    config: MicrophoneModuleConfig,
+
+    pipewire: Arc<PipewireInstance>, // this is an arc to implement efficient subscriptions
+
+    capturing: bool,
+}
+
+impl MicrophoneModule {
+    pub fn new() -> Result<Self> {
+        let config = RUNTIME_CONFIG.get().module(MICROPHONE_MODULE_IDENTIFIER)?;
+
+        info!("starting pipewire integration thread");
+
+        Ok(Self { config, pipewire: Arc::new(PipewireInstance::start()), capturing: false })
+    }
+}
+
+impl Module for MicrophoneModule {
+    type Message = MicrophoneMessage;
+
+    fn subscribe(&self) -> Subscription<Self::Message> {
+        from_recipe(CaptureMonitor(self.pipewire.clone())).map(MicrophoneMessage::Capturing)
+    }
+
+    fn update(&mut self, message: &Self::Message) -> (Task<Self::Message>, Option<OsdId>) {
+        match message {
+            MicrophoneMessage::Capturing(capturing) => self.capturing = *capturing,
+        }
+
+        (Task::none(), None)
+    }
+
+    fn snapshot(&self) -> Value {
+        json!({ "capturing": self.capturing })
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        self.capturing.then(|| "Microphone is in use".to_string())
+    }
+
+    fn render_info(&self) -> Vec<Element<'_, Self::Message, Theme, Renderer>> {
+        if self.capturing {
+            vec![icon(self.config.icon).color(CONFIG.colors.accent).into()]
+        } else {
+            vec![]
+        }
+    }
+}
+
+struct CaptureMonitor(Arc<PipewireInstance>);
+
+impl Recipe for CaptureMonitor {
+    type Output = bool;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str("audio capture events");
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("staring audio capture listener");
+
+        let stream = self.0.listen_capture_active();
+        self.0.trigger_update().stream_log("pipewire capture listener"); // we want to get values immediately
+
+        stream
+    }
+}
@@ -1,20 +1,73 @@
-use std::{any::TypeId, fmt::Debug};
+use std::{any::TypeId, collections::HashMap, fmt::Debug, sync::LazyLock, time::Duration};
 
+use anyhow::Result;
 use async_trait::async_trait;
 use downcast::{Any, downcast};
 use dyn_clone::{DynClone, clone_trait_object};
+use futures::{
+    FutureExt, TryFutureExt,
+    future::{self, BoxFuture},
+};
 use iced::{Element, Renderer, Subscription, Task, Theme};
-use log::trace;
+use log::{error, trace};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
 
-use crate::osd::OsdId;
+use crate::{config::Config, osd::OsdId};
 
 pub mod audio;
 pub mod backlight;
+pub mod cpufreq;
+pub mod disk;
 pub mod mako;
+pub mod meter;
+pub mod microphone;
+pub mod mpris;
 pub mod network;
 pub mod power;
 pub mod process;
+pub mod recording;
+pub mod submap;
+pub mod thermal;
 pub mod timer;
+pub mod weather;
+pub mod window;
+
+/// state of a module in the json format of waybar's custom modules, so other
+/// bars and scripts can consume it
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct WaybarState {
+    /// text which is shown for the module
+    pub text: String,
+    /// text shown when hovering the module
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tooltip: Option<String>,
+    /// css classes describing the state
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub class: Vec<String>,
+    /// percentage of a value, from 0 to 100
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub percentage: Option<u32>,
+}
+
+impl WaybarState {
+    /// creates a state which shows the tooltip as its text
+    pub fn new(tooltip: Option<String>) -> Self {
+        Self { text: tooltip.clone().unwrap_or_default(), tooltip, ..Default::default() }
+    }
+
+    /// sets the percentage from a fraction, which is rounded
+    pub fn fraction(mut self, fraction: f64) -> Self {
+        self.percentage = Some((fraction.max(0.0) * 100.0).round() as u32);
+        self
+    }
+
+    /// adds a css class to the state
+    pub fn class(mut self, class: &str) -> Self {
+        self.class.push(class.to_string());
+        self
+    }
+}
 
 /// id representing a module (or rather it's message)
 pub type ModuleId = TypeId;
@@ -41,6 +94,47 @@ pub trait Module: Send {
         None
     }
 
+    /// returns a value which is reported back over ipc once a message from
+    /// `pass_message` was handled
+    fn pass_response(&self, _message: &Self::Message) -> Value {
+        Value::Null
+    }
+
+    /// returns a snapshot of the module's current state, which is reported when
+    /// queried over ipc
+    fn snapshot(&self) -> Value {
+        Value::Null
+    }
+
+    /// returns the module's state in the format of a waybar custom module,
+    /// which is reported when queried as json over ipc
+    fn waybar(&self) -> WaybarState {
+        WaybarState::new(self.tooltip())
+    }
+
+    /// reports whether a given osd takes pointer input, which makes it
+    /// interactive and wider than the bar
+    fn osd_interactive(&self, _id: OsdId) -> bool {
+        false
+    }
+
+    /// returns how long a given osd is shown, none for the configured timeout
+    fn osd_timeout(&self, _id: OsdId) -> Option<Duration> {
+        None
+    }
+
+    /// returns the osd which is shown when requested over ipc, none if the
+    /// module has no osd to show right now
+    fn main_osd(&self) -> Option<OsdId> {
+        None
+    }
+
+    /// returns a human readable description of the module's state, which is
+    /// shown when hovering the module in the bar
+    fn tooltip(&self) -> Option<String> {
+        None
+    }
+
     /// reports whether the module has a status indicator
     /// this should stay the same during the whole application lifecycle (use
     /// infos for dynamic appearance)
@@ -78,6 +172,20 @@ pub trait AbstractModule: Send {
 
     fn pass_message(&self, message: &str) -> Option<Box<dyn ModuleMessage>>;
 
+    fn pass_response(&self, message: &dyn ModuleMessage) -> Value;
+
+    fn snapshot(&self) -> Value;
+
+    fn waybar(&self) -> WaybarState;
+
+    fn osd_interactive(&self, id: OsdId) -> bool;
+
+    fn osd_timeout(&self, id: OsdId) -> Option<Duration>;
+
+    fn main_osd(&self) -> Option<OsdId>;
+
+    fn tooltip(&self) -> Option<String>;
+
     fn update(
         &mut self,
         message: Box<dyn ModuleMessage>,
@@ -108,6 +216,40 @@ impl<T: Module> AbstractModule for T {
         Module::pass_message(self, message).map(|msg| -> Box<dyn ModuleMessage> { Box::new(msg) })
     }
 
+    fn pass_response(&self, message: &dyn ModuleMessage) -> Value {
+        match message.downcast_ref::<<T as Module>::Message>() {
+            Ok(message) => Module::pass_response(self, message),
+            Err(e) => {
+                error!("dropping module message of invalid type: {e:#}");
+                Value::Null
+            }
+        }
+    }
+
+    fn snapshot(&self) -> Value {
+        Module::snapshot(self)
+    }
+
+    fn waybar(&self) -> WaybarState {
+        Module::waybar(self)
+    }
+
+    fn osd_interactive(&self, id: OsdId) -> bool {
+        Module::osd_interactive(self, id)
+    }
+
+    fn osd_timeout(&self, id: OsdId) -> Option<Duration> {
+        Module::osd_timeout(self, id)
+    }
+
+    fn main_osd(&self) -> Option<OsdId> {
+        Module::main_osd(self)
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        Module::tooltip(self)
+    }
+
     fn update(
         &mut self,
         message: Box<dyn ModuleMessage>,
@@ -117,9 +259,14 @@ impl<T: Module> AbstractModule for T {
             std::any::type_name_of_val(self).rsplit("::").next().unwrap_or_default()
         );
 
-        let Ok(heap) = message
-            .downcast::<<T as Module>::Message>()
-            .map_err(|e| panic!("received invalid type for module message: {e:#}"));
+        // messages are routed by type, so this only fails if there is a bug
+        let heap = match message.downcast::<<T as Module>::Message>() {
+            Ok(heap) => heap,
+            Err(e) => {
+                error!("dropping module message of invalid type: {e:#}");
+                return (Task::none(), None);
+            }
+        };
 
         let (task, osd) = Module::update(self, &heap);
 
@@ -142,7 +289,228 @@ impl<T: Module> AbstractModule for T {
     }
 }
 
+/// a message passed to a module over ipc, which consists of parts separated
+/// by `|`, being either an action (like `pause`) or a `key=value` pair
+#[derive(Debug, Default, PartialEq)]
+pub struct PassedMessage<'a> {
+    /// last part which is not a pair
+    pub action: Option<&'a str>,
+    /// values of the pairs by their key
+    pub values: HashMap<&'a str, &'a str>,
+}
+
+impl<'a> PassedMessage<'a> {
+    /// parses a passed message, ignoring whitespace around parts, keys and
+    /// values as well as empty parts
+    pub fn parse(message: &'a str) -> Self {
+        let mut parsed = Self::default();
+
+        for part in message.split('|').map(str::trim).filter(|part| !part.is_empty()) {
+            match part.split_once('=') {
+                Some((key, value)) => {
+                    parsed.values.insert(key.trim(), value.trim());
+                }
+                None => parsed.action = Some(part),
+            }
+        }
+
+        parsed
+    }
+
+    /// returns the value for the given key
+    pub fn get(&self, key: &str) -> Option<&'a str> {
+        self.values.get(key).copied()
+    }
+
+    /// returns the value for the given key as a number
+    pub fn number(&self, key: &str) -> Option<PassedNumber> {
+        self.get(key).and_then(PassedNumber::parse)
+    }
+}
+
+/// a number passed to a module, which is relative if it is explicitly signed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PassedNumber {
+    Absolute(f64),
+    Relative(f64),
+}
+
+impl PassedNumber {
+    /// parses a number like `50`, `+5` or `-10`
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let number = value.parse::<f64>().ok().filter(|number| number.is_finite())?;
+
+        if value.starts_with(['+', '-']) {
+            Some(Self::Relative(number))
+        } else {
+            Some(Self::Absolute(number))
+        }
+    }
+
+    /// applies the number to the current value
+    pub fn apply(self, current: f64) -> f64 {
+        match self {
+            Self::Absolute(value) => value,
+            Self::Relative(offset) => current + offset,
+        }
+    }
+}
+
 /// converts the module to a boxed type
 pub fn boxed<T: Module + 'static>(m: T) -> Box<dyn AbstractModule> {
     Box::new(m)
 }
+
+/// constructs a module, reading its config
+type Constructor =
+    Box<dyn Fn() -> BoxFuture<'static, Result<Box<dyn AbstractModule>>> + Send + Sync>;
+
+/// how a module is constructed and its config is checked
+struct ModuleEntry {
+    construct: Constructor,
+    /// deserializes the config of the module with the given identifier
+    check: fn(&Config, &str) -> Result<()>,
+}
+
+/// all modules which can be constructed by their identifier, every module
+/// adds itself in its `register` function
+#[derive(Default)]
+pub struct ModuleRegistry(HashMap<&'static str, ModuleEntry>);
+
+impl ModuleRegistry {
+    /// adds a module which is constructed asynchronously
+    pub fn add<T, F>(
+        &mut self,
+        identifier: &'static str,
+        construct: fn() -> F,
+        check: fn(&Config, &str) -> Result<()>,
+    ) where
+        T: Module + 'static,
+        F: Future<Output = Result<T>> + Send + 'static,
+    {
+        self.insert(identifier, Box::new(move || construct().map_ok(boxed).boxed()), check);
+    }
+
+    /// adds a module which is constructed synchronously
+    pub fn add_sync<T: Module + 'static>(
+        &mut self,
+        identifier: &'static str,
+        construct: fn() -> Result<T>,
+        check: fn(&Config, &str) -> Result<()>,
+    ) {
+        self.insert(
+            identifier,
+            Box::new(move || future::ready(construct().map(boxed)).boxed()),
+            check,
+        );
+    }
+
+    fn insert(
+        &mut self,
+        identifier: &'static str,
+        construct: Constructor,
+        check: fn(&Config, &str) -> Result<()>,
+    ) {
+        if self.0.insert(identifier, ModuleEntry { construct, check }).is_some() {
+            error!("module `{identifier}` is registered twice, using the last one");
+        }
+    }
+}
+
+/// checks the config of a module by deserializing it
+pub fn deserialize_config<T: DeserializeOwned + Default>(
+    config: &Config,
+    identifier: &str,
+) -> Result<()> {
+    config.module::<T>(identifier).map(drop)
+}
+
+/// all modules which exist in this version
+static REGISTRY: LazyLock<ModuleRegistry> = LazyLock::new(|| {
+    let mut registry = ModuleRegistry::default();
+
+    audio::register(&mut registry);
+    backlight::register(&mut registry);
+    cpufreq::register(&mut registry);
+    disk::register(&mut registry);
+    mako::register(&mut registry);
+    meter::register(&mut registry);
+    microphone::register(&mut registry);
+    mpris::register(&mut registry);
+    network::register(&mut registry);
+    power::register(&mut registry);
+    process::register(&mut registry);
+    recording::register(&mut registry);
+    submap::register(&mut registry);
+    thermal::register(&mut registry);
+    timer::register(&mut registry);
+    weather::register(&mut registry);
+    window::register(&mut registry);
+
+    registry
+});
+
+/// constructs the module with the given identifier, returns none if there is
+/// no module with this identifier
+pub async fn construct(identifier: &str) -> Option<Result<Box<dyn AbstractModule>>> {
+    Some((REGISTRY.0.get(identifier)?.construct)().await)
+}
+
+/// deserializes the config of the module with the given identifier, returns
+/// none if there is no module with this identifier
+pub fn check_config(identifier: &str, config: &Config) -> Option<Result<()>> {
+    Some((REGISTRY.0.get(identifier)?.check)(config, identifier))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PassedMessage, PassedNumber};
+
+    #[test]
+    fn parses_actions_and_pairs() {
+        let parsed = PassedMessage::parse("pause|id=0");
+
+        assert_eq!(parsed.action, Some("pause"));
+        assert_eq!(parsed.get("id"), Some("0"));
+        assert_eq!(parsed.get("duration"), None);
+    }
+
+    #[test]
+    fn ignores_whitespace_and_empty_parts() {
+        let parsed = PassedMessage::parse("  duration = 60 || message= Soup is ready  |");
+
+        assert_eq!(parsed.action, None);
+        assert_eq!(parsed.get("duration"), Some("60"));
+        assert_eq!(parsed.get("message"), Some("Soup is ready"));
+        assert_eq!(parsed.values.len(), 2);
+    }
+
+    #[test]
+    fn treats_parts_without_equals_as_action() {
+        assert_eq!(PassedMessage::parse("mute").action, Some("mute"));
+        assert_eq!(PassedMessage::parse("volume").get("volume"), None);
+
+        // the last action wins
+        assert_eq!(PassedMessage::parse("pause|resume").action, Some("resume"));
+    }
+
+    #[test]
+    fn parses_signed_numbers_as_relative() {
+        let parsed = PassedMessage::parse("volume=+5|brightness=-10|set=50|id=abc");
+
+        assert_eq!(parsed.number("volume"), Some(PassedNumber::Relative(5.0)));
+        assert_eq!(parsed.number("brightness"), Some(PassedNumber::Relative(-10.0)));
+        assert_eq!(parsed.number("set"), Some(PassedNumber::Absolute(50.0)));
+        assert_eq!(parsed.number("id"), None);
+        assert_eq!(PassedNumber::parse(" + 5"), None);
+        assert_eq!(PassedNumber::parse("nan"), None);
+    }
+
+    #[test]
+    fn applies_numbers() {
+        assert_eq!(PassedNumber::Relative(5.0).apply(40.0), 45.0);
+        assert_eq!(PassedNumber::Relative(-10.0).apply(40.0), 30.0);
+        assert_eq!(PassedNumber::Absolute(50.0).apply(40.0), 50.0);
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    hash::Hasher as _,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use iced::{
+    Element, Renderer, Subscription, Task, Theme,
+    advanced::subscription::{EventStream, Hasher, Recipe, from_recipe},
+    widget::mouse_area,
+};
+use iced_winit::futures::BoxStream;
+use liischte_lib::{
+    StreamContext,
+    mpris::{Mpris, PlaybackState, PlaybackStatus},
+};
+use log::debug;
+use lucide_icons::Icon;
+use serde_json::{Value, json};
+
+use crate::{
+    module::{Module, ModuleMessage, ModuleRegistry},
+    osd::OsdId,
+    ui::{icon, scroll_change},
+};
+
+pub const MPRIS_MODULE_IDENTIFIER: &str = "mpris";
+
+/// time after skipping a track in which scrolling is ignored, so that one
+/// swipe on a touchpad does not skip multiple tracks
+const SKIP_COOLDOWN: Duration = Duration::from_millis(500);
+
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    // the module has no config which could be checked
+    registry.add(MPRIS_MODULE_IDENTIFIER, MprisModule::new, |_, _| Ok(()));
+}
+
+impl ModuleMessage for MprisMessage {}
+#[derive(Clone, Debug)]
+pub enum MprisMessage {
+    Playback(Option<PlaybackState>),
+
+    PlayPause,
+    Next,
+    Previous,
+    Scroll(f32), // scrolled lines
+
+    Ok,
+}
+
+pub struct MprisModule {
+    mpris: Mpris,
+
+    playback: Option<PlaybackState>,
+
+    /// lines scrolled since the last skip
+    scrolled: f32,
+    /// time the last track was skipped by scrolling
+    skipped: Option<Instant>,
+}
+
+impl MprisModule {
+    pub async fn new() -> Result<Self> {
+        Ok(Self {
+            mpris: Mpris::connect().await.context("failed to connect to session bus")?,
+            playback: None,
+            scrolled: 0f32,
+            skipped: None,
+        })
+    }
+}
+
+impl Module for MprisModule {
+    type Message = MprisMessage;
+
+    fn subscribe(&self) -> Subscription<Self::Message> {
+        from_recipe(PlaybackMonitor(self.mpris.clone())).map(MprisMessage::Playback)
+    }
+
+    fn update(&mut self, message: &Self::Message) -> (Task<Self::Message>, Option<OsdId>) {
+        match message {
+            MprisMessage::Playback(playback) => self.playback = playback.clone(),
+            MprisMessage::Scroll(lines) => {
+                if self.skipped.is_some_and(|time| time.elapsed() < SKIP_COOLDOWN) {
+                    return (Task::none(), None);
+                }
+
+                // touchpads send many small events, so we wait for a whole line
+                self.scrolled += lines;
+                if self.scrolled.abs() < 1f32 {
+                    return (Task::none(), None);
+                }
+
+                let skip =
+                    if self.scrolled > 0f32 { MprisMessage::Previous } else { MprisMessage::Next };
+
+                self.scrolled = 0f32;
+                self.skipped = Some(Instant::now());
+
+                return self.update(&skip);
+            }
+            MprisMessage::PlayPause | MprisMessage::Next | MprisMessage::Previous => {
+                let Some(ref playback) = self.playback else { return (Task::none(), None) };
+
+                let mpris = self.mpris.clone();
+                let player = playback.player.clone();
+                let action = message.clone();
+
+                return (
+                    Task::future(async move {
+                        match action {
+                            MprisMessage::PlayPause => mpris.play_pause(&player).await,
+                            MprisMessage::Next => mpris.next(&player).await,
+                            _ => mpris.previous(&player).await,
+                        }
+                        .stream_log("failed to control mpris player");
+
+                        MprisMessage::Ok
+                    }),
+                    None,
+                );
+            }
+            MprisMessage::Ok => {}
+        }
+
+        (Task::none(), None)
+    }
+
+    fn snapshot(&self) -> Value {
+        json!({
+            "playback": self.playback.as_ref().map(|playback| json!({
+                "player": playback.player,
+                "title": playback.title,
+                "artist": playback.artist,
+                "status": format!("{:?}", playback.status),
+            })),
+        })
+    }
+
+    fn render_info(&self) -> Vec<Element<'_, Self::Message, Theme, Renderer>> {
+        let Some(ref playback) = self.playback else { return vec![] };
+
+        let symbol = match playback.status {
+            PlaybackStatus::Playing => Icon::Play,
+            PlaybackStatus::Paused => Icon::Pause,
+            PlaybackStatus::Stopped => Icon::Square,
+        };
+
+        vec![
+            mouse_area(icon(symbol))
+                .on_release(MprisMessage::PlayPause)
+                .on_scroll(|delta| MprisMessage::Scroll(scroll_change(delta, 1f32, 1f32)))
+                .into(),
+        ]
+    }
+}
+
+struct PlaybackMonitor(Mpris);
+
+impl Recipe for PlaybackMonitor {
+    type Output = Option<PlaybackState>;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str("mpris playback events");
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("starting mpris playback listener");
+
+        self.0.listen_playback()
+    }
+}
@@ -1,32 +1,119 @@
-use std::hash::Hasher as _;
+use std::{
+    hash::Hasher as _,
+    time::{Duration, Instant},
+};
 
 use anyhow::{Context, Result};
 use futures::{StreamExt, stream};
 use iced::{
-    Element, Renderer, Subscription, Task, Theme,
+    Background, Border, Element, Length, Renderer, Subscription, Task, Theme,
     advanced::subscription::{EventStream, Hasher, Recipe, from_recipe},
-    widget::stack,
+    alignment::{Horizontal, Vertical},
+    widget::{Column, column, container, mouse_area, row, stack, text},
 };
 use iced_winit::futures::BoxStream;
-use liischte_lib::networkmanager::{
-    ActiveConnection, ActiveConnectionKind, NetworkManager, OwnedObjectPath, describe_path,
+use liischte_lib::{
+    StreamContext,
+    modemmanager::CellularInfo,
+    networkmanager::{
+        ActiveConnection, ActiveConnectionKind, ActiveConnectionState, ApInfo, NetworkManager,
+        OwnedObjectPath, describe_path,
+    },
 };
-use log::{debug, trace};
+use log::{debug, info, trace, warn};
 use lucide_icons::Icon;
 use serde::Deserialize;
-
-use super::{Module, ModuleMessage};
-use crate::{config::CONFIG, osd::OsdId, ui::icon};
+use serde_json::{Value, json};
+use tokio::time::sleep;
+
+use super::{Module, ModuleMessage, ModuleRegistry, PassedMessage, deserialize_config};
+use crate::{
+    config::{CONFIG, RUNTIME_CONFIG, deserialize_duration_seconds},
+    osd::OsdId,
+    ui::icon,
+};
 
 pub const NETWORK_MODULE_IDENTIFIER: &str = "network";
 
-#[derive(Deserialize, Default)]
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    registry.add(
+        NETWORK_MODULE_IDENTIFIER,
+        NewtorkModule::new,
+        deserialize_config::<NetworkModuleConfig>,
+    );
+}
+
+#[derive(Deserialize)]
 #[serde(default)]
-struct NetworkModuleConfig {
-    /// enable modem manager support
+pub struct NetworkModuleConfig {
+    /// enable modem manager support (signal strength and access technology)
     modem: bool,
+    /// how the cellular signal strength is determined
+    signal: CellularSignal,
+    /// weight of a new wireless strength sample in the moving average, where
+    /// `1.0` disables smoothing
+    smoothing: f64,
+    /// strengths (from 0 to 1) between the wifi icons, ascending
+    wireless_thresholds: Vec<f64>,
+    /// strengths (from 0 to 1) between the cellular icons, ascending
+    cellular_thresholds: Vec<f64>,
+    /// minimum time between two wifi scans when opening the picker in seconds
+    #[serde(deserialize_with = "deserialize_duration_seconds")]
+    rescan_interval: Duration,
+    /// whether to show an osd when the connection changes
+    osd: bool,
+    /// time the connection osd is shown in milliseconds, none for the osd
+    /// timeout
+    osd_timeout: Option<u64>,
 }
 
+impl Default for NetworkModuleConfig {
+    fn default() -> Self {
+        Self {
+            modem: false,
+            signal: CellularSignal::Quality,
+            smoothing: 0.3,
+            wireless_thresholds: vec![0.25, 0.50, 0.75],
+            cellular_thresholds: vec![0.2, 0.4, 0.6, 0.8],
+            rescan_interval: Duration::from_secs(30),
+            osd: true,
+            osd_timeout: None,
+        }
+    }
+}
+
+#[derive(Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+enum CellularSignal {
+    /// signal quality percentage reported by the modem
+    Quality,
+    /// received signal power in dBm, mapped onto `CELLULAR_DBM_RANGE`
+    Dbm,
+}
+
+/// id of the osd to pick a wifi network
+const OSD_PICKER: OsdId = 0;
+/// id of the osd showing a changed connection
+const OSD_CONNECTION: OsdId = 1;
+
+/// time the connection has to stay the same before its change is shown
+const CONNECTION_DEBOUNCE: Duration = Duration::from_secs(1);
+
+/// kind and name of the primary connection, none if disconnected
+type Connection = Option<(ActiveConnectionKind, String)>;
+
+/// icons for the wireless strength, from no to full signal
+const WIRELESS_ICONS: [Icon; 4] = [Icon::WifiZero, Icon::WifiLow, Icon::WifiHigh, Icon::Wifi];
+/// icons for the cellular strength, from no to full signal
+const CELLULAR_ICONS: [Icon; 5] =
+    [Icon::SignalZero, Icon::SignalLow, Icon::SignalMedium, Icon::SignalHigh, Icon::Signal];
+/// margin by which a threshold has to be crossed to change the icon
+const WIRELESS_HYSTERESIS: f64 = 0.03;
+
+/// received signal power in dBm which is considered no and full signal
+const CELLULAR_DBM_RANGE: (f64, f64) = (-110.0, -70.0);
+
 impl ModuleMessage for NetworkMessage {}
 #[derive(Clone, Debug)]
 pub enum NetworkMessage {
@@ -35,6 +122,18 @@ pub enum NetworkMessage {
 
     WirelessStrength(f64),
     CellularStrength(f64),
+    CellularDbm(f64),
+    CellularInfo(CellularInfo),
+
+    AirplaneMode(bool),
+    SetAirplaneMode(bool),
+
+    ShowPicker,
+    AccessPoints(OwnedObjectPath, Vec<ApInfo>),
+    Connect(String),
+    Ok,
+
+    AnnounceConnection(u64),
 }
 
 pub struct NewtorkModule {
@@ -46,22 +145,54 @@ pub struct NewtorkModule {
     primary: Option<ActiveConnection>,
     primary_path: Option<OwnedObjectPath>, /* we need this if the primary is communicated before
                                             * the active */
+    observed: Connection,          // last settled connection
+    announced: Option<Connection>, // none before the first connection was known
+    pending: u64,                  // counts changes to debounce them
     wireless_strength: f64,
+    wireless_smoothed: f64,
+    wireless_level: Option<usize>, // none if no sample was received yet
     cellular_strength: f64,
+    cellular_dbm: Option<f64>, // only read if configured
+    cellular_info: CellularInfo,
+
+    airplane: bool,
+
+    wireless_device: Option<OwnedObjectPath>, // device the access points were found on
+    access_points: Vec<ApInfo>,
+    last_scan: Option<Instant>,
 }
 
 impl NewtorkModule {
     pub async fn new() -> Result<Self> {
+        let mut config: NetworkModuleConfig =
+            RUNTIME_CONFIG.get().module(NETWORK_MODULE_IDENTIFIER)?;
+
+        config.wireless_thresholds = validate_thresholds("wireless", config.wireless_thresholds);
+        config.cellular_thresholds = validate_thresholds("cellular", config.cellular_thresholds);
+
         Ok(Self {
-            config: CONFIG.module(NETWORK_MODULE_IDENTIFIER),
+            config,
             nm: NetworkManager::connnect().await.context("could not connect to system bus")?,
 
             active: vec![],
             primary: None,
             primary_path: None,
+            observed: None,
+            announced: None,
+            pending: 0,
 
             wireless_strength: 0f64,
+            wireless_smoothed: 0f64,
+            wireless_level: None,
             cellular_strength: 0f64,
+            cellular_dbm: None,
+            cellular_info: CellularInfo::default(),
+
+            airplane: false,
+
+            wireless_device: None,
+            access_points: vec![],
+            last_scan: None,
         })
     }
 }
@@ -73,9 +204,12 @@ impl Module for NewtorkModule {
         let mut subs = vec![
             from_recipe(PrimaryMonitor(self.nm.clone())).map(NetworkMessage::PrimaryConnection),
             from_recipe(ActiveMonitor(self.nm.clone())).map(NetworkMessage::ActiveConnections),
+            from_recipe(AirplaneMonitor(self.nm.clone())).map(NetworkMessage::AirplaneMode),
         ];
 
-        if let Some(ref primary) = self.primary
+        // there is no signal to monitor with the radios turned off
+        if !self.airplane
+            && let Some(ref primary) = self.primary
             && let Some(ref device) = primary.device
         {
             match (&primary.kind, self.config.modem) {
@@ -86,9 +220,16 @@ impl Module for NewtorkModule {
                     );
                 }
                 (ActiveConnectionKind::Cellular, true) => {
-                    subs.push(
+                    subs.push(if self.config.signal == CellularSignal::Dbm {
+                        from_recipe(CellularDbmMonitor(device.clone(), self.nm.clone()))
+                            .map(NetworkMessage::CellularDbm)
+                    } else {
                         from_recipe(CellularStrengthMonitor(device.clone(), self.nm.clone()))
-                            .map(NetworkMessage::CellularStrength),
+                            .map(NetworkMessage::CellularStrength)
+                    });
+                    subs.push(
+                        from_recipe(CellularInfoMonitor(device.clone(), self.nm.clone()))
+                            .map(NetworkMessage::CellularInfo),
                     );
                 }
                 _ => {}
@@ -98,9 +239,30 @@ impl Module for NewtorkModule {
         Subscription::batch(subs)
     }
 
+    fn pass_message(&self, message: &str) -> Option<Self::Message> {
+        let passed = PassedMessage::parse(message);
+
+        match (passed.action, passed.get("airplane")) {
+            (Some("airplane"), _) => Some(NetworkMessage::SetAirplaneMode(!self.airplane)),
+            (_, Some("on")) => Some(NetworkMessage::SetAirplaneMode(true)),
+            (_, Some("off")) => Some(NetworkMessage::SetAirplaneMode(false)),
+            (Some("picker"), _) => Some(NetworkMessage::ShowPicker),
+            _ => {
+                info!("passed unknown message {message} to network");
+                None
+            }
+        }
+    }
+
     fn update(&mut self, message: &Self::Message) -> (Task<Self::Message>, Option<OsdId>) {
         match message {
             NetworkMessage::PrimaryConnection(primary) => {
+                // the average of another connection is meaningless
+                if self.primary_path != *primary {
+                    self.wireless_level = None;
+                    self.cellular_info = CellularInfo::default();
+                }
+
                 self.primary_path = primary.clone();
 
                 if let Some(primary) = primary {
@@ -112,12 +274,137 @@ impl Module for NewtorkModule {
             NetworkMessage::ActiveConnections(active) => self.active = active.clone(),
             NetworkMessage::WirelessStrength(strength) => {
                 trace!("reported wireless strength: {strength}");
-                self.wireless_strength = *strength
+                self.wireless_strength = *strength;
+
+                let smoothing = self.config.smoothing.clamp(0f64, 1f64);
+                self.wireless_smoothed = match self.wireless_level {
+                    Some(_) => smoothing * strength + (1f64 - smoothing) * self.wireless_smoothed,
+                    None => *strength,
+                };
+
+                self.wireless_level = Some(wireless_level(
+                    self.wireless_smoothed,
+                    self.wireless_level,
+                    &self.config.wireless_thresholds,
+                ));
             }
             NetworkMessage::CellularStrength(strength) => {
                 trace!("reported cellular strength: {strength}");
                 self.cellular_strength = *strength
             }
+            NetworkMessage::CellularDbm(dbm) => {
+                trace!("reported cellular power: {dbm} dBm");
+                self.cellular_dbm = Some(*dbm);
+
+                let (low, high) = CELLULAR_DBM_RANGE;
+                self.cellular_strength = ((dbm - low) / (high - low)).clamp(0f64, 1f64);
+            }
+            NetworkMessage::CellularInfo(info) => {
+                trace!("reported cellular info: {info:?}");
+                self.cellular_info = info.clone()
+            }
+            NetworkMessage::AirplaneMode(airplane) => self.airplane = *airplane,
+            NetworkMessage::SetAirplaneMode(airplane) => {
+                let nm = self.nm.clone();
+                let airplane = *airplane;
+
+                return (
+                    Task::future(async move {
+                        nm.set_airplane_mode(airplane)
+                            .await
+                            .stream_log("failed to toggle airplane mode");
+
+                        NetworkMessage::Ok
+                    }),
+                    None,
+                );
+            }
+            NetworkMessage::ShowPicker => {
+                let nm = self.nm.clone();
+
+                // we don't want to scan every time the picker is opened
+                let rescan =
+                    self.last_scan.is_none_or(|last| last.elapsed() >= self.config.rescan_interval);
+                if rescan {
+                    self.last_scan = Some(Instant::now());
+                }
+
+                return (
+                    Task::future(async move {
+                        let result = async {
+                            let device = nm
+                                .wireless_devices()
+                                .await?
+                                .into_iter()
+                                .next()
+                                .context("there is no wireless device")?;
+
+                            let aps = if rescan {
+                                nm.scan_access_points(&device).await?
+                            } else {
+                                nm.access_points(&device).await?
+                            };
+
+                            anyhow::Ok((device, aps))
+                        };
+
+                        result
+                            .await
+                            .stream_log("failed to list wireless networks")
+                            .map(|(device, aps)| NetworkMessage::AccessPoints(device, aps))
+                            .unwrap_or(NetworkMessage::Ok)
+                    }),
+                    None,
+                );
+            }
+            NetworkMessage::AccessPoints(device, aps) => {
+                self.wireless_device = Some(device.clone());
+
+                // only show the strongest access point of every network
+                let mut aps = aps.iter().filter(|ap| !ap.ssid.is_empty()).collect::<Vec<_>>();
+                aps.sort_by(|a, b| b.strength.total_cmp(&a.strength));
+
+                self.access_points.clear();
+                for ap in aps {
+                    if !self.access_points.iter().any(|other| other.ssid == ap.ssid) {
+                        self.access_points.push(ap.clone());
+                    }
+                }
+
+                return (Task::none(), Some(OSD_PICKER));
+            }
+            NetworkMessage::Connect(ssid) => {
+                let Some(device) = self.wireless_device.clone() else {
+                    return (Task::none(), None);
+                };
+
+                let nm = self.nm.clone();
+                let ssid = ssid.clone();
+
+                return (
+                    Task::future(async move {
+                        nm.activate_connection(&device, &ssid)
+                            .await
+                            .stream_log("failed to connect to wireless network");
+
+                        NetworkMessage::Ok
+                    }),
+                    None,
+                );
+            }
+            NetworkMessage::Ok => {}
+            NetworkMessage::AnnounceConnection(pending) => {
+                if *pending != self.pending || self.announced.as_ref() == Some(&self.observed) {
+                    return (Task::none(), None);
+                }
+
+                // the connection found on startup is not a change
+                let startup = self.announced.is_none();
+                self.announced = Some(self.observed.clone());
+
+                let osd = !startup && self.config.osd;
+                return (Task::none(), osd.then_some(OSD_CONNECTION));
+            }
         };
 
         // if we first receive the primary before the active connection
@@ -127,53 +414,265 @@ impl Module for NewtorkModule {
             self.primary = self.active.iter().find(|con| con.path == *primary).cloned();
         }
 
+        // changes are only announced once the connection stayed the same for a while
+        if let Some(connection) = self.settled_connection()
+            && (connection != self.observed || self.pending == 0)
+        {
+            self.observed = connection;
+            self.pending += 1;
+
+            let pending = self.pending;
+            return (
+                Task::future(async move {
+                    sleep(CONNECTION_DEBOUNCE).await;
+                    NetworkMessage::AnnounceConnection(pending)
+                }),
+                None,
+            );
+        }
+
         (Task::none(), None)
     }
 
+    fn snapshot(&self) -> Value {
+        json!({
+            "primary": self.primary.as_ref().map(|primary| json!({
+                "name": primary.name,
+                "kind": format!("{:?}", primary.kind),
+            })),
+            "wireless_strength": self.wireless_strength,
+            "wireless_smoothed": self.wireless_smoothed,
+            "cellular_strength": self.cellular_strength,
+            "cellular_dbm": self.cellular_dbm,
+            "cellular_operator": self.cellular_info.operator,
+            "cellular_technology": self.cellular_info.technology,
+            "airplane": self.airplane,
+        })
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        if self.airplane {
+            return Some("Airplane mode".to_string());
+        }
+
+        let Some(ref primary) = self.primary else { return Some("Disconnected".to_string()) };
+
+        Some(match primary.kind {
+            ActiveConnectionKind::Wireless => {
+                format!("{} ({:.0}%)", primary.name, self.wireless_strength * 100f64)
+            }
+            ActiveConnectionKind::Cellular => {
+                let name = if self.cellular_info.operator.is_empty() {
+                    &primary.name
+                } else {
+                    &self.cellular_info.operator
+                };
+
+                match self.cellular_info.technology {
+                    Some(technology) => {
+                        format!("{name} ({technology}, {:.0}%)", self.cellular_strength * 100f64)
+                    }
+                    None => format!("{name} ({:.0}%)", self.cellular_strength * 100f64),
+                }
+            }
+            _ => primary.name.clone(),
+        })
+    }
+
     fn has_status(&self) -> bool {
         true
     }
 
     fn render_status(&self) -> Element<'_, Self::Message, Theme, Renderer> {
-        let Some(ref primary) = self.primary else { return icon(Icon::Ban).into() };
+        if self.airplane {
+            return icon(Icon::Plane).into();
+        }
+
+        let Some(ref primary) = self.primary else {
+            return icon(Icon::Ban).color(CONFIG.colors.warning).into();
+        };
 
         let (symbol, background) = match primary.kind {
             ActiveConnectionKind::Wired => (Icon::ChevronsLeftRightEllipsis, None),
             ActiveConnectionKind::Wireless => (
-                match () {
-                    _ if self.wireless_strength > 0.75 => Icon::Wifi,
-                    _ if self.wireless_strength > 0.50 => Icon::WifiHigh,
-                    _ if self.wireless_strength > 0.25 => Icon::WifiLow,
-                    _ => Icon::WifiZero,
-                },
+                level_icon(
+                    &WIRELESS_ICONS,
+                    self.wireless_level.unwrap_or_default(),
+                    self.config.wireless_thresholds.len(),
+                ),
                 Some(Icon::Wifi),
             ),
             ActiveConnectionKind::Cellular => (
-                match () {
-                    _ if self.cellular_strength > 0.8 => Icon::Signal,
-                    _ if self.cellular_strength > 0.6 => Icon::SignalHigh,
-                    _ if self.cellular_strength > 0.4 => Icon::SignalMedium,
-                    _ if self.cellular_strength > 0.2 => Icon::SignalLow,
-                    _ => Icon::SignalZero,
-                },
+                level_icon(
+                    &CELLULAR_ICONS,
+                    self.config
+                        .cellular_thresholds
+                        .iter()
+                        .filter(|threshold| self.cellular_strength > **threshold)
+                        .count(),
+                    self.config.cellular_thresholds.len(),
+                ),
                 Some(Icon::Signal),
             ),
             _ => (Icon::Waypoints, None),
         };
 
-        if CONFIG.looks.tone_opacity != 0.0
-            && let Some(background) = background
-        {
-            stack![
+        let status: Element<'_, Self::Message, Theme, Renderer> = match background {
+            Some(background) if CONFIG.looks.tone_opacity != 0.0 => stack![
                 icon(background)
                     .color(CONFIG.looks.foreground.scale_alpha(CONFIG.looks.tone_opacity)),
                 icon(symbol)
             ]
-            .into()
+            .into(),
+            _ => icon(symbol).into(),
+        };
+
+        // cellular connections show the technology in use below the signal
+        if primary.kind == ActiveConnectionKind::Cellular
+            && let Some(technology) = self.cellular_info.technology
+        {
+            column![status, text(technology).size(10)].align_x(Horizontal::Center).into()
         } else {
-            icon(symbol).into()
+            status
         }
     }
+
+    fn osd_interactive(&self, id: OsdId) -> bool {
+        id == OSD_PICKER
+    }
+
+    fn main_osd(&self) -> Option<OsdId> {
+        Some(OSD_CONNECTION)
+    }
+
+    fn osd_timeout(&self, id: OsdId) -> Option<Duration> {
+        self.config.osd_timeout.filter(|_| id == OSD_CONNECTION).map(Duration::from_millis)
+    }
+
+    fn render_osd(&self, id: OsdId) -> Element<'_, Self::Message, Theme, Renderer> {
+        if id == OSD_PICKER { self.render_picker() } else { self.render_connection() }
+    }
+}
+
+/// determines the level of the wireless icon for a strength, only changing the
+/// previous level if a threshold is crossed by more than the hysteresis
+fn wireless_level(strength: f64, previous: Option<usize>, thresholds: &[f64]) -> usize {
+    let count =
+        |offset: f64| thresholds.iter().filter(|threshold| strength > *threshold + offset).count();
+
+    let (lower, upper) = (count(WIRELESS_HYSTERESIS), count(-WIRELESS_HYSTERESIS));
+
+    match previous {
+        Some(previous) => previous.clamp(lower, upper),
+        None => count(0f64),
+    }
+}
+
+/// picks the icon for a level out of the given number of levels, spreading
+/// the levels evenly over the available icons
+fn level_icon(icons: &[Icon], level: usize, levels: usize) -> Icon {
+    if levels == 0 {
+        return icons[icons.len() - 1];
+    }
+
+    let index = level.min(levels) as f64 / levels as f64 * (icons.len() - 1) as f64;
+    icons[index.round() as usize]
+}
+
+/// makes sure the thresholds between signal levels are within 0..1 and sorted
+fn validate_thresholds(kind: &str, mut thresholds: Vec<f64>) -> Vec<f64> {
+    if thresholds.iter().any(|threshold| !(0f64..=1f64).contains(threshold)) {
+        warn!("{kind} thresholds must be between 0 and 1, clamping them");
+        thresholds.iter_mut().for_each(|threshold| *threshold = threshold.clamp(0f64, 1f64));
+    }
+
+    if !thresholds.is_sorted() {
+        warn!("{kind} thresholds must be ascending, sorting them");
+        thresholds.sort_by(f64::total_cmp);
+    }
+
+    thresholds
+}
+
+impl NewtorkModule {
+    /// returns the primary connection, none while it is still being activated
+    fn settled_connection(&self) -> Option<Connection> {
+        let Some(ref path) = self.primary_path else {
+            return Some(None);
+        };
+
+        let connection = self.active.iter().find(|con| con.path == *path)?;
+
+        (connection.state == ActiveConnectionState::Activated)
+            .then(|| Some((connection.kind.clone(), connection.name.clone())))
+    }
+
+    /// renders the last announced connection with its name
+    fn render_connection(&self) -> Element<'_, NetworkMessage, Theme, Renderer> {
+        let (symbol, name) = match self.announced {
+            Some(Some((ref kind, ref name))) => (
+                match kind {
+                    ActiveConnectionKind::Wired => Icon::ChevronsLeftRightEllipsis,
+                    ActiveConnectionKind::Wireless => Icon::Wifi,
+                    ActiveConnectionKind::Cellular => Icon::Signal,
+                    _ => Icon::Waypoints,
+                },
+                name.as_str(),
+            ),
+            _ => (Icon::Ban, "Disconnected"),
+        };
+
+        column![icon(symbol), text(name).size(12).align_x(Horizontal::Center)]
+            .spacing(4)
+            .align_x(Horizontal::Center)
+            .into()
+    }
+
+    /// renders a list of all found wifi networks which can be clicked to
+    /// connect to them
+    fn render_picker(&self) -> Element<'_, NetworkMessage, Theme, Renderer> {
+        let current = self
+            .primary
+            .as_ref()
+            .filter(|primary| primary.kind == ActiveConnectionKind::Wireless)
+            .map(|primary| primary.name.as_str());
+
+        Column::from_iter(self.access_points.iter().map(|ap| {
+            let selected = current == Some(ap.ssid.as_str());
+
+            let thresholds = &self.config.wireless_thresholds;
+            let strength = level_icon(
+                &WIRELESS_ICONS,
+                wireless_level(ap.strength, None, thresholds),
+                thresholds.len(),
+            );
+
+            let mut content =
+                row![icon(strength).size(14), text(&ap.ssid).size(12).width(Length::Fill)]
+                    .spacing(6)
+                    .align_y(Vertical::Center);
+
+            if ap.secured {
+                content = content.push(icon(Icon::Lock).size(12));
+            }
+
+            mouse_area(
+                container(content)
+                    .style(move |_| container::Style {
+                        background: selected.then_some(Background::Color(CONFIG.looks.semi)),
+                        border: Border::default().rounded(6),
+                        ..Default::default()
+                    })
+                    .padding(6)
+                    .width(Length::Fill),
+            )
+            .on_release(NetworkMessage::Connect(ap.ssid.clone()))
+            .into()
+        }))
+        .padding(8)
+        .spacing(4)
+        .into()
+    }
 }
 
 struct PrimaryMonitor(NetworkManager);
@@ -188,7 +687,7 @@ impl Recipe for PrimaryMonitor {
     fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
         debug!("staring primary connection listener");
 
-        stream::once(async move { self.0.listen_primary_connection().await }).flatten().boxed()
+        self.0.listen_primary_connection()
     }
 }
 
@@ -208,6 +707,22 @@ impl Recipe for ActiveMonitor {
     }
 }
 
+struct AirplaneMonitor(NetworkManager);
+
+impl Recipe for AirplaneMonitor {
+    type Output = bool;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str("network airplane mode events");
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("staring airplane mode listener");
+
+        stream::once(async move { self.0.listen_airplane_mode().await }).flatten().boxed()
+    }
+}
+
 struct WirelessStrengthMonitor(OwnedObjectPath, NetworkManager);
 
 impl Recipe for WirelessStrengthMonitor {
@@ -241,3 +756,37 @@ impl Recipe for CellularStrengthMonitor {
         self.1.listen_cellular_strength(self.0)
     }
 }
+
+struct CellularInfoMonitor(OwnedObjectPath, NetworkManager);
+
+impl Recipe for CellularInfoMonitor {
+    type Output = CellularInfo;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str("network cellular info events");
+        state.write_str(self.0.as_str());
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("staring cellular info monitor for {}", describe_path(&self.0));
+
+        self.1.listen_cellular_info(self.0)
+    }
+}
+
+struct CellularDbmMonitor(OwnedObjectPath, NetworkManager);
+
+impl Recipe for CellularDbmMonitor {
+    type Output = f64;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str("network cellular dbm events");
+        state.write_str(self.0.as_str());
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("staring cellular dbm monitor for {}", describe_path(&self.0));
+
+        self.1.listen_cellular_dbm(self.0)
+    }
+}
@@ -4,48 +4,83 @@ use anyhow::{Context, Result};
 use async_trait::async_trait;
 use futures::{StreamExt, stream};
 use iced::{
-    Background, Element, Length, Limits, Rectangle, Renderer, Size, Subscription, Task, Theme,
+    Element, Rectangle, Renderer, Subscription, Task, Theme,
     advanced::subscription::{EventStream, Recipe, from_recipe},
-    core::{
-        Layout, Widget,
-        layout::{self, Node},
-        mouse, renderer,
-        widget::Tree,
-    },
     widget::stack,
 };
 use iced_winit::futures::BoxStream;
 use liischte_lib::sysfs::power::{
-    BatteryPowerDevice, MainsPowerDevice, PowerDevice, PowerDeviceKind,
+    BatteryPowerDevice, BatteryStatus, MainsPowerDevice, PowerDevice, PowerDeviceKind,
 };
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use lucide_icons::Icon;
 use serde::Deserialize;
+use serde_json::{Value, json};
+use tokio::process::Command;
 
-use crate::{config::CONFIG, osd::OsdId, ui::icon};
+use crate::{
+    config::{CONFIG, RUNTIME_CONFIG},
+    osd::OsdId,
+    ui::{fill::icon_fill, icon},
+};
 
-use super::{Module, ModuleMessage};
+use super::{Module, ModuleMessage, ModuleRegistry, WaybarState, deserialize_config};
 
 pub const POWER_MODULE_IDENTIFIER: &str = "power";
 
+/// position and size of the charge inside the battery icon on the 24px icon grid
+const BATTERY_INSET: Rectangle = Rectangle { x: 5.0, y: 10.4, width: 10.0, height: 6.0 };
+/// the official icons have slight vertical aliasing, so we try to replicate that
+const BATTERY_ALIASING: f32 = 0.2;
+
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    registry.add(
+        POWER_MODULE_IDENTIFIER,
+        PowerModule::new,
+        deserialize_config::<PowerModuleConfig>,
+    );
+}
+
 #[derive(Deserialize)]
 #[serde(default)]
-struct PowerModuleConfig {
+pub struct PowerModuleConfig {
     /// force the use of a specific mains supply
     mains: Option<String>,
     /// force the use of a specific set of batteries
     batteries: Vec<String>,
 
-    /// polling rate to poll battery status in seconds
+    /// polling rate to poll battery status in seconds, in addition to reacting
+    /// to udev events
     polling_rate: u64,
 
     /// battery percentage below which it is considered critical
     critical: f64,
+    /// show the charge relative to the design capacity, so worn batteries
+    /// never show as full
+    normalize_to_design: bool,
+
+    /// shell command to run once when discharging below the action threshold
+    action: Option<String>,
+    /// battery percentage below which the action is run
+    action_threshold: f64,
+
+    /// show each battery as a separate info instead of a combined status
+    separate: bool,
 }
 
 impl Default for PowerModuleConfig {
     fn default() -> Self {
-        Self { mains: None, batteries: vec![], polling_rate: 30, critical: 0.1 }
+        Self {
+            mains: None,
+            batteries: vec![],
+            polling_rate: 60,
+            critical: 0.1,
+            normalize_to_design: false,
+            action: None,
+            action_threshold: 0.05,
+            separate: false,
+        }
     }
 }
 
@@ -53,7 +88,9 @@ impl ModuleMessage for PowerStatusMessage {}
 #[derive(Clone, Debug)]
 pub enum PowerStatusMessage {
     MainsOnlineMessage(bool),
-    BatteryChargeMessage(usize, f64),
+    BatteryChargeMessage(usize, f64, BatteryStatus),
+
+    Ok,
 }
 
 struct Mains {
@@ -65,6 +102,12 @@ struct Battery {
     device: BatteryPowerDevice,
     capacity: f64,
     charge: f64,
+    status: BatteryStatus,
+
+    /// fraction of the design capacity which is left, if known
+    health: Option<f64>,
+    /// amount of charge cycles, if known
+    cycles: Option<u64>,
 }
 
 pub struct PowerModule {
@@ -72,11 +115,14 @@ pub struct PowerModule {
 
     mains: Option<Mains>,
     batteries: Vec<Battery>,
+
+    /// whether the action was already run while discharging
+    action_run: bool,
 }
 
 impl PowerModule {
     pub async fn new() -> Result<Self> {
-        let config: PowerModuleConfig = CONFIG.module(POWER_MODULE_IDENTIFIER);
+        let config: PowerModuleConfig = RUNTIME_CONFIG.get().module(POWER_MODULE_IDENTIFIER)?;
 
         info!("reading available power devices from sysfs");
         let mut mains = None;
@@ -104,7 +150,14 @@ impl PowerModule {
                     {
                         batteries.push(Battery {
                             capacity: device.read_capacity().await?,
-                            charge: device.read_charge().await?,
+                            charge: if config.normalize_to_design {
+                                device.read_design_charge().await?
+                            } else {
+                                device.read_charge().await?
+                            },
+                            status: device.read_status().await.unwrap_or(BatteryStatus::Unknown),
+                            health: device.read_health().await,
+                            cycles: device.read_cycle_count().await,
                             device,
                         });
                     }
@@ -123,7 +176,58 @@ impl PowerModule {
                 .join(", ")
         );
 
-        Ok(Self { mains, batteries, config })
+        Ok(Self { mains, batteries, config, action_run: false })
+    }
+
+    /// calculates the total charge over all batteries
+    fn charge(&self) -> f64 {
+        combined_charge(self.batteries.iter().map(|bat| (bat.capacity, bat.charge)))
+    }
+
+    /// determines the combined status over all batteries, where being on
+    /// mains only counts as charging if there are no batteries
+    fn status(&self) -> BatteryStatus {
+        if self.batteries.is_empty() {
+            return if self.mains.as_ref().is_some_and(|ac| ac.online) {
+                BatteryStatus::Charging
+            } else {
+                BatteryStatus::Unknown
+            };
+        }
+
+        if self.batteries.iter().any(|bat| bat.status == BatteryStatus::Charging) {
+            BatteryStatus::Charging
+        } else if self.batteries.iter().all(|bat| bat.status == BatteryStatus::Full) {
+            BatteryStatus::Full
+        } else {
+            BatteryStatus::Discharging
+        }
+    }
+
+    /// renders the icon for a battery with the given charge and status
+    fn render_battery(
+        &self,
+        charge: f64,
+        status: BatteryStatus,
+    ) -> Element<'_, PowerStatusMessage, Theme, Renderer> {
+        match status {
+            BatteryStatus::Charging => icon(Icon::BatteryCharging).into(),
+            BatteryStatus::Full => icon(Icon::BatteryFull).into(),
+            _ if charge < self.config.critical => {
+                icon(Icon::BatteryWarning).color(CONFIG.colors.critical).into()
+            }
+            _ => stack![
+                icon(Icon::Battery),
+                icon_fill(
+                    charge as f32,
+                    BATTERY_INSET,
+                    CONFIG.looks.icon_size,
+                    CONFIG.looks.foreground
+                )
+                .aliasing(BATTERY_ALIASING)
+            ]
+            .into(),
+        }
     }
 }
 
@@ -137,9 +241,10 @@ impl Module for PowerModule {
                 from_recipe(ChargeMonitor(
                     bat.device.clone(),
                     Duration::from_secs(self.config.polling_rate),
+                    self.config.normalize_to_design,
                 ))
                 .with(i)
-                .map(|(i, c)| PowerStatusMessage::BatteryChargeMessage(i, c))
+                .map(|(i, (c, s))| PowerStatusMessage::BatteryChargeMessage(i, c, s))
             })),
             self.mains
                 .as_ref()
@@ -158,89 +263,117 @@ impl Module for PowerModule {
                     ac.online = *online;
                 }
             }
-            PowerStatusMessage::BatteryChargeMessage(i, charge) => {
+            PowerStatusMessage::BatteryChargeMessage(i, charge, status) => {
                 if let Some(bat) = self.batteries.get_mut(*i) {
-                    bat.charge = *charge
+                    bat.charge = *charge;
+                    bat.status = *status;
                 }
             }
+            PowerStatusMessage::Ok => {}
+        }
+
+        // the action is only run once until the batteries stop discharging
+        if self.status() != BatteryStatus::Discharging {
+            self.action_run = false;
+        } else if !self.action_run
+            && let Some(action) = &self.config.action
+            && self.charge() < self.config.action_threshold
+        {
+            self.action_run = true;
+
+            warn!("battery is at {:.0}%, running action `{action}`", self.charge() * 100f64);
+            let action = action.clone();
+
+            return (
+                Task::future(async move {
+                    match Command::new("sh").arg("-c").arg(&action).status().await {
+                        Ok(status) if status.success() => info!("battery action `{action}` ran"),
+                        Ok(status) => error!("battery action `{action}` failed with {status}"),
+                        Err(e) => error!("failed to run battery action `{action}`: {e:#}"),
+                    }
+
+                    PowerStatusMessage::Ok
+                }),
+                None,
+            );
         }
 
         (Task::none(), None)
     }
 
-    fn has_status(&self) -> bool {
-        true
+    fn snapshot(&self) -> Value {
+        json!({
+            "online": self.mains.as_ref().map(|ac| ac.online),
+            "charge": self.charge(),
+            "batteries": self.batteries.iter().map(|bat| json!({
+                "name": bat.device.0.device.name,
+                "capacity": bat.capacity,
+                "charge": bat.charge,
+                "status": format!("{:?}", bat.status),
+                "health": bat.health,
+                "cycles": bat.cycles,
+            })).collect::<Vec<_>>(),
+        })
     }
 
-    fn render_status(&self) -> Element<'_, Self::Message, Theme, Renderer> {
-        if self.mains.as_ref().map(|ac| ac.online).unwrap_or_default() {
-            icon(Icon::BatteryCharging).into()
-        } else {
-            let total = self.batteries.iter().map(|bat| bat.capacity).sum::<f64>();
-            let charge =
-                self.batteries.iter().map(|bat| (bat.capacity / total) * bat.charge).sum::<f64>();
+    fn tooltip(&self) -> Option<String> {
+        if self.batteries.is_empty() {
+            return Some("On mains power".to_string());
+        }
 
-            if charge < self.config.critical {
-                icon(Icon::BatteryWarning).into()
-            } else {
-                stack![icon(Icon::Battery), BatteryBar(charge as f32)].into()
+        Some(match self.status() {
+            BatteryStatus::Charging => {
+                format!("Battery at {:.0}%, charging", self.charge() * 100f64)
             }
-        }
+            BatteryStatus::Full => "Battery full".to_string(),
+            _ => format!("Battery at {:.0}%", self.charge() * 100f64),
+        })
     }
-}
 
-struct BatteryBar(f32);
+    fn waybar(&self) -> WaybarState {
+        let state = WaybarState::new(self.tooltip());
+        if self.batteries.is_empty() {
+            return state.class("mains");
+        }
+
+        let charge = self.charge();
+        let state = state.fraction(charge).class(&format!("{:?}", self.status()).to_lowercase());
 
-impl<Message, Theme, Renderer> Widget<Message, Theme, Renderer> for BatteryBar
-where
-    Message: Clone,
-    Renderer: iced::core::Renderer,
-{
-    fn size(&self) -> Size<Length> {
-        Size { width: Length::Fill, height: Length::Fill }
+        if charge < self.config.critical { state.class("critical") } else { state }
     }
 
-    fn layout(&self, _tree: &mut Tree, _renderer: &Renderer, limits: &Limits) -> Node {
-        layout::atomic(limits, Length::Fill, Length::Fill)
+    fn has_status(&self) -> bool {
+        // without batteries, there is nothing to show separately
+        !self.config.separate || self.batteries.is_empty()
     }
 
-    fn draw(
-        &self,
-        _tree: &Tree,
-        renderer: &mut Renderer,
-        _theme: &Theme,
-        _style: &renderer::Style,
-        layout: Layout<'_>,
-        _cursor: mouse::Cursor,
-        _viewport: &Rectangle,
-    ) {
-        let bounds = layout.bounds();
-
-        // the official icons have slight vertical aliasing, so we try to replicate that
-        const ALIASING: f32 = 0.2;
-
-        renderer.fill_quad(
-            renderer::Quad {
-                bounds: Rectangle {
-                    x: (bounds.x + 5.0).floor(),
-                    y: (bounds.y + 14.0).floor() + ALIASING,
-                    width: 10.0 * self.0,
-                    height: 6.0 - ALIASING * 2.0,
-                },
-                ..renderer::Quad::default()
-            },
-            Background::Color(CONFIG.looks.foreground),
-        );
+    fn render_status(&self) -> Element<'_, Self::Message, Theme, Renderer> {
+        self.render_battery(self.charge(), self.status())
+    }
+
+    fn render_info(&self) -> Vec<Element<'_, Self::Message, Theme, Renderer>> {
+        if !self.config.separate {
+            return vec![];
+        }
+
+        self.batteries.iter().map(|bat| self.render_battery(bat.charge, bat.status)).collect()
     }
 }
 
-impl<'a, Message, Theme, Renderer> From<BatteryBar> for Element<'a, Message, Theme, Renderer>
-where
-    Message: Clone + 'a,
-    Renderer: iced::core::Renderer + 'a,
-{
-    fn from(bar: BatteryBar) -> Element<'a, Message, Theme, Renderer> {
-        Element::new(bar)
+/// combines the charges of batteries given with their capacity, weighted by
+/// the capacity. if no capacity could be read, all batteries count the same
+fn combined_charge(batteries: impl Iterator<Item = (f64, f64)> + Clone) -> f64 {
+    let count = batteries.clone().count();
+    if count == 0 {
+        return 0f64;
+    }
+
+    let total = batteries.clone().map(|(capacity, _)| capacity).sum::<f64>();
+
+    if total > 0f64 {
+        batteries.map(|(capacity, charge)| capacity / total * charge).sum()
+    } else {
+        batteries.map(|(_, charge)| charge).sum::<f64>() / count as f64
     }
 }
 
@@ -266,17 +399,57 @@ impl Recipe for OnlineMonitor {
     }
 }
 
-struct ChargeMonitor(BatteryPowerDevice, Duration);
+struct ChargeMonitor(BatteryPowerDevice, Duration, bool);
 
 impl Recipe for ChargeMonitor {
-    type Output = f64;
+    type Output = (f64, BatteryStatus);
 
     fn hash(&self, state: &mut iced::advanced::subscription::Hasher) {
         state.write_str(&format!("battery charge events for {}", self.0.0.device.name));
+        state.write_u8(self.2 as u8);
     }
 
     fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
         debug!("starting battery charge listener for {}", self.0.0.device.name);
-        self.0.listen_charge(self.1)
+
+        match self.0.clone().listen_charge_events(self.1, self.2) {
+            Ok(s) => s,
+            Err(e) => {
+                error!("failed to listen to battery events, only polling instead: {e:#}");
+                self.0.listen_charge(self.1, self.2)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::combined_charge;
+
+    fn combine(batteries: &[(f64, f64)]) -> f64 {
+        combined_charge(batteries.iter().copied())
+    }
+
+    #[test]
+    fn one_battery_keeps_its_charge() {
+        assert!((combine(&[(50.0, 0.42)]) - 0.42).abs() < 1e-9);
+    }
+
+    #[test]
+    fn two_batteries_are_weighted_by_capacity() {
+        // 30 of 40 Wh and 6 of 20 Wh are left, so 36 of 60 Wh
+        assert!((combine(&[(40.0, 0.75), (20.0, 0.3)]) - 0.6).abs() < 1e-9);
+        assert!((combine(&[(20.0, 0.5), (20.0, 1.0)]) - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn zero_capacity_averages_charges() {
+        assert!((combine(&[(0.0, 0.2), (0.0, 0.6)]) - 0.4).abs() < 1e-9);
+        assert!((combine(&[(0.0, 0.8)]) - 0.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn no_batteries_have_no_charge() {
+        assert_eq!(combine(&[]), 0.0);
     }
 }
@@ -1,31 +1,49 @@
-use std::{hash::Hasher, time::Duration};
+use std::{
+    hash::Hasher,
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
 use iced::{
     Element, Renderer, Subscription, Task, Theme,
     advanced::subscription::{EventStream, Recipe, from_recipe},
-    widget::mouse_area,
+    alignment::Horizontal,
+    widget::{column, mouse_area, text},
 };
 use iced_winit::futures::BoxStream;
 use liischte_lib::process::{
     ProcessInfo, ProcessSignal, listen_running_processes, read_running_processes, send_signal,
 };
-use log::{debug, error};
+use log::{debug, error, info};
 use lucide_icons::Icon;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
+use serde_json::{Value, json};
+use tokio::time::sleep;
 
 use crate::{
-    config::{CONFIG, deserialize_duration_seconds, deserialize_icon},
-    module::{Module, ModuleMessage},
+    config::{RUNTIME_CONFIG, deserialize_duration_seconds, deserialize_icon},
+    module::{Module, ModuleMessage, ModuleRegistry, PassedMessage, deserialize_config},
     osd::OsdId,
     ui::icon,
 };
 
 pub const PROCESS_MODULE_IDENTIFIER: &str = "process";
 
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    registry.add_sync(
+        PROCESS_MODULE_IDENTIFIER,
+        ProcessModule::new,
+        deserialize_config::<ProcessModuleConfig>,
+    );
+}
+
+/// time in which a click has to be confirmed by a second one
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(3);
+
 #[derive(Deserialize)]
 #[serde(default)]
-struct ProcessModuleConfig {
+pub struct ProcessModuleConfig {
     /// polling rate to poll processes in seconds
     #[serde(deserialize_with = "deserialize_duration_seconds")]
     polling_rate: Duration,
@@ -37,11 +55,58 @@ struct ProcessModuleConfig {
 #[derive(Deserialize)]
 struct ProcessModuleConfigItem {
     /// start of cmdline of the process
-    cmdline: String,
+    cmdline: Option<String>,
+    /// start of the name (`comm`) of the process
+    name: Option<String>,
+    /// whether the patterns may occur anywhere instead of just at the start
+    #[serde(default)]
+    substring: bool,
 
     /// icon to show in that case
     #[serde(deserialize_with = "deserialize_icon")]
     icon: Icon,
+
+    /// signal sent to the process when clicked
+    #[serde(default = "default_signal", deserialize_with = "deserialize_signal")]
+    signal: ProcessSignal,
+    /// whether a second click is required to send the signal
+    #[serde(default)]
+    confirm: bool,
+
+    /// whether to show the number of matching processes
+    #[serde(default)]
+    count: bool,
+}
+
+impl ProcessModuleConfigItem {
+    /// checks whether a process matches all patterns of this indicator
+    fn matches(&self, process: &ProcessInfo) -> bool {
+        let check = |pattern: &Option<String>, value: &str| match pattern {
+            Some(pattern) if self.substring => value.contains(pattern),
+            Some(pattern) => value.starts_with(pattern),
+            None => true,
+        };
+
+        (self.cmdline.is_some() || self.name.is_some())
+            && check(&self.cmdline, &process.cmdline)
+            && check(&self.name, &process.name)
+    }
+}
+
+fn default_signal() -> ProcessSignal {
+    ProcessSignal::SIGTERM
+}
+
+/// deserializes a signal from its name, with or without the `SIG` prefix
+fn deserialize_signal<'de, D>(deserializer: D) -> Result<ProcessSignal, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let name = String::deserialize(deserializer)?.trim().to_uppercase();
+
+    name.parse::<ProcessSignal>()
+        .or_else(|_| format!("SIG{name}").parse::<ProcessSignal>())
+        .map_err(|_| serde::de::Error::custom(format!("`{name}` is not a valid signal")))
 }
 
 impl Default for ProcessModuleConfig {
@@ -54,7 +119,8 @@ impl ModuleMessage for ProcessMessage {}
 #[derive(Clone, Debug)]
 pub enum ProcessMessage {
     Processes(Vec<ProcessInfo>),
-    Stop(u64),
+    Stop(usize),
+    Unconfirm,
     Rescan,
     Ok,
 }
@@ -62,15 +128,18 @@ pub enum ProcessMessage {
 pub struct ProcessModule {
     config: ProcessModuleConfig,
 
-    /// this is actually the current state
-    icons: Vec<(u64, Icon)>,
+    /// this is actually the current state, as index of the indicator and the
+    /// pids of all processes matching it
+    running: Vec<(usize, Vec<u64>)>,
+    /// indicator which was clicked once and waits for confirmation
+    confirming: Option<(usize, Instant)>,
 }
 
 impl ProcessModule {
     pub fn new() -> Result<Self> {
-        let config: ProcessModuleConfig = CONFIG.module(PROCESS_MODULE_IDENTIFIER);
+        let config: ProcessModuleConfig = RUNTIME_CONFIG.get().module(PROCESS_MODULE_IDENTIFIER)?;
 
-        Ok(Self { config, icons: Vec::new() })
+        Ok(Self { config, running: Vec::new(), confirming: None })
     }
 }
 
@@ -82,26 +151,62 @@ impl Module for ProcessModule {
     }
 
     fn pass_message(&self, message: &str) -> Option<Self::Message> {
-        if message.eq("rescan") { Some(Self::Message::Rescan) } else { None }
+        if PassedMessage::parse(message).action == Some("rescan") {
+            Some(Self::Message::Rescan)
+        } else {
+            None
+        }
     }
 
     fn update(&mut self, message: &Self::Message) -> (Task<Self::Message>, Option<OsdId>) {
         match message {
             ProcessMessage::Processes(infos) => {
-                self.icons = self
+                self.running = self
                     .config
                     .indicators
                     .iter()
-                    .filter_map(|item| {
-                        infos
+                    .enumerate()
+                    .filter_map(|(i, item)| {
+                        let pids = infos
                             .iter()
-                            .find(|process| process.cmdline.starts_with(&item.cmdline))
-                            .map(|process| (process.pid, item.icon))
+                            .filter(|process| item.matches(process))
+                            .map(|process| process.pid)
+                            .collect::<Vec<_>>();
+
+                        (!pids.is_empty()).then_some((i, pids))
                     })
                     .collect()
             }
-            ProcessMessage::Stop(pid) => {
-                if let Err(e) = send_signal(*pid, ProcessSignal::SIGTERM) {
+            ProcessMessage::Stop(index) => {
+                let (Some(item), Some((_, pids))) = (
+                    self.config.indicators.get(*index),
+                    self.running.iter().find(|(running, _)| running == index),
+                ) else {
+                    return (Task::none(), None);
+                };
+
+                // the first click only asks for confirmation
+                if item.confirm
+                    && self.confirming.is_none_or(|(confirming, _)| confirming != *index)
+                {
+                    info!("waiting for confirmation to signal process of indicator `{index}`");
+                    self.confirming = Some((*index, Instant::now()));
+
+                    return (
+                        Task::future(async {
+                            sleep(CONFIRM_TIMEOUT).await;
+                            ProcessMessage::Unconfirm
+                        }),
+                        None,
+                    );
+                }
+
+                self.confirming = None;
+
+                // only the first process is signalled, like before multiple were tracked
+                if let Some(pid) = pids.first()
+                    && let Err(e) = send_signal(*pid, item.signal)
+                {
                     error!("failed to stop process `{pid}` on click: {e:#}")
                 }
 
@@ -130,16 +235,49 @@ impl Module for ProcessModule {
                     None,
                 );
             }
+            ProcessMessage::Unconfirm => {
+                // a later click might have started a new confirmation
+                if let Some((_, since)) = self.confirming
+                    && since.elapsed() >= CONFIRM_TIMEOUT
+                {
+                    self.confirming = None;
+                }
+            }
             ProcessMessage::Ok => {}
         }
 
         (Task::none(), None)
     }
 
+    fn snapshot(&self) -> Value {
+        json!({
+            "running": self.running.iter().flat_map(|(_, pids)| pids).collect::<Vec<_>>()
+        })
+    }
+
     fn render_info(&self) -> Vec<Element<'_, Self::Message, Theme, Renderer>> {
-        self.icons
+        self.running
             .iter()
-            .map(|(pid, c)| mouse_area(icon(*c)).on_release(Self::Message::Stop(*pid)).into())
+            .map(|(index, pids)| {
+                let item = &self.config.indicators[*index];
+
+                let symbol = if self.confirming.is_some_and(|(confirming, _)| confirming == *index)
+                {
+                    Icon::CircleX
+                } else {
+                    item.icon
+                };
+
+                let content: Element<'_, Self::Message, Theme, Renderer> = if item.count {
+                    column![icon(symbol), text(pids.len().to_string()).size(12)]
+                        .align_x(Horizontal::Center)
+                        .into()
+                } else {
+                    icon(symbol).into()
+                };
+
+                mouse_area(content).on_release(Self::Message::Stop(*index)).into()
+            })
             .collect::<Vec<_>>()
     }
 }
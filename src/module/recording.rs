@@ -0,0 +1,197 @@
+use std::{hash::Hasher as _, time::Duration};
+
+use anyhow::Result;
+use iced::{
+    Element, Renderer, Subscription, Task, Theme,
+    advanced::subscription::{EventStream, Hasher, Recipe, from_recipe},
+};
+use iced_winit::futures::BoxStream;
+use liischte_lib::{
+    hyprland::HyprlandInstance,
+    process::{ProcessInfo, listen_running_processes},
+};
+use log::{debug, warn};
+use lucide_icons::Icon;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    config::{CONFIG, RUNTIME_CONFIG, deserialize_duration_seconds, deserialize_icon},
+    module::{Module, ModuleMessage, ModuleRegistry, deserialize_config},
+    osd::OsdId,
+    ui::icon,
+};
+
+pub const RECORDING_MODULE_IDENTIFIER: &str = "recording";
+
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    registry.add_sync(
+        RECORDING_MODULE_IDENTIFIER,
+        RecordingModule::new,
+        deserialize_config::<RecordingModuleConfig>,
+    );
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct RecordingModuleConfig {
+    /// polling rate to poll for recording processes in seconds
+    #[serde(deserialize_with = "deserialize_duration_seconds")]
+    polling_rate: Duration,
+
+    /// names of processes which record the screen
+    processes: Vec<String>,
+    /// whether to listen to hyprland for screencasts through the portal
+    screencast: bool,
+
+    /// icon to show while recording
+    #[serde(deserialize_with = "deserialize_icon")]
+    icon: Icon,
+}
+
+impl Default for RecordingModuleConfig {
+    fn default() -> Self {
+        Self {
+            polling_rate: Duration::from_secs(2),
+            processes: ["wf-recorder", "wl-screenrec", "obs", "gpu-screen-reco"]
+                .map(str::to_string)
+                .to_vec(),
+            screencast: true,
+            icon: Icon::Circle,
+        }
+    }
+}
+
+impl ModuleMessage for RecordingMessage {}
+#[derive(Clone, Debug)]
+pub enum RecordingMessage {
+    Processes(Vec<ProcessInfo>),
+    Screencast(bool),
+}
+
+pub struct RecordingModule {
+    config: RecordingModuleConfig,
+    instance: Option<HyprlandInstance>,
+
+    /// names of the recording processes which are running
+    recorders: Vec<String>,
+    /// whether a screencast through the portal is active
+    screencast: bool,
+}
+
+impl RecordingModule {
+    pub fn new() -> Result<Self> {
+        let config: RecordingModuleConfig =
+            RUNTIME_CONFIG.get().module(RECORDING_MODULE_IDENTIFIER)?;
+
+        // screencasts are optional, so we can still detect processes outside of hyprland
+        let instance = if config.screencast {
+            HyprlandInstance::env()
+                .map_err(|e| warn!("not listening to screencasts, hyprland is not running: {e:#}"))
+                .ok()
+        } else {
+            None
+        };
+
+        Ok(Self { config, instance, recorders: Vec::new(), screencast: false })
+    }
+
+    /// returns whether anything is recording the screen
+    fn recording(&self) -> bool {
+        self.screencast || !self.recorders.is_empty()
+    }
+}
+
+impl Module for RecordingModule {
+    type Message = RecordingMessage;
+
+    fn subscribe(&self) -> Subscription<Self::Message> {
+        let mut subscriptions = vec![
+            from_recipe(RecorderMonitor(self.config.polling_rate)).map(RecordingMessage::Processes),
+        ];
+
+        if let Some(instance) = &self.instance {
+            subscriptions.push(
+                from_recipe(ScreencastMonitor(instance.clone())).map(RecordingMessage::Screencast),
+            );
+        }
+
+        Subscription::batch(subscriptions)
+    }
+
+    fn update(&mut self, message: &Self::Message) -> (Task<Self::Message>, Option<OsdId>) {
+        match message {
+            RecordingMessage::Processes(infos) => {
+                self.recorders = self
+                    .config
+                    .processes
+                    .iter()
+                    .filter(|name| infos.iter().any(|process| process.name == **name))
+                    .cloned()
+                    .collect()
+            }
+            RecordingMessage::Screencast(active) => self.screencast = *active,
+        }
+
+        (Task::none(), None)
+    }
+
+    fn snapshot(&self) -> Value {
+        json!({
+            "recording": self.recording(),
+            "processes": self.recorders,
+            "screencast": self.screencast,
+        })
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        if !self.recorders.is_empty() {
+            Some(format!("Recording with {}", self.recorders.join(", ")))
+        } else if self.screencast {
+            Some("Screen is being shared".to_string())
+        } else {
+            None
+        }
+    }
+
+    fn render_info(&self) -> Vec<Element<'_, Self::Message, Theme, Renderer>> {
+        if self.recording() {
+            vec![icon(self.config.icon).color(CONFIG.colors.accent).into()]
+        } else {
+            vec![]
+        }
+    }
+}
+
+struct RecorderMonitor(Duration);
+
+impl Recipe for RecorderMonitor {
+    type Output = Vec<ProcessInfo>;
+
+    fn hash(&self, state: &mut Hasher) {
+        // this must differ from the process module, as it polls at another rate
+        state.write_str("recording processes stream");
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("starting recording processes stream");
+        listen_running_processes(self.0)
+    }
+}
+
+struct ScreencastMonitor(HyprlandInstance);
+
+impl Recipe for ScreencastMonitor {
+    type Output = bool;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str("hyprland screencast events");
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("starting hyprland screencast listener");
+
+        self.0.listen_screencast()
+    }
+}
@@ -0,0 +1,112 @@
+use std::hash::Hasher as _;
+
+use anyhow::{Context, Result};
+use iced::{
+    Element, Renderer, Subscription, Task, Theme,
+    advanced::subscription::{EventStream, Hasher, Recipe, from_recipe},
+};
+use iced_winit::futures::BoxStream;
+use liischte_lib::hyprland::HyprlandInstance;
+use log::debug;
+use lucide_icons::Icon;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    config::{RUNTIME_CONFIG, deserialize_icon},
+    module::{Module, ModuleMessage, ModuleRegistry, deserialize_config},
+    osd::OsdId,
+    ui::icon,
+};
+
+pub const SUBMAP_MODULE_IDENTIFIER: &str = "submap";
+
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    registry.add(
+        SUBMAP_MODULE_IDENTIFIER,
+        SubmapModule::new,
+        deserialize_config::<SubmapModuleConfig>,
+    );
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct SubmapModuleConfig {
+    /// icon to show while a submap is active
+    #[serde(deserialize_with = "deserialize_icon")]
+    icon: Icon,
+}
+
+impl Default for SubmapModuleConfig {
+    fn default() -> Self {
+        Self { icon: Icon::Keyboard }
+    }
+}
+
+impl ModuleMessage for SubmapMessage {}
+#[derive(Clone, Debug)]
+pub enum SubmapMessage {
+    Submap(String),
+}
+
+pub struct SubmapModule {
+    config: SubmapModuleConfig,
+    instance: HyprlandInstance,
+
+    submap: String,
+}
+
+impl SubmapModule {
+    pub async fn new() -> Result<Self> {
+        let instance = HyprlandInstance::env().context(
+            "failed read environment for hyprland instance signature, are you running inside it?",
+        )?;
+
+        Ok(Self {
+            config: RUNTIME_CONFIG.get().module(SUBMAP_MODULE_IDENTIFIER)?,
+            instance,
+            submap: String::new(),
+        })
+    }
+}
+
+impl Module for SubmapModule {
+    type Message = SubmapMessage;
+
+    fn subscribe(&self) -> Subscription<Self::Message> {
+        from_recipe(SubmapMonitor(self.instance.clone())).map(SubmapMessage::Submap)
+    }
+
+    fn update(&mut self, message: &Self::Message) -> (Task<Self::Message>, Option<OsdId>) {
+        match message {
+            SubmapMessage::Submap(submap) => self.submap = submap.clone(),
+        }
+
+        (Task::none(), None)
+    }
+
+    fn snapshot(&self) -> Value {
+        json!({ "submap": self.submap })
+    }
+
+    fn render_info(&self) -> Vec<Element<'_, Self::Message, Theme, Renderer>> {
+        if self.submap.is_empty() { vec![] } else { vec![icon(self.config.icon).into()] }
+    }
+}
+
+struct SubmapMonitor(HyprlandInstance);
+
+impl Recipe for SubmapMonitor {
+    type Output = String;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str("hyprland submap events");
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("starting hyprland submap listener");
+
+        self.0.listen_submap()
+    }
+}
@@ -0,0 +1,160 @@
+use std::{hash::Hasher as _, time::Duration};
+
+use anyhow::{Context, Result, anyhow};
+use iced::{
+    Element, Renderer, Subscription, Task, Theme,
+    advanced::subscription::{EventStream, Hasher, Recipe, from_recipe},
+    alignment::Horizontal,
+    widget::{column, text},
+};
+use iced_winit::futures::BoxStream;
+use liischte_lib::sysfs::thermal::ThermalZone;
+use log::{debug, info, warn};
+use lucide_icons::Icon;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    config::{CONFIG, RUNTIME_CONFIG, deserialize_duration_seconds},
+    module::{Module, ModuleMessage, ModuleRegistry, deserialize_config},
+    osd::OsdId,
+    ui::icon,
+};
+
+pub const THERMAL_MODULE_IDENTIFIER: &str = "thermal";
+
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    registry.add(
+        THERMAL_MODULE_IDENTIFIER,
+        ThermalModule::new,
+        deserialize_config::<ThermalModuleConfig>,
+    );
+}
+
+/// zone types which are used if no zone is configured, in order of preference
+const FALLBACK_ZONES: &[&str] = &["x86_pkg_temp", "cpu"];
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct ThermalModuleConfig {
+    /// use the first zone whose type contains this (we use a cpu zone
+    /// otherwise)
+    zone: Option<String>,
+
+    /// polling rate to poll the temperature in seconds
+    #[serde(deserialize_with = "deserialize_duration_seconds")]
+    polling_rate: Duration,
+
+    /// temperature in degrees celsius above which a warning is shown
+    warning: f64,
+}
+
+impl Default for ThermalModuleConfig {
+    fn default() -> Self {
+        Self { zone: None, polling_rate: Duration::from_secs(5), warning: 80f64 }
+    }
+}
+
+impl ModuleMessage for ThermalMessage {}
+#[derive(Clone, Debug)]
+pub enum ThermalMessage {
+    Temperature(f64),
+}
+
+pub struct ThermalModule {
+    config: ThermalModuleConfig,
+
+    zone: ThermalZone,
+    temperature: f64,
+}
+
+impl ThermalModule {
+    pub async fn new() -> Result<Self> {
+        let config: ThermalModuleConfig = RUNTIME_CONFIG.get().module(THERMAL_MODULE_IDENTIFIER)?;
+
+        info!("reading available thermal zones from sysfs");
+        let zones = ThermalZone::read_all().await.context("failed to read thermal zones")?;
+
+        for zone in &zones {
+            debug!("checking thermal zone with name `{}` ({})", zone.device.name, zone.kind);
+        }
+
+        let configured = config
+            .zone
+            .as_ref()
+            .and_then(|configured| zones.iter().find(|zone| zone.kind.contains(configured)));
+
+        if configured.is_none()
+            && let Some(ref configured) = config.zone
+        {
+            warn!("configured thermal zone `{configured}` was not found, using a fallback");
+        }
+
+        let selected = configured
+            .or_else(|| {
+                FALLBACK_ZONES.iter().find_map(|fallback| {
+                    zones.iter().find(|zone| zone.kind.to_lowercase().contains(fallback))
+                })
+            })
+            .or(zones.first())
+            .cloned()
+            .ok_or(anyhow!("no thermal zone was found"))?;
+
+        info!("using thermal zone {} ({})", selected.device.name, selected.kind);
+
+        Ok(Self { temperature: selected.read_temperature().await?, zone: selected, config })
+    }
+}
+
+impl Module for ThermalModule {
+    type Message = ThermalMessage;
+
+    fn subscribe(&self) -> Subscription<Self::Message> {
+        from_recipe(TemperatureMonitor(self.zone.clone(), self.config.polling_rate))
+            .map(ThermalMessage::Temperature)
+    }
+
+    fn update(&mut self, message: &Self::Message) -> (Task<Self::Message>, Option<OsdId>) {
+        match message {
+            ThermalMessage::Temperature(temperature) => self.temperature = *temperature,
+        }
+
+        (Task::none(), None)
+    }
+
+    fn snapshot(&self) -> Value {
+        json!({ "zone": self.zone.kind, "temperature": self.temperature })
+    }
+
+    fn has_status(&self) -> bool {
+        true
+    }
+
+    fn render_status(&self) -> Element<'_, Self::Message, Theme, Renderer> {
+        let symbol = if self.temperature > self.config.warning {
+            icon(Icon::ThermometerSun).color(CONFIG.colors.warning)
+        } else {
+            icon(Icon::Thermometer)
+        };
+
+        column![symbol, text!("{:.0}°", self.temperature).size(12)]
+            .align_x(Horizontal::Center)
+            .into()
+    }
+}
+
+struct TemperatureMonitor(ThermalZone, Duration);
+
+impl Recipe for TemperatureMonitor {
+    type Output = f64;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str(&format!("thermal temperature events for {}", self.0.device.name));
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("starting temperature listener for {}", self.0.device.name);
+        self.0.listen_temperature(self.1)
+    }
+}
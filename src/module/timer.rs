@@ -1,8 +1,10 @@
 use std::time::{Duration, Instant};
 
+use anyhow::Result;
 use iced::{
     Background, Border, Element, Renderer, Subscription, Task, Theme,
     alignment::Horizontal,
+    task::Handle,
     widget::{column, progress_bar},
 };
 use liischte_lib::StreamContext;
@@ -10,20 +12,30 @@ use log::{info, warn};
 use lucide_icons::Icon;
 use notify_rust::Notification;
 use serde::Deserialize;
+use serde_json::{Value, json};
 use tokio::time::sleep;
 
 use crate::{
-    config::{CONFIG, deserialize_icon},
-    module::{Module, ModuleMessage},
+    config::{CONFIG, RUNTIME_CONFIG, deserialize_icon},
+    module::{Module, ModuleMessage, ModuleRegistry, PassedMessage, deserialize_config},
     osd::OsdId,
     ui::{PILL_RADIUS, icon},
 };
 
 pub const TIMER_MODULE_IDENTIFIER: &str = "timer";
 
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    registry.add_sync(
+        TIMER_MODULE_IDENTIFIER,
+        TimerModule::new,
+        deserialize_config::<TimerModuleConfig>,
+    );
+}
+
 #[derive(Deserialize)]
 #[serde(default)]
-struct TimerModuleConfig {
+pub struct TimerModuleConfig {
     /// default icon to show if none is set
     #[serde(deserialize_with = "deserialize_icon")]
     default_icon: Icon,
@@ -32,6 +44,9 @@ struct TimerModuleConfig {
     heading: String,
     /// set notification to never expire
     persistent: bool,
+
+    /// whether timers start over once expired if not specified
+    repeat: bool,
 }
 
 impl Default for TimerModuleConfig {
@@ -41,6 +56,8 @@ impl Default for TimerModuleConfig {
 
             heading: "Timer Expired!".to_string(),
             persistent: true,
+
+            repeat: false,
         }
     }
 }
@@ -48,7 +65,10 @@ impl Default for TimerModuleConfig {
 impl ModuleMessage for TimerMessage {}
 #[derive(Clone, Debug)]
 pub enum TimerMessage {
-    Create(Icon, String, Duration),
+    Create(Icon, String, Duration, bool),
+    Pause(usize),
+    Resume(usize),
+    Cancel(usize),
     Stop,
     Ok,
 }
@@ -57,19 +77,74 @@ pub struct TimerModule {
     config: TimerModuleConfig,
 
     timers: Vec<Timer>,
+    next_id: usize, // ids are never reused, so they stay valid for scripts
 }
 
 pub struct Timer {
+    id: usize,
     icon: Icon,
     message: String,
+    repeat: bool,
 
-    start: Instant,
     duration: Duration,
+    elapsed: Duration,        // elapsed time before the timer was last resumed
+    resumed: Option<Instant>, // when the timer was last resumed, none if paused
+
+    stop: Option<Handle>, // dropping this cancels the scheduled stop
+}
+
+impl Timer {
+    /// returns the time the timer has been running for
+    fn elapsed(&self, now: Instant) -> Duration {
+        self.elapsed + self.resumed.map(|resumed| now - resumed).unwrap_or_default()
+    }
+
+    /// returns the time until the timer expires
+    fn remaining(&self, now: Instant) -> Duration {
+        self.duration.saturating_sub(self.elapsed(now))
+    }
+
+    /// returns the fraction of the duration which is remaining
+    fn progress(&self, now: Instant) -> f32 {
+        if self.duration.is_zero() {
+            0.0
+        } else {
+            self.remaining(now).as_secs_f32() / self.duration.as_secs_f32()
+        }
+    }
+
+    /// returns whether the timer is running and has expired
+    fn expired(&self, now: Instant) -> bool {
+        self.resumed.is_some() && self.remaining(now).is_zero()
+    }
+
+    /// schedules the stop of the timer for when it expires, replacing the
+    /// previously scheduled one
+    fn schedule(&mut self) -> Task<TimerMessage> {
+        let remaining = self.remaining(Instant::now());
+
+        let (task, handle) = Task::abortable(Task::future(async move {
+            sleep(remaining + Duration::from_millis(100) /* a bit of leeway */).await;
+            TimerMessage::Stop
+        }));
+
+        self.stop = Some(handle.abort_on_drop());
+        task
+    }
 }
 
 impl TimerModule {
-    pub fn new() -> Self {
-        Self { config: CONFIG.module(TIMER_MODULE_IDENTIFIER), timers: vec![] }
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            config: RUNTIME_CONFIG.get().module(TIMER_MODULE_IDENTIFIER)?,
+            timers: vec![],
+            next_id: 0,
+        })
+    }
+
+    /// finds the timer with the given id
+    fn timer_mut(&mut self, id: usize) -> Option<&mut Timer> {
+        self.timers.iter_mut().find(|timer| timer.id == id)
     }
 }
 
@@ -81,35 +156,53 @@ impl Module for TimerModule {
     }
 
     fn pass_message(&self, message: &str) -> Option<Self::Message> {
-        let mut desc = None;
-        let mut icon = None;
-        let mut duration = None;
-
-        for (key, value) in message
-            .split('|')
-            .filter_map(|s| s.split_once('='))
-            .map(|(key, value)| (key.trim(), value.trim()))
-        {
-            match key {
-                "icon" => {
-                    let Some(icon_val) = Icon::from_name(value) else {
-                        info!("passed invalid icon {value} to timer");
-                        continue;
-                    };
-
-                    icon = Some(icon_val);
-                }
-                "duration" => {
-                    let Ok(int) = value.parse::<u64>() else {
-                        info!("passed invalid integer {value} as duration to timer");
-                        continue;
-                    };
+        let message = PassedMessage::parse(message);
 
-                    duration = Some(Duration::from_secs(int))
-                }
-                "message" => desc = Some(value.to_string()),
-                _ => {}
+        let id = message.get("id").and_then(|value| {
+            value
+                .parse::<usize>()
+                .map_err(|_| info!("passed invalid integer {value} as id to timer"))
+                .ok()
+        });
+        let icon = message.get("icon").and_then(|value| {
+            Icon::from_name(value).or_else(|| {
+                info!("passed invalid icon {value} to timer");
+                None
+            })
+        });
+        let duration = message.get("duration").and_then(|value| {
+            value
+                .parse::<u64>()
+                .map(Duration::from_secs)
+                .map_err(|_| info!("passed invalid integer {value} as duration to timer"))
+                .ok()
+        });
+        let desc = message.get("message").map(str::to_string);
+        let repeat = message.get("repeat").and_then(|value| {
+            value
+                .parse::<bool>()
+                .map_err(|_| info!("passed invalid boolean {value} as repeat to timer"))
+                .ok()
+        });
+
+        match message.action {
+            Some(action @ ("pause" | "resume" | "cancel")) => {
+                let Some(id) = id else {
+                    warn!("not changing timer because no id was given");
+                    return None;
+                };
+
+                return Some(match action {
+                    "pause" => TimerMessage::Pause(id),
+                    "resume" => TimerMessage::Resume(id),
+                    _ => TimerMessage::Cancel(id),
+                });
+            }
+            Some(action) => {
+                warn!("passed unknown action {action} to timer");
+                return None;
             }
+            None => {}
         }
 
         let Some(duration) = duration else {
@@ -117,88 +210,141 @@ impl Module for TimerModule {
             return None;
         };
 
+        let repeat = repeat.unwrap_or(self.config.repeat);
+
+        // it would expire and notify again right away, forever
+        if repeat && duration.is_zero() {
+            warn!("not adding repeating timer because its duration is zero");
+            return None;
+        }
+
         Some(TimerMessage::Create(
             icon.unwrap_or(self.config.default_icon),
             desc.unwrap_or(format!("{} seconds have elapsed", duration.as_secs())),
             duration,
+            repeat,
         ))
     }
 
+    fn pass_response(&self, message: &Self::Message) -> Value {
+        match message {
+            // the created timer is always the last one
+            TimerMessage::Create(..) => json!({ "id": self.timers.last().map(|timer| timer.id) }),
+            _ => Value::Null,
+        }
+    }
+
     fn update(&mut self, message: &Self::Message) -> (Task<Self::Message>, Option<OsdId>) {
         match message {
-            TimerMessage::Create(icon, desc, duration) => {
-                self.timers.push(Timer {
+            TimerMessage::Create(icon, desc, duration, repeat) => {
+                let mut timer = Timer {
+                    id: self.next_id,
                     message: desc.clone(),
+                    repeat: *repeat,
                     icon: *icon,
                     duration: *duration,
-                    start: Instant::now(),
-                });
+                    elapsed: Duration::ZERO,
+                    resumed: Some(Instant::now()),
+                    stop: None,
+                };
+
+                let task = timer.schedule();
+                self.timers.push(timer);
+
+                info!("created timer {} for {}s", self.next_id, duration.as_secs());
+                self.next_id += 1;
+
+                (task, None)
+            }
+            TimerMessage::Pause(id) => {
+                if let Some(timer) = self.timer_mut(*id)
+                    && let Some(resumed) = timer.resumed.take()
+                {
+                    timer.elapsed += Instant::now() - resumed;
+                    timer.stop = None;
+                } else {
+                    info!("timer {id} does not exist or is already paused");
+                }
+
+                (Task::none(), None)
+            }
+            TimerMessage::Resume(id) => {
+                if let Some(timer) = self.timer_mut(*id)
+                    && timer.resumed.is_none()
+                {
+                    timer.resumed = Some(Instant::now());
+                    (timer.schedule(), None)
+                } else {
+                    info!("timer {id} does not exist or is not paused");
+                    (Task::none(), None)
+                }
+            }
+            TimerMessage::Cancel(id) => {
+                if let Some(index) = self.timers.iter().position(|timer| timer.id == *id) {
+                    // this also aborts the scheduled stop
+                    self.timers.remove(index);
+                } else {
+                    info!("timer {id} does not exist");
+                }
 
-                let duration = *duration;
-                (
-                    Task::future(async move {
-                        sleep(duration + Duration::from_millis(100) /* a bit of leeway */).await;
-                        TimerMessage::Stop
-                    }),
-                    None,
-                )
+                (Task::none(), None)
             }
             TimerMessage::Stop => {
                 let now = Instant::now();
+                let mut tasks = vec![];
 
-                (
-                    Task::batch(
-                        self.timers.extract_if(.., |timer| timer.start + timer.duration < now).map(
-                            |timer| {
-                                let heading = self.config.heading.clone();
-                                let persistent = self.config.persistent;
-
-                                Task::future(async move {
-                                    let mut builder = Notification::new();
-
-                                    builder.summary(&heading);
-                                    builder.body(&timer.message);
-                                    if persistent {
-                                        builder.timeout(0);
-                                    }
-
-                                    builder
-                                        .show_async()
-                                        .await
-                                        .stream_log("failed to send notification");
-
-                                    TimerMessage::Ok // we need this, with .discard() we have lifetime issues
-                                })
-                            },
-                        ),
-                    ),
-                    None,
-                )
+                for timer in self.timers.iter_mut().filter(|timer| timer.expired(now)) {
+                    tasks.push(notify(&self.config, timer.message.clone()));
+
+                    // repeating timers start over instead of being removed
+                    if timer.repeat {
+                        timer.elapsed = Duration::ZERO;
+                        timer.resumed = Some(now);
+                        tasks.push(timer.schedule());
+                    }
+                }
+
+                self.timers.retain(|timer| !timer.expired(now));
+
+                (Task::batch(tasks), None)
             }
             TimerMessage::Ok => (Task::none(), None),
         }
     }
 
+    fn snapshot(&self) -> Value {
+        let now = Instant::now();
+
+        json!({
+            "timers": self.timers.iter().map(|timer| json!({
+                "id": timer.id,
+                "message": timer.message,
+                "duration": timer.duration.as_secs(),
+                "remaining": timer.remaining(now).as_secs(),
+                "paused": timer.resumed.is_none(),
+                "repeat": timer.repeat,
+            })).collect::<Vec<_>>(),
+        })
+    }
+
     fn render_info(&self) -> Vec<Element<'_, Self::Message, Theme, Renderer>> {
+        let now = Instant::now();
+
         self.timers
             .iter()
             .map(|timer| {
                 column![
                     icon(timer.icon),
-                    progress_bar(
-                        0.0..=1.0,
-                        1.0 - (Instant::now() - timer.start).as_secs_f32()
-                            / timer.duration.as_secs_f32()
-                    )
-                    .style(|_| progress_bar::Style {
-                        background: Background::Color(
-                            CONFIG.looks.foreground.scale_alpha(CONFIG.looks.tone_opacity),
-                        ),
-                        border: Border::default().width(0).rounded(PILL_RADIUS),
-                        bar: Background::Color(CONFIG.looks.foreground),
-                    })
-                    .height(2.0)
-                    .width(24)
+                    progress_bar(0.0..=1.0, timer.progress(now))
+                        .style(|_| progress_bar::Style {
+                            background: Background::Color(
+                                CONFIG.looks.foreground.scale_alpha(CONFIG.looks.tone_opacity),
+                            ),
+                            border: Border::default().width(0).rounded(PILL_RADIUS),
+                            bar: Background::Color(CONFIG.looks.foreground),
+                        })
+                        .height(2.0)
+                        .width(24)
                 ]
                 .align_x(Horizontal::Center)
                 .spacing(-4.0)
@@ -207,3 +353,23 @@ impl Module for TimerModule {
             .collect::<Vec<_>>()
     }
 }
+
+/// sends the notification for an expired timer
+fn notify(config: &TimerModuleConfig, message: String) -> Task<TimerMessage> {
+    let heading = config.heading.clone();
+    let persistent = config.persistent;
+
+    Task::future(async move {
+        let mut builder = Notification::new();
+
+        builder.summary(&heading);
+        builder.body(&message);
+        if persistent {
+            builder.timeout(0);
+        }
+
+        builder.show_async().await.stream_log("failed to send notification");
+
+        TimerMessage::Ok // we need this, with .discard() we have lifetime issues
+    })
+}
@@ -0,0 +1,162 @@
+use std::{hash::Hasher as _, time::Duration};
+
+use anyhow::Result;
+use futures::{StreamExt, stream};
+use iced::{
+    Element, Renderer, Subscription, Task, Theme,
+    advanced::subscription::{EventStream, Hasher, Recipe, from_recipe},
+    alignment::Horizontal,
+    widget::{column, text},
+};
+use iced_winit::futures::BoxStream;
+use liischte_lib::weather::{Weather, WeatherCondition, listen_weather};
+use log::{debug, error};
+use lucide_icons::Icon;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    config::{RUNTIME_CONFIG, deserialize_duration_seconds},
+    module::{Module, ModuleMessage, ModuleRegistry, deserialize_config},
+    osd::OsdId,
+    ui::icon,
+};
+
+pub const WEATHER_MODULE_IDENTIFIER: &str = "weather";
+
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    registry.add_sync(
+        WEATHER_MODULE_IDENTIFIER,
+        WeatherModule::new,
+        deserialize_config::<WeatherModuleConfig>,
+    );
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct WeatherModuleConfig {
+    /// url returning the current weather as open-meteo or wttr.in json
+    url: String,
+
+    /// polling rate to poll the weather in seconds
+    #[serde(deserialize_with = "deserialize_duration_seconds")]
+    polling_rate: Duration,
+
+    /// whether to show the temperature below the icon
+    temperature: bool,
+}
+
+impl Default for WeatherModuleConfig {
+    fn default() -> Self {
+        Self {
+            url: "https://wttr.in/?format=j1".to_string(),
+            polling_rate: Duration::from_mins(30),
+            temperature: true,
+        }
+    }
+}
+
+impl ModuleMessage for WeatherMessage {}
+#[derive(Clone, Debug)]
+pub enum WeatherMessage {
+    Weather(Weather),
+}
+
+pub struct WeatherModule {
+    config: WeatherModuleConfig,
+
+    /// last weather that was fetched successfully
+    weather: Option<Weather>,
+}
+
+impl WeatherModule {
+    pub fn new() -> Result<Self> {
+        Ok(Self { config: RUNTIME_CONFIG.get().module(WEATHER_MODULE_IDENTIFIER)?, weather: None })
+    }
+}
+
+impl Module for WeatherModule {
+    type Message = WeatherMessage;
+
+    fn subscribe(&self) -> Subscription<Self::Message> {
+        from_recipe(WeatherMonitor(self.config.url.clone(), self.config.polling_rate))
+            .map(WeatherMessage::Weather)
+    }
+
+    fn update(&mut self, message: &Self::Message) -> (Task<Self::Message>, Option<OsdId>) {
+        match message {
+            WeatherMessage::Weather(weather) => self.weather = Some(*weather),
+        }
+
+        (Task::none(), None)
+    }
+
+    fn snapshot(&self) -> Value {
+        self.weather
+            .map(|weather| {
+                json!({
+                    "temperature": weather.temperature,
+                    "condition": format!("{:?}", weather.condition),
+                })
+            })
+            .unwrap_or(Value::Null)
+    }
+
+    fn tooltip(&self) -> Option<String> {
+        let weather = self.weather?;
+
+        Some(format!("{:?} ({:.0}°C)", weather.condition, weather.temperature))
+    }
+
+    fn has_status(&self) -> bool {
+        true
+    }
+
+    fn render_status(&self) -> Element<'_, Self::Message, Theme, Renderer> {
+        let Some(weather) = self.weather else {
+            return icon(Icon::CloudOff).into();
+        };
+
+        let symbol = match weather.condition {
+            WeatherCondition::Clear => Icon::Sun,
+            WeatherCondition::PartlyCloudy => Icon::CloudSun,
+            WeatherCondition::Cloudy | WeatherCondition::Unknown => Icon::Cloud,
+            WeatherCondition::Fog => Icon::CloudFog,
+            WeatherCondition::Drizzle => Icon::CloudDrizzle,
+            WeatherCondition::Rain => Icon::CloudRain,
+            WeatherCondition::Snow => Icon::CloudSnow,
+            WeatherCondition::Thunderstorm => Icon::CloudLightning,
+        };
+
+        if self.config.temperature {
+            column![icon(symbol), text(format!("{:.0}°", weather.temperature)).size(12)]
+                .align_x(Horizontal::Center)
+                .into()
+        } else {
+            icon(symbol).into()
+        }
+    }
+}
+
+struct WeatherMonitor(String, Duration);
+
+impl Recipe for WeatherMonitor {
+    type Output = Weather;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str(&format!("weather stream for {}", self.0));
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("starting weather stream");
+
+        match listen_weather(self.0, self.1) {
+            Ok(stream) => stream,
+            Err(e) => {
+                error!("failed to start weather stream: {e:#}");
+                stream::empty().boxed()
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::hash::Hasher as _;
+
+use anyhow::{Context, Result};
+use iced::{
+    Element, Renderer, Subscription, Task, Theme,
+    advanced::subscription::{EventStream, Hasher, Recipe, from_recipe},
+    alignment::Horizontal,
+    widget::{Column, text},
+};
+use iced_winit::futures::BoxStream;
+use liischte_lib::hyprland::HyprlandInstance;
+use log::debug;
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::{
+    config::RUNTIME_CONFIG,
+    module::{Module, ModuleMessage, ModuleRegistry, deserialize_config},
+    osd::OsdId,
+};
+
+pub const WINDOW_MODULE_IDENTIFIER: &str = "window";
+
+/// registers the module under its identifier
+pub fn register(registry: &mut ModuleRegistry) {
+    registry.add(
+        WINDOW_MODULE_IDENTIFIER,
+        WindowModule::new,
+        deserialize_config::<WindowModuleConfig>,
+    );
+}
+
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct WindowModuleConfig {
+    /// maximum amount of characters of the title to show
+    max_chars: usize,
+    /// text size of the characters
+    size: f32,
+}
+
+impl Default for WindowModuleConfig {
+    fn default() -> Self {
+        Self { max_chars: 16, size: 12f32 }
+    }
+}
+
+impl ModuleMessage for WindowMessage {}
+#[derive(Clone, Debug)]
+pub enum WindowMessage {
+    Title(Option<String>),
+}
+
+pub struct WindowModule {
+    config: WindowModuleConfig,
+    instance: HyprlandInstance,
+
+    title: Option<String>,
+}
+
+impl WindowModule {
+    pub async fn new() -> Result<Self> {
+        let instance = HyprlandInstance::env().context(
+            "failed read environment for hyprland instance signature, are you running inside it?",
+        )?;
+
+        Ok(Self {
+            config: RUNTIME_CONFIG.get().module(WINDOW_MODULE_IDENTIFIER)?,
+            instance,
+            title: None,
+        })
+    }
+}
+
+impl Module for WindowModule {
+    type Message = WindowMessage;
+
+    fn subscribe(&self) -> Subscription<Self::Message> {
+        from_recipe(ActiveWindowMonitor(self.instance.clone())).map(WindowMessage::Title)
+    }
+
+    fn update(&mut self, message: &Self::Message) -> (Task<Self::Message>, Option<OsdId>) {
+        match message {
+            WindowMessage::Title(title) => {
+                self.title = title.as_ref().map(|title| truncate(title, self.config.max_chars))
+            }
+        }
+
+        (Task::none(), None)
+    }
+
+    fn snapshot(&self) -> Value {
+        json!({ "title": self.title })
+    }
+
+    fn render_info(&self) -> Vec<Element<'_, Self::Message, Theme, Renderer>> {
+        let Some(ref title) = self.title else { return vec![] };
+
+        // the bar is vertical, so we show the title top to bottom
+        vec![
+            Column::from_iter(title.chars().map(|c| text(c).size(self.config.size).into()))
+                .align_x(Horizontal::Center)
+                .into(),
+        ]
+    }
+}
+
+/// truncates a string to the given amount of characters with an ellipsis
+fn truncate(string: &str, max: usize) -> String {
+    if string.chars().count() <= max {
+        string.to_owned()
+    } else {
+        string.chars().take(max.saturating_sub(1)).chain(std::iter::once('…')).collect()
+    }
+}
+
+struct ActiveWindowMonitor(HyprlandInstance);
+
+impl Recipe for ActiveWindowMonitor {
+    type Output = Option<String>;
+
+    fn hash(&self, state: &mut Hasher) {
+        state.write_str("hyprland active window events");
+    }
+
+    fn stream(self: Box<Self>, _input: EventStream) -> BoxStream<Self::Output> {
+        debug!("starting hyprland active window listener");
+
+        self.0.listen_active_window()
+    }
+}
@@ -1,7 +1,8 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use iced::{
     Limits, Task,
+    alignment::Vertical,
     runtime::platform_specific::wayland::layer_surface::{
         IcedMargin, IcedOutput, SctkLayerSurfaceSettings,
     },
@@ -13,21 +14,68 @@ use iced_winit::commands::{
     subsurface::Anchor,
 };
 use log::debug;
+use serde::Deserialize;
 use tokio::time::sleep;
 
-use crate::{config::CONFIG, module::ModuleId};
+use crate::{config::CONFIG, module::ModuleId, ui::window::shell_layer};
 
 /// an id that can be returned by a module to differentiate betweent it's own
 /// different osds, different ids will cause respawning
-pub type OsdId = u32;
+pub type OsdId = u64;
+
+/// osd id reserved for showing the tooltip of a module
+pub const TOOLTIP_OSD: OsdId = OsdId::MAX;
+
+/// time between two frames of the fade animation
+const FADE_TICK: Duration = Duration::from_millis(16);
+
+/// where the osd is shown on the screen
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OsdPosition {
+    /// on top of the bar
+    #[default]
+    FollowBar,
+    /// in the center of the screen
+    Center,
+    /// at the top center of the screen
+    Top,
+    /// at the bottom center of the screen
+    Bottom,
+}
+
+impl OsdPosition {
+    /// returns how the osd is aligned vertically on its surface
+    pub fn alignment(self) -> Vertical {
+        match self {
+            OsdPosition::FollowBar | OsdPosition::Center => Vertical::Center,
+            OsdPosition::Top => Vertical::Top,
+            OsdPosition::Bottom => Vertical::Bottom,
+        }
+    }
+}
+
+/// direction of the fade animation
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Fade {
+    In,
+    Out,
+}
 
 pub struct OsdHandler {
     current: Option<(ModuleId, OsdId)>,
     last: Option<(ModuleId, OsdId)>, // iced re-renders before the surface is closed
+    interactive: bool,               // whether the current osd takes pointer input
+    wide: bool,                      // whether the current osd is wider than the bar
 
     timeout: Option<Handle>,
+    duration: Duration, // time the current osd is shown for
     respawning: bool,
 
+    alpha: f32,                         // opacity the osd is currently shown with
+    fade: Option<(Fade, Instant, f32)>, // running fade with its start and initial opacity
+    fade_generation: u64,               // ticks of previous fades are ignored
+
     pub output: Option<IcedOutput>,
     pub surface: Id,
 }
@@ -36,6 +84,7 @@ pub struct OsdHandler {
 pub enum OsdMessage {
     Close,
     Respawn,
+    Tick(u64),
 }
 
 impl OsdHandler {
@@ -44,8 +93,14 @@ impl OsdHandler {
         Self {
             current: None,
             last: None,
+            interactive: false,
+            wide: false,
             timeout: None,
+            duration: Duration::from_millis(CONFIG.osd.timeout),
             respawning: false,
+            alpha: 1f32,
+            fade: None,
+            fade_generation: 0,
             surface: Id::unique(),
             output: None,
         }
@@ -55,10 +110,13 @@ impl OsdHandler {
     pub fn update(&mut self, message: OsdMessage) -> Task<OsdMessage> {
         match message {
             OsdMessage::Close => {
-                debug!("closing osd layer");
-                let last = self.current.take();
+                // a respawning osd has no surface to fade out
+                if CONFIG.osd.fade_time == 0 || self.respawning || self.current.is_none() {
+                    return self.close();
+                }
 
-                self.destroy_surface(last)
+                debug!("fading out osd layer");
+                self.start_fade(Fade::Out)
             }
             OsdMessage::Respawn => {
                 debug!("respawning osd layer");
@@ -66,16 +124,84 @@ impl OsdHandler {
 
                 Task::batch(vec![self.create_surface(), self.reset_timeout()])
             }
+            OsdMessage::Tick(generation) => {
+                let Some((fade, start, initial)) = self.fade else { return Task::none() };
+                if generation != self.fade_generation {
+                    return Task::none();
+                }
+
+                let progress = (start.elapsed().as_secs_f32()
+                    / Duration::from_millis(CONFIG.osd.fade_time).as_secs_f32())
+                .min(1f32);
+                let target = if fade == Fade::In { 1f32 } else { 0f32 };
+
+                self.alpha = initial + (target - initial) * progress;
+
+                if progress < 1f32 {
+                    self.tick()
+                } else {
+                    self.fade = None;
+
+                    if fade == Fade::Out { self.close() } else { Task::none() }
+                }
+            }
+        }
+    }
+
+    /// requests the osd for a given id, interactive osds take pointer input. it
+    /// is shown for the given timeout, or the configured one if none
+    pub fn request_osd(
+        &mut self,
+        id: ModuleId,
+        osd: OsdId,
+        interactive: bool,
+        timeout: Option<Duration>,
+    ) -> Task<OsdMessage> {
+        self.request(id, osd, interactive, interactive, timeout)
+    }
+
+    /// requests an osd which is wide but not interactive, for content which
+    /// doesn't fit the width of the bar
+    pub fn request_wide_osd(&mut self, id: ModuleId, osd: OsdId) -> Task<OsdMessage> {
+        self.request(id, osd, false, true, None)
+    }
+
+    /// requests the tooltip of a module, which is wide but not interactive
+    pub fn request_tooltip(&mut self, id: ModuleId) -> Task<OsdMessage> {
+        // the pointer has to cross the bar to reach an interactive osd
+        if self.interactive && self.current.is_some() {
+            return Task::none();
+        }
+
+        self.request(id, TOOLTIP_OSD, false, true, None)
+    }
+
+    /// closes the tooltip of a module if it is currently shown
+    pub fn dismiss_tooltip(&mut self, id: ModuleId) -> Task<OsdMessage> {
+        if self.current == Some((id, TOOLTIP_OSD)) && !self.respawning {
+            self.timeout = None;
+            self.update(OsdMessage::Close)
+        } else {
+            Task::none()
         }
     }
 
-    /// requests the osd for a given id
-    pub fn request_osd(&mut self, id: ModuleId, osd: OsdId) -> Task<OsdMessage> {
+    fn request(
+        &mut self,
+        id: ModuleId,
+        osd: OsdId,
+        interactive: bool,
+        wide: bool,
+        timeout: Option<Duration>,
+    ) -> Task<OsdMessage> {
         let same = self.current == Some((id, osd));
         let alive = self.current.is_some();
 
         let last = self.current;
         self.current = Some((id, osd));
+        self.interactive = interactive;
+        self.wide = wide;
+        self.duration = timeout.unwrap_or(Duration::from_millis(CONFIG.osd.timeout));
 
         let task = match (alive, same, self.respawning) {
             // spawn surface if not alive and not respawning
@@ -96,6 +222,10 @@ impl OsdHandler {
                     }),
                 ])
             }
+            // the osd was about to close, so we bring it back
+            (true, true, false) if self.fade.is_some_and(|(fade, _, _)| fade == Fade::Out) => {
+                self.start_fade(Fade::In)
+            }
             _ => Task::none(),
         };
 
@@ -103,13 +233,24 @@ impl OsdHandler {
     }
 
     /// returns the active osd
-    pub fn get_active(&self) -> Option<(ModuleId, u32)> {
+    pub fn get_active(&self) -> Option<(ModuleId, OsdId)> {
         self.current.or(self.last)
     }
 
+    /// returns the opacity the osd should be rendered with
+    pub fn get_alpha(&self) -> f32 {
+        self.alpha
+    }
+
+    /// returns the width of the osd surface
+    pub fn get_width(&self) -> u32 {
+        if self.wide { CONFIG.osd.interactive_width } else { CONFIG.looks.width }
+    }
+
     fn reset_timeout(&mut self) -> Task<OsdMessage> {
-        let (timeout, handle) = Task::abortable(Task::future(async {
-            sleep(Duration::from_millis(CONFIG.osd.timeout)).await;
+        let duration = self.duration;
+        let (timeout, handle) = Task::abortable(Task::future(async move {
+            sleep(duration).await;
             OsdMessage::Close
         }));
 
@@ -117,9 +258,36 @@ impl OsdHandler {
         timeout
     }
 
-    fn destroy_surface(&mut self, last: Option<(ModuleId, u32)>) -> Task<OsdMessage> {
+    fn close(&mut self) -> Task<OsdMessage> {
+        debug!("closing osd layer");
+        let last = self.current.take();
+
+        self.destroy_surface(last)
+    }
+
+    fn start_fade(&mut self, fade: Fade) -> Task<OsdMessage> {
+        self.fade = Some((fade, Instant::now(), self.alpha));
+        self.fade_generation += 1;
+
+        self.tick()
+    }
+
+    fn tick(&self) -> Task<OsdMessage> {
+        let generation = self.fade_generation;
+
+        Task::future(async move {
+            sleep(FADE_TICK).await;
+            OsdMessage::Tick(generation)
+        })
+    }
+
+    fn destroy_surface(&mut self, last: Option<(ModuleId, OsdId)>) -> Task<OsdMessage> {
         self.last = last;
 
+        // a running fade would otherwise continue on the next surface
+        self.fade = None;
+        self.fade_generation += 1;
+
         destroy_layer_surface(self.surface)
     }
 
@@ -129,29 +297,53 @@ impl OsdHandler {
             return Task::none();
         };
 
-        get_layer_surface(SctkLayerSurfaceSettings {
+        let padding = CONFIG.looks.padding as i32;
+
+        let (anchor, length, exclusive_zone) = match CONFIG.osd.position {
+            // the osd covers the bar, which it can only do for vertical bars
+            OsdPosition::FollowBar => {
+                let (along, length) = if CONFIG.looks.horizontal() {
+                    (Anchor::TOP | Anchor::BOTTOM, None)
+                } else {
+                    (
+                        CONFIG.looks.anchor.anchors(Anchor::TOP, Anchor::BOTTOM),
+                        CONFIG.looks.extent(),
+                    )
+                };
+
+                (along | if CONFIG.right { Anchor::RIGHT } else { Anchor::LEFT }, length, -1)
+            }
+            // the surface spans the screen vertically and is centered horizontally, the pill
+            // is then aligned on it (see `OsdPosition::alignment`)
+            _ => (Anchor::TOP | Anchor::BOTTOM, None, 0),
+        };
+
+        let fade = if CONFIG.osd.fade_time == 0 {
+            self.alpha = 1f32;
+            Task::none()
+        } else {
+            self.alpha = 0f32;
+            self.start_fade(Fade::In)
+        };
+
+        let surface = get_layer_surface(SctkLayerSurfaceSettings {
             output,
             id: self.surface,
 
-            layer: CONFIG.osd.layer.into(),
-            anchor: Anchor::TOP
-                | if CONFIG.right { Anchor::RIGHT } else { Anchor::LEFT }
-                | Anchor::BOTTOM,
-
-            margin: IcedMargin {
-                bottom: CONFIG.looks.padding as i32,
-                left: CONFIG.looks.padding as i32,
-                top: CONFIG.looks.padding as i32,
-                right: 0,
-            },
-            size: Some((Some(CONFIG.looks.width), None)),
-            exclusive_zone: -1,
+            layer: shell_layer(CONFIG.osd.layer),
+            anchor,
+
+            margin: IcedMargin { bottom: padding, left: padding, top: padding, right: 0 },
+            size: Some((Some(self.get_width()), length)),
+            exclusive_zone,
             size_limits: Limits::NONE,
 
-            pointer_interactivity: false,
+            pointer_interactivity: self.interactive,
             namespace: format!("{}-osd", CONFIG.namespace),
 
             ..Default::default()
-        })
+        });
+
+        Task::batch(vec![surface, fade])
     }
 }
@@ -0,0 +1,139 @@
+use iced::{
+    Background, Color, Element, Length, Rectangle, Size,
+    core::{
+        self, Layout, Widget,
+        layout::{self, Limits, Node},
+        mouse, renderer,
+        widget::Tree,
+    },
+};
+
+/// creates a fill which is stacked on an icon of the given size, takes a value
+/// between 0 and 1 and the area it fills on the 24px icon grid
+pub fn icon_fill(value: f32, inset: Rectangle, size: f32, color: Color) -> IconFill {
+    IconFill { value, inset, size, color, aliasing: 0.0 }
+}
+
+/// a fill drawn over an icon, which grows from the left of its inset with the
+/// value. this is used to show a level inside an outline icon
+pub struct IconFill {
+    value: f32,
+
+    inset: Rectangle,
+    size: f32,
+    color: Color,
+
+    aliasing: f32,
+}
+
+impl IconFill {
+    /// shrinks the fill vertically by the given amount on each side, to
+    /// replicate the slight aliasing of the icon it is drawn in
+    pub fn aliasing(mut self, aliasing: f32) -> Self {
+        self.aliasing = aliasing;
+        self
+    }
+
+    /// computes the bounds of the fill, the icon is centered inside the given
+    /// bounds like the glyph it is stacked on
+    fn geometry(&self, bounds: Rectangle) -> Rectangle {
+        let scale = self.size / 24.0;
+        let (x, y) = (bounds.center_x() - self.size / 2.0, bounds.center_y() - self.size / 2.0);
+
+        Rectangle {
+            x: (x + self.inset.x * scale).floor(),
+            y: (y + self.inset.y * scale).floor() + self.aliasing,
+            width: self.inset.width * scale * self.value.clamp(0.0, 1.0),
+            height: self.inset.height * scale - self.aliasing * 2.0,
+        }
+    }
+}
+
+impl<Message, Theme, Renderer> Widget<Message, Theme, Renderer> for IconFill
+where
+    Message: Clone,
+    Renderer: core::Renderer,
+{
+    fn size(&self) -> Size<Length> {
+        Size { width: Length::Fill, height: Length::Fill }
+    }
+
+    fn layout(&self, _tree: &mut Tree, _renderer: &Renderer, limits: &Limits) -> Node {
+        layout::atomic(limits, Length::Fill, Length::Fill)
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        renderer.fill_quad(
+            renderer::Quad { bounds: self.geometry(layout.bounds()), ..renderer::Quad::default() },
+            Background::Color(self.color),
+        );
+    }
+}
+
+impl<'a, Message, Theme, Renderer> From<IconFill> for Element<'a, Message, Theme, Renderer>
+where
+    Message: Clone + 'a,
+    Renderer: core::Renderer + 'a,
+{
+    fn from(fill: IconFill) -> Element<'a, Message, Theme, Renderer> {
+        Element::new(fill)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::{Color, Rectangle};
+
+    use super::icon_fill;
+
+    const INSET: Rectangle = Rectangle { x: 5.0, y: 10.4, width: 10.0, height: 6.0 };
+
+    #[test]
+    fn fill_is_proportional_to_value() {
+        for (size, width) in [(16.0, 24.0), (24.0, 40.0), (32.0, 64.0)] {
+            let bounds = Rectangle { x: 3.0, y: 7.0, width, height: size * 1.3 };
+            let full = icon_fill(1.0, INSET, size, Color::WHITE).aliasing(0.2).geometry(bounds);
+
+            for value in [0.0, 0.25, 0.5, 0.8] {
+                let fill =
+                    icon_fill(value, INSET, size, Color::WHITE).aliasing(0.2).geometry(bounds);
+
+                assert!((fill.width - full.width * value).abs() < 1e-4);
+                assert_eq!((fill.x, fill.y, fill.height), (full.x, full.y, full.height));
+            }
+        }
+    }
+
+    #[test]
+    fn fill_scales_with_icon_size() {
+        let bounds = |size: f32| Rectangle { x: 0.0, y: 0.0, width: 40.0, height: size };
+
+        let small = icon_fill(1.0, INSET, 16.0, Color::WHITE).geometry(bounds(16.0));
+        let large = icon_fill(1.0, INSET, 32.0, Color::WHITE).geometry(bounds(32.0));
+
+        assert!((large.width - small.width * 2.0).abs() < 1e-4);
+        assert!(small.x >= 12.0 && small.x + small.width <= 28.0);
+        assert!(large.x >= 4.0 && large.x + large.width <= 36.0);
+    }
+
+    #[test]
+    fn aliasing_shrinks_vertically() {
+        let bounds = Rectangle { x: 0.0, y: 0.0, width: 24.0, height: 24.0 };
+
+        let sharp = icon_fill(1.0, INSET, 24.0, Color::WHITE).geometry(bounds);
+        let aliased = icon_fill(1.0, INSET, 24.0, Color::WHITE).aliasing(0.2).geometry(bounds);
+
+        assert!((sharp.height - aliased.height - 0.4).abs() < 1e-4);
+        assert!((aliased.y - sharp.y - 0.2).abs() < 1e-4);
+        assert_eq!(sharp.width, aliased.width);
+    }
+}
@@ -1,11 +1,15 @@
 use iced::{
-    Color, Font, Radius,
-    widget::{Rule, Space, Text, horizontal_rule, rule, text},
+    Background, Border, Color, Element, Font, Radius,
+    mouse::ScrollDelta,
+    widget::{
+        MouseArea, Space, Text, container, horizontal_rule, mouse_area, rule, text, vertical_rule,
+    },
 };
 use lucide_icons::Icon;
 
-use crate::config::CONFIG;
+use crate::config::{CONFIG, SeparatorStyle};
 
+pub mod fill;
 pub mod outputs;
 pub mod progress;
 pub mod runtime;
@@ -19,21 +23,76 @@ pub const PILL_RADIUS: Radius = Radius {
     bottom_left: f32::MAX,
 };
 
-/// creates a separator for the bar
-pub fn separator<'a>(visible: bool) -> Rule<'a> {
-    horizontal_rule(2)
-        .style(move |_| rule::Style {
-            color: if visible { CONFIG.looks.semi } else { Color::TRANSPARENT },
-            width: 2,
-            fill_mode: rule::FillMode::Full,
-            radius: Radius::new(2),
-        })
-        .width(32)
+/// pixels of a smooth scroll which are worth one line
+const PIXELS_PER_LINE: f32 = 10.0;
+/// smallest and largest number of lines a single scroll event counts as
+const SCROLL_LINES: (f32, f32) = (0.1, 5.0);
+
+/// size of the dot of a dotted separator
+const SEPARATOR_DOT: f32 = 4.0;
+
+/// creates a separator for the bar in the configured style
+pub fn separator<'a, Message: 'a>(visible: bool) -> Element<'a, Message> {
+    let color = if visible { CONFIG.looks.semi } else { Color::TRANSPARENT };
+
+    match CONFIG.looks.separator_style {
+        SeparatorStyle::Line => {
+            let rule = if CONFIG.looks.horizontal() {
+                vertical_rule(2).height(CONFIG.looks.separator_width)
+            } else {
+                horizontal_rule(2).width(CONFIG.looks.separator_width)
+            };
+
+            rule.style(move |_| rule::Style {
+                color,
+                width: 2,
+                fill_mode: rule::FillMode::Full,
+                radius: Radius::new(2),
+            })
+            .into()
+        }
+        SeparatorStyle::Dots => container(Space::new(SEPARATOR_DOT, SEPARATOR_DOT))
+            .style(move |_| container::Style {
+                background: Some(Background::Color(color)),
+                border: Border { radius: PILL_RADIUS, ..Default::default() },
+                ..Default::default()
+            })
+            .into(),
+        // the spacing around the separator is kept, so the groups are further apart
+        SeparatorStyle::Space | SeparatorStyle::None => empty().into(),
+    }
+}
+
+/// computes by how much a scroll event changes a value, where one line (or
+/// its pixels) changes it by `step`. faster scrolls count as more lines with an
+/// acceleration above `1.0`, within the bounds of `SCROLL_LINES`
+pub fn scroll_change(delta: ScrollDelta, step: f32, acceleration: f32) -> f32 {
+    let lines = match delta {
+        ScrollDelta::Lines { y, .. } => y,
+        ScrollDelta::Pixels { y, .. } => -y / PIXELS_PER_LINE, // natural scrolling, fear me
+    };
+
+    if lines == 0.0 {
+        return 0.0;
+    }
+
+    let (min, max) = SCROLL_LINES;
+    lines.signum() * lines.abs().powf(acceleration.max(0.0)).clamp(min, max) * step
 }
 
 /// creates an icon with the lucide icon font
 pub fn icon<'a>(icon: Icon) -> Text<'a> {
-    text(icon.unicode()).font(Font::with_name("lucide")).size(24)
+    text(icon.unicode()).font(Font::with_name("lucide")).size(CONFIG.looks.icon_size)
+}
+
+/// wraps a widget such that hovering it emits messages to show and hide its
+/// tooltip, which is shown in the osd as the bar is too narrow
+pub fn tooltip<'a, Message: Clone + 'a>(
+    content: impl Into<Element<'a, Message>>,
+    show: Message,
+    hide: Message,
+) -> MouseArea<'a, Message> {
+    mouse_area(content).on_enter(show).on_exit(hide)
 }
 
 /// creates an empty widget
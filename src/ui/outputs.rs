@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use iced::Subscription;
 use iced::runtime::platform_specific::wayland::layer_surface::IcedOutput;
 
@@ -5,27 +7,42 @@ use iced::{
     Event as IcedEvent,
     event::{
         PlatformSpecific as PlatformEvent, listen_with,
-        wayland::{Event as WaylandEvent, OutputEvent},
+        wayland::{Event as WaylandEvent, LayerEvent, OutputEvent},
     },
+    window::Id as SurfaceId,
 };
-use log::debug;
+use log::{debug, warn};
+use regex::Regex;
 use wayland_client::protocol::wl_output::WlOutput;
 
 use crate::config::CONFIG;
 
+/// time to wait for the configured output before giving up on it
+pub const OUTPUT_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Clone, Debug)]
 pub enum OutputMessage {
     Change(Output),
     Removed(Output),
+    /// a layer surface was closed by the compositor, e.g. because its output
+    /// went away
+    Closed(SurfaceId),
 }
 
 pub struct OutputHandler {
     outputs: Vec<Output>,
+    fallback: bool, // whether the active output is used if none matches
 }
 
 impl OutputHandler {
     pub fn new() -> Self {
-        Self { outputs: Vec::new() }
+        Self { outputs: Vec::new(), fallback: false }
+    }
+
+    /// uses the active output from now on while no output matches the
+    /// configured one
+    pub fn fall_back(&mut self) {
+        self.fallback = true;
     }
 
     pub fn subscribe(&self) -> Subscription<OutputMessage> {
@@ -51,6 +68,11 @@ impl OutputHandler {
                 OutputEvent::Removed,
                 wloutput,
             ))) => Some(OutputMessage::Removed(Output::empty(wloutput))),
+            IcedEvent::PlatformSpecific(PlatformEvent::Wayland(WaylandEvent::Layer(
+                LayerEvent::Done,
+                _,
+                id,
+            ))) => Some(OutputMessage::Closed(id)),
 
             _ => None,
         })
@@ -66,26 +88,67 @@ impl OutputHandler {
             OutputMessage::Removed(output) => {
                 self.outputs.retain(|o| *o != output);
             }
+            OutputMessage::Closed(_) => {}
         }
     }
 
-    pub fn get_configured(&self) -> Option<IcedOutput> {
+    /// returns all outputs matching the configured one, in the order they
+    /// were mapped
+    pub fn get_configured(&self) -> Vec<IcedOutput> {
         let setting = CONFIG.output.to_lowercase();
 
-        if setting == "active" {
-            Some(IcedOutput::Active)
+        let matches: Box<dyn Fn(&Output) -> bool> = if setting == "active" {
+            return vec![IcedOutput::Active];
+        } else if let Some(pattern) = CONFIG.output.strip_prefix("regex:") {
+            // the pattern is not lowercased, as that would change its meaning
+            let regex = match Regex::new(pattern.trim()) {
+                Ok(regex) => regex,
+                Err(e) => {
+                    warn!("configured output pattern is invalid: {e}");
+                    return vec![];
+                }
+            };
+
+            Box::new(move |out: &Output| {
+                regex.is_match(&out.name) || regex.is_match(&out.description)
+            })
         } else if let Some(desc) = setting.strip_prefix("desc:") {
-            self.outputs
-                .iter()
-                .find(|out| out.description.to_lowercase().starts_with(desc.trim()))
-                .map(|out| IcedOutput::Output(out.wl.clone()))
+            let desc = desc.trim().to_string();
+            Box::new(move |out: &Output| out.description.to_lowercase().starts_with(&desc))
         } else {
-            self.outputs
-                .iter()
-                .find(|out| out.name.to_lowercase() == setting)
-                .map(|out| IcedOutput::Output(out.wl.clone()))
+            Box::new(move |out: &Output| out.name.to_lowercase() == setting)
+        };
+
+        let outputs: Vec<_> = self
+            .outputs
+            .iter()
+            .filter(|out| matches(out))
+            .map(|out| IcedOutput::Output(out.wl.clone()))
+            .collect();
+
+        if outputs.is_empty() && self.fallback { vec![IcedOutput::Active] } else { outputs }
+    }
+
+    /// returns the names and descriptions of all known outputs, for diagnostics
+    pub fn get_available(&self) -> Vec<String> {
+        self.outputs.iter().map(|out| format!("`{}` ({})", out.name, out.description)).collect()
+    }
+
+    /// returns whether two outputs refer to the same one
+    pub fn same(a: &IcedOutput, b: &IcedOutput) -> bool {
+        match (a, b) {
+            (IcedOutput::Output(a), IcedOutput::Output(b)) => a == b,
+            (IcedOutput::Active, IcedOutput::Active) => true,
+            _ => false,
         }
     }
+
+    /// returns the name of a given output, `None` if it is not a specific one
+    pub fn get_name(&self, output: &IcedOutput) -> Option<String> {
+        let IcedOutput::Output(wl) = output else { return None };
+
+        self.outputs.iter().find(|out| out.wl == *wl).map(|out| out.name.clone())
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -10,9 +10,10 @@ use iced::{
 };
 
 /// creates a vertical progress bar, takes a value between 0 and 1
-pub fn vertical_progress(value: f32, height: f32, inner: f32, outer: f32) -> VerticalProgress {
-    VerticalProgress {
+pub fn vertical_progress(value: f32, height: f32, inner: f32, outer: f32) -> Progress {
+    Progress {
         value,
+        horizontal: false,
         height,
         width_inner: inner,
         width_outer: outer,
@@ -21,8 +22,23 @@ pub fn vertical_progress(value: f32, height: f32, inner: f32, outer: f32) -> Ver
     }
 }
 
-pub struct VerticalProgress {
+/// creates a horizontal progress bar, takes a value between 0 and 1
+pub fn horizontal_progress(value: f32, width: f32, inner: f32, outer: f32) -> Progress {
+    Progress { horizontal: true, ..vertical_progress(value, width, inner, outer) }
+}
+
+/// creates a progress bar which follows the orientation of the bar
+pub fn bar_progress(value: f32, length: f32, inner: f32, outer: f32) -> Progress {
+    if CONFIG.looks.horizontal() {
+        horizontal_progress(value, length, inner, outer)
+    } else {
+        vertical_progress(value, length, inner, outer)
+    }
+}
+
+pub struct Progress {
     value: f32,
+    horizontal: bool, // the naming of the fields below assumes a vertical bar
 
     height: f32,
     width_outer: f32,
@@ -32,17 +48,25 @@ pub struct VerticalProgress {
     color_outer: Color,
 }
 
-impl<Message, Theme, Renderer> Widget<Message, Theme, Renderer> for VerticalProgress
+impl<Message, Theme, Renderer> Widget<Message, Theme, Renderer> for Progress
 where
     Message: Clone,
     Renderer: core::Renderer,
 {
     fn size(&self) -> Size<Length> {
-        Size { width: Length::Shrink, height: self.height.into() }
+        if self.horizontal {
+            Size { width: self.height.into(), height: Length::Shrink }
+        } else {
+            Size { width: Length::Shrink, height: self.height.into() }
+        }
     }
 
     fn layout(&self, _tree: &mut Tree, _renderer: &Renderer, limits: &layout::Limits) -> Node {
-        layout::atomic(limits, self.width_outer, self.height)
+        if self.horizontal {
+            layout::atomic(limits, self.height, self.width_outer)
+        } else {
+            layout::atomic(limits, self.width_outer, self.height)
+        }
     }
 
     fn draw(
@@ -58,17 +82,21 @@ where
         let bounds = layout.bounds();
         let rounding = Border { color: Color::TRANSPARENT, width: 0.0, radius: PILL_RADIUS };
 
+        // we lay out the bar vertically and flip it if it is horizontal
+        let bounds = if self.horizontal { transpose(bounds) } else { bounds };
+        let orient = |rect: Rectangle| if self.horizontal { transpose(rect) } else { rect };
+
         let offset = (1.0 - self.value).clamp(0.0, 1.0);
         let x = bounds.x + bounds.width / 2.0;
 
         renderer.fill_quad(
             renderer::Quad {
-                bounds: Rectangle {
+                bounds: orient(Rectangle {
                     x: x - self.width_inner / 2.0,
                     y: bounds.y + (self.width_outer - self.width_inner) / 2.0,
                     width: self.width_inner,
                     height: bounds.height - (self.width_outer - self.width_inner),
-                },
+                }),
                 border: rounding,
                 ..renderer::Quad::default()
             },
@@ -80,14 +108,21 @@ where
             return;
         };
 
+        // horizontal bars fill up from the left instead of the bottom
+        let (start, length) = if self.horizontal {
+            (bounds.y, bounds.height - bounds.height * offset)
+        } else {
+            (bounds.y + bounds.height * offset, bounds.height - bounds.height * offset)
+        };
+
         renderer.fill_quad(
             renderer::Quad {
-                bounds: Rectangle {
+                bounds: orient(Rectangle {
                     x: x - self.width_outer / 2.0,
-                    y: bounds.y + bounds.height * offset,
+                    y: start,
                     width: self.width_outer,
-                    height: bounds.height - bounds.height * offset,
-                },
+                    height: length,
+                }),
                 border: rounding,
                 ..renderer::Quad::default()
             },
@@ -96,12 +131,17 @@ where
     }
 }
 
-impl<'a, Message, Theme, Renderer> From<VerticalProgress> for Element<'a, Message, Theme, Renderer>
+impl<'a, Message, Theme, Renderer> From<Progress> for Element<'a, Message, Theme, Renderer>
 where
     Message: Clone + 'a,
     Renderer: core::Renderer + 'a,
 {
-    fn from(progress: VerticalProgress) -> Element<'a, Message, Theme, Renderer> {
+    fn from(progress: Progress) -> Element<'a, Message, Theme, Renderer> {
         Element::new(progress)
     }
 }
+
+/// swaps the axes of a rectangle
+fn transpose(rect: Rectangle) -> Rectangle {
+    Rectangle { x: rect.y, y: rect.x, width: rect.height, height: rect.width }
+}
@@ -8,9 +8,8 @@ use iced::{
     runtime::{Appearance, DefaultStyle},
 };
 use iced::{Element, Result, Settings, Subscription, Task};
-use iced_winit::commands::subsurface::Layer;
+use iced_winit::commands::subsurface::{Anchor, Layer};
 use serde::Deserialize;
-use serde::Serialize;
 
 use std::marker::PhantomData;
 
@@ -233,22 +232,41 @@ where
     }
 }
 
-#[derive(Clone, Copy, Deserialize, Serialize, Debug, clap::ValueEnum)]
+pub use liischte_lib::ipc::WindowLayer;
+
+/// where the bar is placed along the edge of the screen
+#[derive(Clone, Copy, Deserialize, Debug, Default, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
-pub enum WindowLayer {
-    Overlay,
-    Top,
-    Bottom,
-    Background,
+pub enum BarAnchor {
+    /// span the whole edge
+    #[default]
+    Fill,
+    /// at the top (or left) end of the edge
+    Start,
+    /// in the middle of the edge
+    Center,
+    /// at the bottom (or right) end of the edge
+    End,
 }
 
-impl From<WindowLayer> for Layer {
-    fn from(val: WindowLayer) -> Self {
-        match val {
-            WindowLayer::Overlay => Layer::Overlay,
-            WindowLayer::Top => Layer::Top,
-            WindowLayer::Bottom => Layer::Bottom,
-            WindowLayer::Background => Layer::Background,
+impl BarAnchor {
+    /// returns the anchors along the edge, given the anchors of both ends
+    pub fn anchors(self, start: Anchor, end: Anchor) -> Anchor {
+        match self {
+            BarAnchor::Fill => start | end,
+            BarAnchor::Start => start,
+            BarAnchor::Center => Anchor::empty(),
+            BarAnchor::End => end,
         }
     }
 }
+
+/// converts the configured layer to the one of the layer shell
+pub fn shell_layer(layer: WindowLayer) -> Layer {
+    match layer {
+        WindowLayer::Overlay => Layer::Overlay,
+        WindowLayer::Top => Layer::Top,
+        WindowLayer::Bottom => Layer::Bottom,
+        WindowLayer::Background => Layer::Background,
+    }
+}